@@ -0,0 +1,57 @@
+use image::Rgba;
+use matrix_graph::{
+    agents::{
+        AntColony,
+        Physarum,
+        Turmite,
+        ant_colony::Nest,
+        physarum,
+        turmite::Colony,
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+        Rule,
+    },
+    error,
+};
+
+/// Langton's ant and a Fibonacci turmite, a Physarum network and a foraging ant colony.
+fn main() -> Result<(), error::MatrixError> {
+    let mut ant = Turmite::langtons_ant();
+    let mut colony = Turmite::initial_state(100, 100, &[(50, 50)]);
+    for _ in 0..12000 {
+        colony = ant.step(&colony)?;
+    }
+    ant.draw(&colony)?.save("langtons_ant.png")?;
+    let spiral = Turmite::fibonacci_spiral();
+    let painter = spiral.clone();
+    let mut frames = PngDirectory::new("./animation", |colony: &Colony| painter.draw(colony)).with_prefix("turmite_");
+    Simulation::new(Turmite::initial_state(100, 100, &[(50, 50)]), spiral)
+        .with_steps(3000)
+        .with_frame_interval(100)
+        .with_sink(&mut frames)
+        .run()?;
+
+    let mut mold_rule = Physarum::new().with_seed(7);
+    let mut mold = mold_rule.initial_state(200, 200, 8000);
+    for _ in 0..400 {
+        mold = mold_rule.step(&mold)?;
+    }
+    println!("physarum: {:.2} of the trail on a fifth of the cells", physarum::trail_concentration(&mold, 0.2));
+    mold_rule.clone().with_agent_color(Some(Rgba([255, 255, 255, 255]))).draw(&mold, 40.0)?.save("physarum.png")?;
+
+    let mut ants = AntColony::new((40.0, 100.0), 4.0).with_seed(7);
+    let mut nest: Nest = ants.initial_state(200, 200, 400)?;
+    AntColony::add_food(&mut nest, (150, 60), 8, 10.0)?;
+    AntColony::add_food(&mut nest, (140, 160), 6, 10.0)?;
+    for step in 1..=4000 {
+        nest = ants.step(&nest)?;
+        if step % 1000 == 0 {
+            println!("step {step}: {:.1} units of food delivered", ants.get_delivered());
+        }
+    }
+    ants.draw(&nest, 5.0)?.save("ant_colony.png")?;
+
+    Ok(())
+}
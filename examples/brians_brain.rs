@@ -0,0 +1,35 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    automata::{
+        Generations,
+        generations::{
+            ALIVE,
+            DEAD,
+        },
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    random::RngCore,
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 100;
+    let (size_x, size_y): (usize, usize) = (100,100);
+    let rule: Generations = "/2/3".parse()?;
+    let matrix: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_height_and_width(size_x,size_y)
+        .with_seeded_generator(7, |rng| if rng.next_u32() as u8 > 200 { ALIVE } else { DEAD })
+        .build();
+
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<u8>| rule.draw(matrix));
+    Simulation::new(matrix, rule.clone())
+        .with_steps(n_sequence)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
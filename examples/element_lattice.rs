@@ -7,21 +7,24 @@ use matrix_graph::{
         Draw,
         LatticeElement,
     },
+    random::{
+        self,
+        Philox,
+    },
     error,
 };
-use rand::Rng;
 
 type Point = (u32, u32);
 type MatrixSize = (usize, usize);
 
 fn main() -> Result<(), error::MatrixError> {
-    let mut rng = rand::thread_rng();
+    let mut rng = Philox::new(7);
     let (size_x, size_y): MatrixSize = (100,100);
     let mut matrix = MatrixImageBuilder::<LatticeElement<f32>>::init().with_height_and_width(size_x,size_y).build();
     
     for point_x in 0..(size_x as u32) {
         for point_y in 0..(size_y as u32) {
-            let value = rng.gen_range(0_f32..(f32::MAX));
+            let value = random::unit_f32(&mut rng) * f32::MAX;
             let _ = matrix.edit_point((point_x, point_y), value);
         }
     }
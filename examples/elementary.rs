@@ -0,0 +1,34 @@
+use matrix_graph::{
+    Channel,
+    automata::{
+        OneDimensional,
+        one_dimensional::Boundary,
+    },
+    traits::Draw,
+    random::{
+        Philox,
+        RngCore,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let (width, generations) = (401, 200);
+    // Rule 30 from a single cell, drawn with its palette.
+    let rule_30 = OneDimensional::elementary(30);
+    let diagram = rule_30.space_time(&rule_30.single_cell_seed(width), generations)?;
+    rule_30.draw(&diagram)?.save("rule_30.png")?;
+
+    // Rule 110 from a random row between fixed dead edges, drawn with `Draw` on the green channel.
+    let rule_110 = OneDimensional::elementary(110).with_boundary(Boundary::Fixed(0));
+    let mut rng = Philox::new(7);
+    let diagram = rule_110.space_time(&rule_110.random_seed(width, || rng.next_u32() as u8), generations)?;
+    rule_110.to_intensity(&diagram)?.draw(Channel::Green)?.save("rule_110.png")?;
+
+    // Three-color totalistic rule 1599.
+    let totalistic = OneDimensional::totalistic_code(3, 1, 1599)?;
+    let diagram = totalistic.space_time(&totalistic.single_cell_seed(width), generations)?;
+    totalistic.draw(&diagram)?.save("totalistic_1599.png")?;
+
+    Ok(())
+}
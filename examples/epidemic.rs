@@ -0,0 +1,47 @@
+use matrix_graph::{
+    MatrixImage,
+    Neighborhood,
+    automata::{
+        Epidemic,
+        ForestFire,
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+        Rule,
+    },
+    error,
+};
+
+/// A SEIR outbreak spreading from the center of the lattice, then a Drossel-Schwabl forest.
+fn main() -> Result<(), error::MatrixError> {
+    let (size_x, size_y) = (200, 200);
+    let mut seir = Epidemic::seir(0.12, 0.3, 0.1)
+        .with_neighborhood(Neighborhood::Moore, 2)
+        .with_immunity_loss(0.002)
+        .with_seed(7);
+    let painter = seir.clone();
+    let matrix = Epidemic::initial_state(size_x, size_y, &[(100, 100), (101, 100), (100, 101)])?;
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<u8>| painter.draw(matrix));
+    Simulation::new(matrix, |matrix: &MatrixImage<u8>| seir.step(matrix))
+        .with_steps(300)
+        .with_frame_interval(5)
+        .with_sink(&mut frames)
+        .run()?;
+    println!("step\tS\tE\tI\tR");
+    for (step, compartments) in seir.get_series_ref().iter().enumerate().step_by(20) {
+        println!("{step}\t{}\t{}\t{}\t{}", compartments.susceptible, compartments.exposed, compartments.infected, compartments.recovered);
+    }
+
+    let mut forest = ForestFire::new(0.02, 0.00002).with_seed(7);
+    let mut matrix = ForestFire::initial_state(size_x, size_y);
+    for _ in 0..2000 {
+        matrix = forest.step(&matrix)?;
+    }
+    let series = &forest.get_series_ref()[1000..];
+    let density = series.iter().map(|census| census.trees).sum::<usize>() as f64 / (series.len() * size_x * size_y) as f64;
+    println!("forest density {density:.3}");
+    forest.draw(&matrix)?.save("forest_fire.png")?;
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Channel::*,
+    SourceChannel,
+    Neighborhood,
+    traits::{
+        Matrix,
+        Draw,
+        LatticeElement,
+    },
+    error,
+};
+
+type Atom = LatticeElement<f32>;
+
+/// Seeds a lattice from a previously saved frame, normalized to [0, 1], and diffuses it.
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 10;
+    let frame = image::open("matrix.png")?;
+    let mut matrix: MatrixImage<Atom> = MatrixImage::from_image_with(&frame, SourceChannel::Luma, |value| {
+        LatticeElement(value as f32 / u8::MAX as f32)
+    });
+    let max_builder: MatrixImageBuilder<Atom> = MatrixImageBuilder::init()
+        .with_initial_value(LatticeElement(f32::MAX))
+        .with_height_and_width(matrix.get_height(), matrix.get_width());
+
+    for id in 0..n_sequence {
+        let prepend = "./animation/matrix_".to_owned();
+        (max_builder.build() * matrix.clone())
+            .draw(Blue)?
+            .save(prepend+&id.to_string()+".png")?;
+        matrix = average(&matrix)?;
+    }
+
+    Ok(())
+}
+
+fn average(matrix: &MatrixImage<Atom>) -> Result<MatrixImage<Atom>, error::MatrixError> {
+    let mut new_matrix = matrix.clone();
+    for point_y in 0..matrix.get_height() as u32 {
+        for point_x in 0..matrix.get_width() as u32 {
+            let center = (point_x, point_y);
+            let (sum, length) = matrix.hood_sum(center, 1, Neighborhood::VonNeumann)?;
+            new_matrix.edit_point(center, sum / LatticeElement(length as f32))?;
+        }
+    }
+    Ok(new_matrix)
+}
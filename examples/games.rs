@@ -0,0 +1,58 @@
+use matrix_graph::{
+    models::{
+        Game,
+        games::{
+            COOPERATE,
+            DEFECT,
+        },
+    },
+    simulation::Rule,
+};
+
+/// Nowak and May's kaleidoscope grown from a single defector, and the cooperation of the prisoner's
+/// dilemma and snowdrift games with Fermi noise, with rock-paper-scissors strategy maps.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut kaleidoscope = Game::prisoners_dilemma(1.9);
+    let mut strategies = Game::uniform_state(99, 99, COOPERATE, &[((49, 49), DEFECT)])?;
+    for step in 1..=60 {
+        let next = kaleidoscope.step(&strategies)?;
+        if step % 20 == 0 {
+            Game::draw_changes(&strategies, &next)?.save(format!("kaleidoscope_{step}.png"))?;
+        }
+        strategies = next;
+    }
+    report("kaleidoscope", &kaleidoscope);
+
+    for (name, game) in [
+        ("prisoners_dilemma_1.05", Game::prisoners_dilemma(1.05)),
+        ("prisoners_dilemma_1.2", Game::prisoners_dilemma(1.2)),
+        ("snowdrift_0.3", Game::snowdrift(0.3)),
+        ("snowdrift_0.7", Game::snowdrift(0.7)),
+    ] {
+        let mut game = game.with_noise(0.1).with_seed(50);
+        let mut strategies = game.initial_state(100, 100, &[1.0, 1.0])?;
+        for _ in 0..200 {
+            strategies = game.step(&strategies)?;
+        }
+        report(name, &game);
+        game.draw(&strategies)?.save(format!("{name}.png"))?;
+    }
+
+    let mut cyclic = Game::rock_paper_scissors().with_noise(0.1).with_seed(50);
+    let mut strategies = cyclic.initial_state(150, 150, &[1.0, 1.0, 1.0])?;
+    for _ in 0..150 {
+        strategies = cyclic.step(&strategies)?;
+    }
+    cyclic.draw(&strategies)?.save("rock_paper_scissors.png")?;
+    if let Some(fractions) = cyclic.get_series_ref().last() {
+        println!("rock_paper_scissors: {fractions:.3?}");
+    }
+
+    Ok(())
+}
+
+fn report(name: &str, game: &Game) {
+    let cooperation = game.cooperation();
+    let samples: Vec<String> = cooperation.iter().step_by(cooperation.len() / 5).map(|fraction| format!("{fraction:.3}")).collect();
+    println!("{name}: cooperation {}", samples.join(", "));
+}
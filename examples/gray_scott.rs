@@ -0,0 +1,42 @@
+use matrix_graph::{
+    models::{
+        GrayScott,
+        gray_scott::Concentrations,
+    },
+    random::{
+        self,
+        Philox,
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 10000;
+    let n_step = 100;
+    let (size_x, size_y) = (100,100);
+    let seed = 7;
+    // One of mitosis, coral, spots, stripes or worms, e.g. `cargo run --example gray_scott -- worms`.
+    let model = match std::env::args().nth(1).as_deref() {
+        Some("mitosis") => GrayScott::mitosis(),
+        Some("spots") => GrayScott::spots(),
+        Some("stripes") => GrayScott::stripes(),
+        Some("worms") => GrayScott::worms(),
+        _ => GrayScott::coral(),
+    };
+    let mut state = GrayScott::initial_state(size_x, size_y)?;
+    let mut rng = Philox::new(seed);
+    GrayScott::seed_squares(&mut state, 5, 4, || random::unit_f32(&mut rng))?;
+
+    let mut frames = PngDirectory::new("./animation", |state: &Concentrations| GrayScott::draw(state));
+    Simulation::new(state, model)
+        .with_steps(n_sequence)
+        .with_frame_interval(n_step)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use image::Rgba;
+use matrix_graph::{
+    models::{
+        Dla,
+        Eden,
+        growth,
+    },
+    palette::Palette,
+    error,
+};
+
+/// Clusters of diffusion-limited aggregation, with and without noise reduction or anisotropy, and of
+/// Eden growth, colored by attachment order, with their box-counting dimensions.
+fn main() -> Result<(), error::MatrixError> {
+    let palette = Palette::gradient(Rgba([255, 240, 120, 255]), Rgba([120, 20, 160, 255]), 256);
+    for (name, mut dla) in [("dla", Dla::new(1.0)), ("dla_sticky_0.1", Dla::new(0.1)), ("dla_noise_reduced", Dla::new(1.0).with_noise_reduction(8)), ("dla_anisotropic", Dla::new(1.0).with_anisotropy(1.0, 0.2))] {
+        dla = dla.with_seed(7);
+        let mut order = growth::seeded(301, 301)?;
+        let sites = dla.grow(&mut order, 6000)?;
+        report(name, sites, &growth::box_counts(&order, |order| *order > 0));
+        growth::draw(&order, &palette)?.save(format!("{name}.png"))?;
+    }
+    let mut eden = Eden::new().with_seed(7);
+    let mut order = growth::seeded(301, 301)?;
+    let sites = eden.grow(&mut order, 20000)?;
+    report("eden", sites, &growth::box_counts(&order, |order| *order > 0));
+    growth::draw(&order, &palette)?.save("eden.png")?;
+
+    Ok(())
+}
+
+fn report(name: &str, sites: usize, counts: &[(usize, usize)]) {
+    match growth::fractal_dimension(&counts[1..6]) {
+        Some(dimension) => println!("{name}: {sites} sites, box-counting dimension {dimension:.3}"),
+        None => println!("{name}: {sites} sites, too few occupied boxes for a box-counting dimension"),
+    }
+}
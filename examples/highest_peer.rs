@@ -7,13 +7,16 @@ use matrix_graph::{
         LatticeElement,
         Optimal,
     },
+    random::{
+        Philox,
+        RngCore,
+    },
     error,
 };
-use rand::Rng;
 
 fn main() -> Result<(), error::MatrixError> {
     let size = (5,5);
-    let mut rng = rand::thread_rng();
+    let mut rng = Philox::new(7);
     let mut matrix: MatrixImage<u32> = MatrixImageBuilder::init()
         .with_height_and_width(size.0, size.1)
         .build();
@@ -24,14 +27,14 @@ fn main() -> Result<(), error::MatrixError> {
         
     for x in 0..size.0 as u32 {
         for y in 0..size.1 as u32 {
-            matrix.edit_point((x,y), rng.gen::<u32>())?;
+            matrix.edit_point((x,y), rng.next_u32())?;
         }
     }
         
         
     for x in 0..size.0 as u32 {
         for y in 0..size.1 as u32 {
-            let optimal = matrix.optimal_peer((x,y), 1, Neighborhood::VonNeumann, objective);
+            let optimal = matrix.optimal_peer((x,y), 1, Neighborhood::VonNeumann, objective)?;
             println!("optimal for point {:?} : {:?}", (x,y), optimal);
         }
     }
@@ -8,19 +8,22 @@ use matrix_graph::{
         Draw,
         LatticeElement,
     },
+    random::{
+        Philox,
+        RngCore,
+    },
     error,
 };
-use rand::Rng;
 
 fn main() -> Result<(), error::MatrixError> {
-    let mut rng = rand::thread_rng();
+    let mut rng = Philox::new(7);
     let (size_x, size_y) = (100,100);
     let mut matrix: MatrixImage<LatticeElement<u32>> = MatrixImageBuilder::init().with_height_and_width(size_x,size_y).build();
     let mut recipient_matrix = matrix.clone();
     
     for point_x in 0..size_x {
         for point_y in 0..size_y {
-            let value = LatticeElement(rng.gen_range(0_u32..u32::MAX));
+            let value = LatticeElement(rng.next_u32());
             let edit_point = (point_x as u32, point_y as u32);
             let _ = matrix.edit_point(edit_point, value);
         }
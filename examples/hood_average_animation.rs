@@ -8,19 +8,26 @@ use matrix_graph::{
         Draw,
         LatticeElement,
     },
+    random::{
+        Philox,
+        RngCore,
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
     error,
 };
-use rand::Rng;
 
 fn main() -> Result<(), error::MatrixError> {
-    let mut rng = rand::thread_rng();
+    let mut rng = Philox::new(7);
     let n_sequence = 10;
     let (size_x, size_y) = (100,100);
     let mut matrix: MatrixImage<LatticeElement<u32>> = MatrixImageBuilder::init().with_height_and_width(size_x,size_y).build();
     
     for point_x in 0..size_x {
         for point_y in 0..size_y {
-            let value: LatticeElement<u32> = LatticeElement(rng.gen_range(0_u32..u32::MAX));
+            let value: LatticeElement<u32> = LatticeElement(rng.next_u32());
             let edit_point = (point_x as u32, point_y as u32);
             let _ = matrix.edit_point(edit_point, value.clone());
             #[cfg(debug_assertions)]
@@ -28,15 +35,11 @@ fn main() -> Result<(), error::MatrixError> {
         }
     }
     
-    for id in 0..n_sequence {
-        matrix = still_image(matrix)?;
-        
-        let prepend = "./animation/matrix_".to_owned();
-    
-        let _image = matrix
-            .draw(Green)?
-            .save(prepend+&id.to_string()+".png")?;
-    }
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<LatticeElement<u32>>| matrix.draw(Green));
+    Simulation::new(matrix, |matrix: &MatrixImage<LatticeElement<u32>>| still_image(matrix.clone()))
+        .with_steps(n_sequence)
+        .with_sink(&mut frames)
+        .run()?;
     
     Ok(())
 }
@@ -0,0 +1,35 @@
+use matrix_graph::{
+    integrators::{
+        Integrator,
+        Method,
+    },
+    models::{
+        GrayScott,
+        gray_scott::Concentrations,
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 2000;
+    let n_step = 20;
+    let (size_x, size_y) = (100,100);
+    let model = GrayScott::mitosis();
+    let mut state = GrayScott::initial_state(size_x, size_y)?;
+    GrayScott::seed_square(&mut state, (50, 50), 8)?;
+
+    // Classic Runge-Kutta with a larger time step than of the model's forward Euler.
+    let integrator = Integrator::new(Method::RungeKutta4, 1.5, |state: &Concentrations| model.derivative(state))?;
+    let mut frames = PngDirectory::new("./animation", |state: &Concentrations| GrayScott::draw(state));
+    Simulation::new(state, integrator)
+        .with_steps(n_sequence)
+        .with_frame_interval(n_step)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use matrix_graph::{
+    models::{
+        SpinModel,
+        spin::{
+            Dynamics,
+            Spins,
+            Update,
+        },
+    },
+    random::{
+        self,
+        Philox,
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let (size_x, size_y) = (32, 32);
+    let sites = size_x * size_y;
+    let mut rng = Philox::new(7);
+
+    // Magnetization, energy and susceptibility across the critical temperature, from Wolff updates.
+    let critical = SpinModel::ising(1.0).critical_temperature();
+    println!("T\t|m|\te\tchi");
+    for step in 0..=10 {
+        let model = SpinModel::ising(critical * (0.7 + 0.06 * step as f64));
+        let mut spins = model.ordered_state(size_x, size_y);
+        let series: Vec<_> = (0..4000)
+            .map(|_| model.sweep(&mut spins, Update::Wolff, || random::unit_f64(&mut rng)))
+            .collect::<Result<_, _>>()?;
+        let series = &series[1000..];
+        let magnetization = series.iter().map(|observables| observables.magnetization.abs()).sum::<f64>() / series.len() as f64;
+        let energy = series.iter().map(|observables| observables.energy).sum::<f64>() / series.len() as f64;
+        println!("{:.3}\t{magnetization:.3}\t{energy:.3}\t{:.2}", model.get_temperature(), model.susceptibility(series, sites));
+    }
+
+    // Coarsening of the domains of a 5-state Potts model quenched below its critical temperature.
+    let potts = SpinModel::potts(5, 0.5 * SpinModel::potts(5, 1.0).critical_temperature());
+    let spins = potts.random_state(200, 200, || random::unit_f64(&mut rng));
+    let mut frames = PngDirectory::new("./animation", |spins: &Spins| potts.draw(spins));
+    Simulation::new(spins, Dynamics::new(potts.clone(), Update::Metropolis, random::generator(Philox::new(8))))
+        .with_steps(200)
+        .with_frame_interval(10)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
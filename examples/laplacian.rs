@@ -1,11 +1,19 @@
+use image::Rgba;
 use matrix_graph::{
     MatrixImage,
     MatrixImageBuilder,
-    Channel::*,
     Neighborhood,
+    models::{
+        Heat,
+        heat::Scheme,
+    },
+    palette::Palette,
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
     traits::{
         Matrix,
-        Draw,
         LatticeElement,
     },
     error,
@@ -14,43 +22,34 @@ use matrix_graph::{
 fn main() -> Result<(), error::MatrixError> {
     let n_sequence = 100;
     let (size_x, size_y) = (100,100);
-    let mut matrix: MatrixImage<LatticeElement<u32>> = MatrixImageBuilder::init()
-        .with_initial_value(LatticeElement::from(u32::MAX - 1_u32))
+    let (diffusion, time_step, grid_spacing) = (1.0, 0.5, 1.0);
+    let mut matrix: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init()
+        .with_initial_value(LatticeElement(0.0))
         .with_height_and_width(size_x,size_y)
         .build();
-    
+
     let center: (u32,u32) = (50,50);
     let neighborhood = matrix.get_lattice_neighborhood(center, 10, Neighborhood::Moore);
-    
     for point in &neighborhood {
-        let _ = matrix.edit_point(*point, u32::MAX/16_u32);
+        matrix.edit_point(*point, 1.0)?;
     }
-    
-    for id in 0..n_sequence {
-        
-        let prepend = "./animation/matrix_".to_owned();
-    
-        let _image = matrix
-            .draw(Blue)?
-            .save(prepend+&id.to_string()+".png")?;
-        
-        matrix = reaction_diffusion(matrix.clone())?;
+
+    // The explicit scheme refuses time steps beyond its stability limit, Crank-Nicolson takes any.
+    let mut heat = Heat::new(diffusion, time_step, grid_spacing);
+    if !heat.is_stable() {
+        println!(
+            "Time step {time_step} exceeds the explicit limit of {}, switching to Crank-Nicolson.",
+            heat.max_stable_time_step(),
+        );
+        heat = heat.with_scheme(Scheme::CrankNicolson);
     }
-    
+
+    let palette = Palette::gradient(Rgba([0, 0, 0, 255]), Rgba([0, 0, 255, 255]), 256);
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<LatticeElement<f32>>| palette.draw_range(matrix, 0.0, 1.0));
+    Simulation::new(matrix, heat)
+        .with_steps(n_sequence)
+        .with_sink(&mut frames)
+        .run()?;
+
     Ok(())
 }
-
-// Laplace operator.
-fn reaction_diffusion(matrix: MatrixImage<LatticeElement<u32>>) -> Result<MatrixImage<LatticeElement<u32>>, error::MatrixError> {
-    let size_x = matrix.get_height();
-    let size_y = matrix.get_width();
-    let mut new_matrix = matrix.clone();
-    for point_x in 0..size_x {
-        for point_y in 0..size_y {
-            let center = (point_x as u32,point_y as u32);
-            let new_value = matrix.laplace_operator(center, 1, Neighborhood::VonNeumann)?;
-            let _ = new_matrix.edit_point(center, new_value)?;
-        }
-    }
-    Ok(new_matrix)
-}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    automata::{
+        LargerThanLife,
+        larger_than_life::{
+            ALIVE,
+            DEAD,
+        },
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    random::RngCore,
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 100;
+    let (size_x, size_y): (usize, usize) = (200,200);
+    // Any R,C,M,S,B,N rulestring, e.g. "R7,C0,M1,S100..200,B75..170,NM" for Waffle.
+    let rule: LargerThanLife = "R5,C0,M1,S34..58,B34..45,NM".parse()?;
+    let matrix: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_height_and_width(size_x,size_y)
+        .with_seeded_generator(7, |rng| if rng.next_u32() as u8 > 128 { ALIVE } else { DEAD })
+        .build();
+
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<u8>| rule.draw(matrix));
+    Simulation::new(matrix, rule.clone())
+        .with_steps(n_sequence)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use image::Rgba;
+use matrix_graph::{
+    models::{
+        LatticeBoltzmann,
+        lattice_boltzmann::{
+            self,
+            Distributions,
+        },
+    },
+    palette::Palette,
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 20000;
+    let (size_x, size_y) = (400, 100);
+    let inflow = 0.1;
+
+    // Reynolds number inflow · diameter / viscosity = 0.1 · 20 / 0.02 = 100, past the onset of the
+    // von Kármán vortex street. The cylinder sits slightly off center to break the symmetry.
+    let cylinder = lattice_boltzmann::disc(size_x, size_y, (80.0, 52.0), 10.0)?;
+    let fluid = LatticeBoltzmann::new(0.02)
+        .with_inflow((inflow, 0.0))
+        .with_obstacles(cylinder);
+    let state = LatticeBoltzmann::initial_state(size_x, size_y, 1.0, (inflow, 0.0))?;
+
+    let palette = Palette::diverging(Rgba([0, 64, 255, 255]), Rgba([255, 255, 255, 255]), Rgba([255, 32, 0, 255]), 255);
+    let mut frames = PngDirectory::new("./animation", |state: &Distributions| fluid.draw_vorticity(state, &palette, 0.02));
+    Simulation::new(state, fluid.clone())
+        .with_steps(n_sequence)
+        .with_frame_interval(200)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
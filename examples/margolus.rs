@@ -0,0 +1,55 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    automata::Margolus,
+    random,
+    simulation::{
+        Simulation,
+        PngDirectory,
+        Rule,
+    },
+    traits::Matrix,
+    error,
+};
+
+/// Critters from a random square run forward and back to where it started, then sand falling
+/// through the block partition.
+fn main() -> Result<(), error::MatrixError> {
+    let (size_x, size_y) = (128, 128);
+    let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_initial_value(0)
+        .with_height_and_width(size_y, size_x)
+        .build();
+    let square: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_height_and_width(32, 32)
+        .with_seeded_generator(7, |rng| (random::unit_f64(rng) < 0.5) as u8)
+        .build();
+    for (index, cell) in square.get_data_ref().iter().enumerate() {
+        matrix.edit_point(((48 + index % 32) as u32, (48 + index / 32) as u32), *cell)?;
+    }
+    let initial = matrix.clone();
+    let mut critters = Margolus::critters();
+    for _ in 0..400 {
+        matrix = critters.step(&matrix)?;
+    }
+    critters.draw(&matrix)?.save("critters.png")?;
+    for _ in 0..400 {
+        matrix = critters.step_back(&matrix)?;
+    }
+    println!("{critters} stepped back to its initial lattice: {}", matrix == initial);
+
+    let sand = Margolus::sand();
+    let painter = sand.clone();
+    let mut grains = matrix;
+    for (index, cell) in grains.get_data_mut_ref().iter_mut().enumerate() {
+        *cell = (index / size_x < size_y / 2 && index % size_x > size_x / 4 && index % size_x < size_x / 2) as u8;
+    }
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<u8>| painter.draw(matrix));
+    Simulation::new(grains, sand)
+        .with_steps(60)
+        .with_frame_interval(2)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
@@ -8,18 +8,21 @@ use matrix_graph::{
         DrawMultiChannel,
     },
     four_channel::FourChannelMatrix,
+    random::{
+        Philox,
+        RngCore,
+    },
     error,
 };
-use rand::Rng;
 
 fn main() -> Result<(), error::MatrixError> {
-    let mut rng = rand::thread_rng();
+    let mut rng = Philox::new(7);
     let (size_x, size_y): (usize, usize) = (100,100);
     let mut matrix = MatrixImageBuilder::<u8>::init().with_height_and_width(size_x,size_y).build();
     
     for point_x in 0..size_x {
         for point_y in 0..size_y {
-            let value: u8 = rng.gen();
+            let value = rng.next_u32() as u8;
             let _ = matrix.edit_point((point_x as u32, point_y as u32), value);
         }
     }
@@ -33,7 +36,7 @@ fn main() -> Result<(), error::MatrixError> {
     
     let _ = matrix.edit_point(center, 0);
     
-    let mut multi_channel: FourChannelMatrix<u8> = FourChannelMatrix::from([matrix.clone(), matrix.clone(), matrix.clone(), matrix.clone()]);
+    let mut multi_channel: FourChannelMatrix<u8> = FourChannelMatrix::try_from([matrix.clone(), matrix.clone(), matrix.clone(), matrix.clone()])?;
     
     let _image = multi_channel
         .multi_channel_image(Some(&[Red, Blue, Green, Alpha]))?
@@ -11,9 +11,12 @@ use matrix_graph::{
         Max,
     },
     four_channel::FourChannelMatrix,
+    random::{
+        Philox,
+        RngCore,
+    },
     error,
 };
-use rand::Rng;
 
 fn main() -> Result<(), error::MatrixError> {
     let (size_x, size_y): (usize, usize) = (100,100);
@@ -24,7 +27,7 @@ fn main() -> Result<(), error::MatrixError> {
         matrix_builder.build(), 
         matrix_builder.build()
     ];
-    let mut multi_channel: FourChannelMatrix<u8> = FourChannelMatrix::from(initial_channels);
+    let mut multi_channel: FourChannelMatrix<u8> = FourChannelMatrix::try_from(initial_channels)?;
     
     for i in 0..100 {
         multi_channel = multi_channel.update_rule(update_rule);
@@ -40,11 +43,11 @@ fn update_rule<T: Clone + Default + Mul<Output=T> + Max + From<u8>>(multi_matrix
  where u8: From<T>
 {
     let mut mm = multi_matrix.clone();
-    let mut rng = rand::thread_rng();
+    let mut rng = Philox::new(7);
     for point_x in 0..*mm.get_width() as usize {
         for point_y in 0..*mm.get_height() as usize {
             for mut matrix in mm.get_data_mut_ref() {
-               let value: u8 = rng.gen();
+               let value = rng.next_u32() as u8;
                 let _ = matrix.edit_point((point_x as u32, point_y as u32), value);             
             }
         }
@@ -0,0 +1,48 @@
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use matrix_graph::{
+    clusters::{
+        Percolation,
+        SweepPoint,
+    },
+    traits::Matrix,
+    error,
+};
+
+/// Site and bond percolation sweeps on the square lattice across their thresholds, near 0.5927 for
+/// sites and exactly 0.5 for bonds, and a site lattice at the threshold with its largest cluster
+/// in red and the clusters spanning it in orange.
+fn main() -> Result<(), error::MatrixError> {
+    let percolation = Percolation::new(128, 128).with_trials(50).with_seed(49);
+    let sites: Vec<f64> = (0..=10).map(|index| 0.54 + 0.01 * index as f64).collect();
+    report("site", &percolation.site_sweep(&sites)?);
+    let bonds: Vec<f64> = (0..=10).map(|index| 0.45 + 0.01 * index as f64).collect();
+    report("bond", &percolation.bond_sweep(&bonds)?);
+
+    let clusters = percolation.site_clusters(0.5927, 0)?;
+    let largest = clusters.largest().map_or(0, |(label, _)| label);
+    let spanning = clusters.spanning();
+    let labels = clusters.get_labels_ref();
+    let mut image = RgbaImage::new(labels.get_width().try_into()?, labels.get_height().try_into()?);
+    for (pixel, label) in image.pixels_mut().zip(labels.get_data_ref()) {
+        *pixel = match *label {
+            0 => Rgba([255, 255, 255, 255]),
+            label if label == largest => Rgba([200, 30, 30, 255]),
+            label if spanning.contains(&label) => Rgba([240, 150, 30, 255]),
+            _ => Rgba([90, 90, 140, 255]),
+        };
+    }
+    image.save("percolation.png")?;
+
+    Ok(())
+}
+
+fn report(name: &str, points: &[SweepPoint]) {
+    println!("{name} percolation");
+    println!("p,spanning,largest,clusters");
+    for point in points {
+        println!("{:.3},{:.3},{:.4},{:.1}", point.probability, point.spanning, point.largest, point.clusters);
+    }
+}
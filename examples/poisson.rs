@@ -0,0 +1,54 @@
+use image::Rgba;
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    models::{
+        Poisson,
+        poisson::Method,
+    },
+    palette::Palette,
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let (size_x, size_y) = (128, 128);
+    let sources = [((32_u32, 40_u32), 1.0), ((96, 90), 1.0), ((70, 20), 0.0), ((20, 110), 0.0)];
+    let mut potential: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init()
+        .with_initial_value(LatticeElement(0.5))
+        .with_height_and_width(size_y, size_x)
+        .build();
+    let mut mask: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_initial_value(0)
+        .with_height_and_width(size_y, size_x)
+        .build();
+    for (point, value) in sources {
+        potential.edit_point(point, value)?;
+        mask.edit_point(point, 1)?;
+    }
+    let source: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init()
+        .with_initial_value(LatticeElement(0.0))
+        .with_height_and_width(size_y, size_x)
+        .build();
+
+    // Laplace's equation between attracting and repelling points, whose gradient agents can climb.
+    for method in [Method::Jacobi, Method::RedBlackGaussSeidel, Method::optimal_sor(size_x, size_y), Method::Multigrid] {
+        let solver = Poisson::new(method).with_fixed(mask.clone()).with_tolerance(1e-6, 100000);
+        match solver.solve(&source, &potential) {
+            Ok((_, residuals)) => println!("{method:?} converged after {} iterations", residuals.len()),
+            Err(error) => println!("{method:?} failed: {error}"),
+        }
+    }
+
+    let (solution, residuals) = Poisson::new(Method::Multigrid).with_fixed(mask).solve(&source, &potential)?;
+    for (cycle, residual) in residuals.iter().enumerate() {
+        println!("V-cycle {cycle}: residual {residual:e}");
+    }
+    let palette = Palette::gradient(Rgba([0, 0, 64, 255]), Rgba([255, 224, 0, 255]), 256);
+    palette.draw_range(&solution, 0.0, 1.0)?.save("potential.png")?;
+
+    Ok(())
+}
@@ -6,13 +6,13 @@ use matrix_graph::{
         Matrix,
         Draw,
     },
+    random::RngCore,
     error,
 };
-use rand::Rng;
 
 fn main() -> Result<(), error::MatrixError> {
     let (size_x, size_y): (usize, usize) = (100,100);
-    let mut matrix = MatrixImageBuilder::<u8>::init().with_height_and_width(size_x,size_y).with_generator(|| { rand::thread_rng().gen::<u8>() }).build();
+    let mut matrix = MatrixImageBuilder::<u8>::init().with_height_and_width(size_x,size_y).with_seeded_generator(7, |rng| rng.next_u32() as u8).build();
     
     let center: (u32, u32) = (50,50);
     let neighborhood = matrix.get_lattice_neighborhood(center, 3, Neighborhood::VonNeumann);
@@ -0,0 +1,59 @@
+use image::Rgba;
+use matrix_graph::{
+    models::{
+        ReactionDiffusion,
+        reaction_diffusion,
+    },
+    n_channel::NChannelMatrix,
+    palette::Palette,
+    random::{
+        self,
+        Philox,
+    },
+    simulation::Simulation,
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+    error,
+};
+type Atom = LatticeElement<f32>;
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 20000;
+    let (size_x, size_y) = (64,64);
+    // One of brusselator, fitzhugh_nagumo, schnakenberg or gierer_meinhardt.
+    let name = std::env::args().nth(1).unwrap_or("brusselator".to_owned());
+    match name.as_str() {
+        "fitzhugh_nagumo" => run(reaction_diffusion::fitzhugh_nagumo(1.0, 0.0, 2.0), [0.0, 0.0], (size_x, size_y), n_sequence, &name),
+        "schnakenberg" => run(reaction_diffusion::schnakenberg(0.1, 0.9), [1.0, 0.9], (size_x, size_y), n_sequence, &name),
+        "gierer_meinhardt" => run(reaction_diffusion::gierer_meinhardt(0.1, 1.0), [1.1, 1.21], (size_x, size_y), n_sequence, &name),
+        _ => run(reaction_diffusion::brusselator(3.0, 9.0), [3.0, 3.0], (size_x, size_y), n_sequence, &name),
+    }
+}
+
+/// Runs the model from a perturbed steady state and draws the first species.
+fn run<F: Fn(&[Atom; 2]) -> [Atom; 2]>(
+    model: ReactionDiffusion<Atom, F, 2>,
+    steady_state: [f32; 2],
+    (size_x, size_y): (usize, usize),
+    n_sequence: usize,
+    name: &str,
+) -> Result<(), error::MatrixError> {
+    let mut state: NChannelMatrix<Atom, 2> = reaction_diffusion::uniform_state(size_x, size_y, steady_state.map(LatticeElement))?;
+    let mut rng = Philox::new(7);
+    reaction_diffusion::perturb(&mut state, 0.01, || random::unit_f32(&mut rng))?;
+
+    let mut simulation = Simulation::new(state, model).with_steps(n_sequence);
+    simulation.run()?;
+
+    let activator = &simulation.get_state().get_data_ref()[0];
+    let values: Vec<f32> = activator.get_data().into_iter().map(|value| value.0).collect();
+    let (min, max) = values.iter().fold((f32::MAX, f32::MIN), |(min, max), value| (min.min(*value), max.max(*value)));
+    println!("{name}: activator between {min} and {max}");
+    Palette::gradient(Rgba([0, 0, 0, 255]), Rgba([255, 220, 120, 255]), 256)
+        .draw_range(activator, min, max)?
+        .save(format!("{name}.png"))?;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::{
+    error::Error,
+    fs,
+};
+use matrix_graph::{
+    automata::{
+        Sandpile,
+        sandpile::Avalanches,
+    },
+    random::Philox,
+    traits::Matrix,
+};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let (size_x, size_y) = (64, 64);
+    let sandpile = Sandpile::new();
+    let mut rng = Philox::new(7);
+
+    // Drive the pile to its critical state, then record the avalanches of further random drops.
+    let mut pile = Sandpile::empty(size_x, size_y);
+    for _ in 0..(size_x * size_y * 3) {
+        sandpile.drop_random(&mut pile, &mut rng)?;
+    }
+    let mut avalanches = Avalanches::default();
+    for _ in 0..100_000 {
+        avalanches.push(sandpile.drop_random(&mut pile, &mut rng)?);
+    }
+    let density = pile.get_data_ref().iter().sum::<u32>() as f64 / (size_x * size_y) as f64;
+    println!("density {density:.3}, largest avalanche {:?}", avalanches.distribution(|avalanche| avalanche.size).keys().last());
+    fs::write("avalanches.csv", avalanches.to_csv())?;
+    fs::write("distributions.csv", avalanches.distributions_csv())?;
+    sandpile.draw(&pile)?.save("sandpile.png")?;
+
+    // Relaxation of a single tower of grains into its fractal pattern.
+    let mut tower = Sandpile::empty(201, 201);
+    tower.edit_point((100_u32, 100), 60_000_u32)?;
+    let avalanche = sandpile.stabilize(&mut tower)?;
+    println!("tower: {} topplings over {} waves", avalanche.size, avalanche.duration);
+    sandpile.draw(&tower)?.save("tower.png")?;
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    automata::LifeLike,
+    random::{
+        self,
+        Philox,
+        Stochastic,
+    },
+    schedule::Schedule,
+    simulation::Rule,
+    traits::Matrix,
+    error,
+};
+
+/// Conway's Game of Life under every update schedule, from the same random soup, along with the
+/// stochastic voter model whose cells copy a random neighbor.
+fn main() -> Result<(), error::MatrixError> {
+    let (size_x, size_y) = (128, 128);
+    let life = LifeLike::conway();
+    let soup: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_height_and_width(size_y, size_x)
+        .with_seeded_generator(7, |rng| (random::unit_f64(rng) < 0.3) as u8)
+        .build();
+    let conway = |matrix: &MatrixImage<u8>, point, _: &mut Philox| {
+        let state = matrix.get_point_value(point).unwrap_or(0);
+        let count = matrix.hood_count(point, 1, Neighborhood::Moore, |value| *value == 1).unwrap_or(0) - state as usize;
+        life.next_state(state, count)
+    };
+    let voter = |matrix: &MatrixImage<u8>, point, rng: &mut Philox| {
+        let neighbor = (random::unit_f64(rng) * 4.0) as usize;
+        let offset = [(0, -1), (1, 0), (0, 1), (-1, 0)][neighbor.min(3)];
+        matrix.get_point_value(matrix.offset_point(point, offset)).unwrap_or(0)
+    };
+    println!("schedule\tlife density\tvoter density");
+    for schedule in [Schedule::Synchronous, Schedule::RandomSequential, Schedule::RandomIndependent, Schedule::Checkerboard, Schedule::Rows, Schedule::Columns] {
+        let mut rule = Stochastic::new(7, conway).with_schedule(schedule);
+        let mut voters = Stochastic::new(7, voter).with_schedule(schedule);
+        let (mut matrix, mut opinions) = (soup.clone(), soup.clone());
+        for _ in 0..200 {
+            matrix = rule.step(&matrix)?;
+            opinions = voters.step(&opinions)?;
+        }
+        let density = |matrix: &MatrixImage<u8>| matrix.get_data_ref().iter().filter(|state| **state == 1).count() as f64 / (size_x * size_y) as f64;
+        println!("{schedule:?}\t{:.3}\t{:.3}", density(&matrix), density(&opinions));
+        life.draw(&matrix)?.save(format!("life_{schedule:?}.png"))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    models::{
+        Wave,
+        wave::{
+            Boundary,
+            Source,
+            WaveState,
+        },
+    },
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+    error,
+};
+
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 600;
+    let (size_x, size_y) = (160, 120);
+
+    // A slower disc in the middle of the lattice bends the wavefronts like a lens.
+    let mut speed: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init()
+        .with_initial_value(LatticeElement(1.0))
+        .with_height_and_width(size_y, size_x)
+        .build();
+    for point in 0..(size_x*size_y) {
+        let (x, y) = speed.into_2d_point(point)?;
+        let (dx, dy) = (x as f32 - 100.0, y as f32 - 60.0);
+        if dx * dx + dy * dy < 25.0 * 25.0 {
+            speed.edit_point((x, y), 0.6)?;
+        }
+    }
+
+    let wave = Wave::new(1.0, 0.5, 1.0)
+        .with_speed_field(speed)
+        .with_boundary(Boundary::Mur)
+        .with_source(Source::line((20, 20), (20, 100), 0.5, 0.05))
+        .with_source(Source::point((60, 30), 2.0, 0.08));
+    let state = Wave::initial_state(size_x, size_y)?;
+
+    let mut frames = PngDirectory::new("./animation", |state: &WaveState| wave.draw(state, 1.0));
+    Simulation::new(state, wave.clone())
+        .with_steps(n_sequence)
+        .with_frame_interval(10)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use matrix_graph::{
+    MatrixImage,
+    MatrixImageBuilder,
+    automata::RuleTable,
+    traits::Matrix,
+    simulation::{
+        Simulation,
+        PngDirectory,
+    },
+    error,
+};
+
+const WIRE: u8 = 3;
+const HEAD: u8 = 1;
+const TAIL: u8 = 2;
+
+/// A clock loop feeding a straight wire, read from Golly's rule table format.
+fn main() -> Result<(), error::MatrixError> {
+    let n_sequence = 60;
+    let (size_x, size_y): (usize, usize) = (40,20);
+    let rule = RuleTable::wireworld();
+    let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_initial_value(0)
+        .with_height_and_width(size_y,size_x)
+        .build();
+    for x in 2..8_u32 {
+        matrix.edit_point((x, 8), WIRE)?;
+        matrix.edit_point((x, 12), WIRE)?;
+    }
+    for y in 9..12_u32 {
+        matrix.edit_point((1_u32, y), WIRE)?;
+        matrix.edit_point((8_u32, y), WIRE)?;
+    }
+    for x in 9..38_u32 {
+        matrix.edit_point((x, 10), WIRE)?;
+    }
+    matrix.edit_point((3_u32, 8_u32), HEAD)?;
+    matrix.edit_point((2_u32, 8_u32), TAIL)?;
+
+    let mut frames = PngDirectory::new("./animation", |matrix: &MatrixImage<u8>| rule.draw(matrix));
+    Simulation::new(matrix, rule.clone())
+        .with_steps(n_sequence)
+        .with_sink(&mut frames)
+        .run()?;
+
+    Ok(())
+}
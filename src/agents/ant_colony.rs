@@ -0,0 +1,248 @@
+//!   Foraging ants communicating through two pheromones. Searching ants follow the food trail and
+//!   lay the home trail, and ants carrying food follow the home trail back to the nest while laying
+//!   the food trail, so that paths between the nest and the food sources are reinforced. The
+//!   strength of the pheromone laid by an ant fades with the time since it left the nest or the
+//!   food, so that both trails grow towards their origin, and the pheromones evaporate every step.
+//!
+//!   The terrain holds the food trail, the home trail and the food as the channels of an
+//!   `NChannelMatrix`.
+
+use core::f64::consts::{
+    FRAC_PI_4,
+    PI,
+    TAU,
+};
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    n_channel::NChannelMatrix,
+    random::{
+        self,
+        Philox,
+    },
+    simulation::Rule,
+    traits::{
+        LatticeElement,
+        Matrix,
+    },
+    error::MatrixError,
+};
+use super::{
+    Agent,
+    World,
+    overlay,
+};
+
+pub const FOOD_TRAIL: usize = 0;
+pub const HOME_TRAIL: usize = 1;
+pub const FOOD: usize = 2;
+
+pub type Terrain = NChannelMatrix<LatticeElement<f32>, 3>;
+pub type Nest = World<Terrain, Forager>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Forager {
+    pub carrying: bool,
+    /// Fraction of the full deposit laid, reset to 1 at the nest and at the food.
+    pub strength: f32,
+    /// Food carried, up to a unit, less when taken from a cell holding less.
+    pub load: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AntColony {
+    nest: (f64, f64),
+    nest_radius: f64,
+    sensor_angle: f64,
+    sensor_distance: f64,
+    wander: f64,
+    deposit: f32,
+    fading: f32,
+    evaporation: f32,
+    seed: u64,
+    step: u64,
+    delivered: f32,
+}
+
+impl AntColony {
+    /// Colony around a nest of the given center and radius, whose ants sense 3 cells away at 45°
+    /// and wander by up to 0.3 radians every step.
+    pub fn new(nest: (f64, f64), nest_radius: f64) -> Self {
+        AntColony {
+            nest,
+            nest_radius,
+            sensor_angle: FRAC_PI_4,
+            sensor_distance: 3.0,
+            wander: 0.3,
+            deposit: 1.0,
+            fading: 0.99,
+            evaporation: 0.01,
+            seed: 0,
+            step: 0,
+            delivered: 0.0,
+        }
+    }
+    pub fn with_sensor(mut self, angle: f64, distance: f64) -> Self {
+        self.sensor_angle = angle;
+        self.sensor_distance = distance;
+        self
+    }
+    /// Largest random turn of an ant at every step.
+    pub fn with_wander(mut self, wander: f64) -> Self {
+        self.wander = wander;
+        self
+    }
+    /// Pheromone laid at full strength, factor by which the strength fades every step and fraction
+    /// of the pheromones evaporating every step.
+    pub fn with_pheromone(mut self, deposit: f32, fading: f32, evaporation: f32) -> Self {
+        self.deposit = deposit;
+        self.fading = fading;
+        self.evaporation = evaporation;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+    /// Food brought back to the nest.
+    pub fn get_delivered(&self) -> f32 {
+        self.delivered
+    }
+    /// Terrain without pheromones or food, with the given number of ants at the nest, heading in
+    /// random directions drawn from the seed.
+    pub fn initial_state(&self, width: usize, height: usize, ants: usize) -> Result<Nest, MatrixError> {
+        let empty: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(height, width)
+            .build();
+        let terrain = NChannelMatrix::try_from([empty.clone(), empty.clone(), empty])?;
+        let mut rng = Philox::new(self.seed);
+        let agents = (0..ants)
+            .map(|_| Agent::new(self.nest, random::unit_f64(&mut rng) * TAU, Forager { carrying: false, strength: 1.0, load: 0.0 }))
+            .collect();
+        Ok(World::new(terrain, agents))
+    }
+    /// Places a disc of food of the given amount per cell.
+    pub fn add_food(nest: &mut Nest, center: (u32, u32), radius: u32, amount: f32) -> Result<(), MatrixError> {
+        let food = &mut nest.field.get_data_mut_ref()[FOOD];
+        for point in food.get_lattice_neighborhood(center, radius as usize, Neighborhood::Moore) {
+            let (x_diff, y_diff) = (point.0 as f64 - center.0 as f64, point.1 as f64 - center.1 as f64);
+            if x_diff * x_diff + y_diff * y_diff <= (radius * radius) as f64 {
+                food.edit_point(point, amount)?;
+            }
+        }
+        Ok(())
+    }
+    fn at_nest(&self, position: (f64, f64), width: usize, height: usize) -> bool {
+        let wrapped = |difference: f64, length: f64| difference.abs().min(length - difference.abs());
+        let x_diff = wrapped(position.0 - self.nest.0, width as f64);
+        let y_diff = wrapped(position.1 - self.nest.1, height as f64);
+        x_diff * x_diff + y_diff * y_diff <= self.nest_radius * self.nest_radius
+    }
+    /// Moves every ant once, one after the other, from the stream of the ant at the given step,
+    /// and evaporates the pheromones. Returns the next state and the food delivered.
+    pub fn next_state(&self, nest: &Nest, step: u64) -> Result<(Nest, f32), MatrixError> {
+        let mut next = nest.clone();
+        let (width, height) = (*next.field.get_width(), *next.field.get_height());
+        let mut delivered = 0.0;
+        for (index, agent) in next.agents.iter_mut().enumerate() {
+            let mut rng = Philox::for_cell(self.seed, step, index);
+            let (followed, laid) = if agent.state.carrying { (HOME_TRAIL, FOOD_TRAIL) } else { (FOOD_TRAIL, HOME_TRAIL) };
+            let trail = &next.field.get_data_ref()[followed];
+            let sense = |angle| agent.sense(trail, self.sensor_distance, angle, 0, Neighborhood::Moore).map(|(sum, _)| sum.0);
+            let (front, left, right) = (sense(0.0)?, sense(-self.sensor_angle)?, sense(self.sensor_angle)?);
+            let steer = if left > front && left > right {
+                -self.sensor_angle
+            } else if right > front && right > left {
+                self.sensor_angle
+            } else {
+                0.0
+            };
+            agent.turn(steer + (random::unit_f64(&mut rng) * 2.0 - 1.0) * self.wander);
+            agent.advance(1.0, width, height);
+            let strength = LatticeElement(self.deposit * agent.state.strength);
+            agent.deposit(&mut next.field.get_data_mut_ref()[laid], strength)?;
+            agent.state.strength *= self.fading;
+            if agent.state.carrying {
+                if self.at_nest(agent.position, width, height) {
+                    delivered += agent.state.load;
+                    agent.state = Forager { carrying: false, strength: 1.0, load: 0.0 };
+                    agent.turn(PI);
+                }
+            } else {
+                let load = agent.consume(&mut next.field.get_data_mut_ref()[FOOD], LatticeElement(1.0))?.0;
+                if load > 0.0 {
+                    agent.state = Forager { carrying: true, strength: 1.0, load };
+                    agent.turn(PI);
+                } else if self.at_nest(agent.position, width, height) {
+                    agent.state.strength = 1.0;
+                }
+            }
+        }
+        for channel in [FOOD_TRAIL, HOME_TRAIL] {
+            for value in next.field.get_data_mut_ref()[channel].get_data_mut_ref() {
+                value.0 *= 1.0 - self.evaporation;
+            }
+        }
+        Ok((next, delivered))
+    }
+    /// Draws the food trail in red, the home trail in blue, both saturating at `max`, the food in
+    /// green, and the ants over them, yellow when they carry food.
+    pub fn draw(&self, nest: &Nest, max: f32) -> Result<RgbaImage, MatrixError> {
+        let [food_trail, home_trail, food] = nest.field.get_data_ref();
+        let mut image = RgbaImage::new(food.get_width().try_into()?, food.get_height().try_into()?);
+        let scale = |value: &LatticeElement<f32>| (value.0 / max * 255.0).clamp(0.0, 255.0) as u8;
+        for (index, pixel) in image.pixels_mut().enumerate() {
+            let green = if food.get_data_ref()[index].0 > 0.0 { 200 } else { 0 };
+            *pixel = Rgba([scale(&food_trail.get_data_ref()[index]), green, scale(&home_trail.get_data_ref()[index]), 255]);
+        }
+        overlay(&mut image, food, &nest.agents, |agent| if agent.state.carrying { Rgba([255, 255, 0, 255]) } else { Rgba([255, 255, 255, 255]) })?;
+        Ok(image)
+    }
+}
+
+impl Rule<Nest> for AntColony {
+    fn step(&mut self, state: &Nest) -> Result<Nest, MatrixError> {
+        let (next, delivered) = self.next_state(state, self.step)?;
+        self.step += 1;
+        self.delivered += delivered;
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        agents::ant_colony::{
+            AntColony,
+            FOOD,
+            Nest,
+        },
+        simulation::Rule,
+    };
+
+    #[test]
+    fn ants_bring_food_back_to_the_nest() {
+        let mut colony = AntColony::new((20.0, 40.0), 3.0).with_seed(47);
+        let mut nest = colony.initial_state(80, 80, 150).unwrap();
+        AntColony::add_food(&mut nest, (60, 40), 5, 2.5).unwrap();
+        let food = |nest: &Nest| nest.field.get_data_ref()[FOOD].get_data_ref().iter().map(|value| value.0).sum::<f32>();
+        let initial = food(&nest);
+        for _ in 0..2500 {
+            nest = colony.step(&nest).unwrap();
+        }
+        let carried: f32 = nest.agents.iter().map(|agent| agent.state.load).sum();
+        assert_eq!(initial - food(&nest), colony.get_delivered() + carried);
+        assert!(nest.agents.iter().all(|agent| [0.0, 0.5, 1.0].contains(&agent.state.load)));
+        assert_eq!((initial - food(&nest)).fract(), 0.5);
+        assert!(colony.get_delivered() > 20.0, "{}", colony.get_delivered());
+    }
+}
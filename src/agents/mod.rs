@@ -0,0 +1,210 @@
+//!   Mobile agents living on top of a lattice field. An `Agent` has a continuous position, a heading
+//!   and an internal state; it senses the field around points ahead of it through
+//!   `get_lattice_neighborhood`, moves with toroidal wrapping and deposits to or consumes from the
+//!   cell it stands on. A `World` holds the field along with its agents and is stepped as the state
+//!   of a `Simulation` by the built-in models, whose agents are drawn over the rendered field.
+//!
+//!   Headings are angles in radians measured from the x axis towards the y axis, so that with the y
+//!   axis pointing down the lattice, a positive turn is clockwise on the rendered image.
+
+pub mod turmite;
+pub use turmite::Turmite;
+
+pub mod physarum;
+pub use physarum::Physarum;
+
+pub mod ant_colony;
+pub use ant_colony::AntColony;
+
+use core::{
+    f64::consts::TAU,
+    fmt::Debug,
+    ops::{
+        Add,
+        Div,
+        Mul,
+        Sub,
+    },
+};
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    Neighborhood,
+    traits::{
+        Matrix,
+        Max,
+    },
+    error::MatrixError,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Agent<S> {
+    pub position: (f64, f64),
+    pub heading: f64,
+    pub state: S,
+}
+
+impl<S> Agent<S> {
+    pub fn new(position: (f64, f64), heading: f64, state: S) -> Self {
+        Agent {
+            position,
+            heading,
+            state,
+        }
+    }
+    /// Cell of the lattice of the given width and height holding a position, wrapped around the edges.
+    fn wrap_cell(position: (f64, f64), width: usize, height: usize) -> (u32, u32) {
+        let x = (position.0.floor() as i64).rem_euclid(width.max(1) as i64);
+        let y = (position.1.floor() as i64).rem_euclid(height.max(1) as i64);
+        (x as u32, y as u32)
+    }
+    /// Cell the agent stands on.
+    pub fn cell<T: Clone>(&self, field: &MatrixImage<T>) -> (u32, u32) {
+        Self::wrap_cell(self.position, field.get_width(), field.get_height())
+    }
+    /// Turns the agent by the given angle, keeping its heading within [0, 2π).
+    pub fn turn(&mut self, angle: f64) {
+        self.heading = (self.heading + angle).rem_euclid(TAU);
+    }
+    /// Position at the given distance from the agent, at the given angle from its heading, not wrapped.
+    pub fn ahead(&self, distance: f64, angle: f64) -> (f64, f64) {
+        let direction = self.heading + angle;
+        (self.position.0 + distance * direction.cos(), self.position.1 + distance * direction.sin())
+    }
+    /// Moves the agent along its heading, wrapping its position around the edges of the lattice.
+    pub fn advance(&mut self, distance: f64, width: usize, height: usize) {
+        let (x, y) = self.ahead(distance, 0.0);
+        self.position = (x.rem_euclid(width as f64), y.rem_euclid(height as f64));
+        if self.position.0 >= width as f64 {
+            self.position.0 = 0.0;
+        }
+        if self.position.1 >= height as f64 {
+            self.position.1 = 0.0;
+        }
+    }
+    /// Moves the agent to the neighboring cell its heading points to, the heading being rounded to
+    /// the nearest of the eight Moore directions, and places it on the corner of the cell.
+    pub fn advance_cell(&mut self, width: usize, height: usize) {
+        let (x, y) = Self::wrap_cell(self.position, width, height);
+        let offset = (self.heading.cos().round(), self.heading.sin().round());
+        let (x, y) = Self::wrap_cell((x as f64 + offset.0, y as f64 + offset.1), width, height);
+        self.position = (x as f64, y as f64);
+    }
+    /// Sum and number of the values of the field over the neighborhood of the cell at the given
+    /// distance and angle from the agent.
+    pub fn sense<T>(&self, field: &MatrixImage<T>, distance: f64, angle: f64, size: usize, hood_type: Neighborhood) -> Result<(T, usize), MatrixError>
+    where T: Clone + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+    {
+        let cell = Self::wrap_cell(self.ahead(distance, angle), field.get_width(), field.get_height());
+        field.hood_sum(cell, size, hood_type.per_cell()?)
+    }
+    /// Adds an amount to the cell the agent stands on.
+    pub fn deposit<T: Clone + Add<Output=T>>(&self, field: &mut MatrixImage<T>, amount: T) -> Result<(), MatrixError> {
+        let cell = self.cell(field);
+        let value = field.get_point_value(cell)?;
+        field.edit_point(cell, value + amount)
+    }
+    /// Takes up to an amount from the cell the agent stands on, returning the amount taken.
+    pub fn consume<T: Clone + Default + PartialOrd + Sub<Output=T>>(&self, field: &mut MatrixImage<T>, amount: T) -> Result<T, MatrixError> {
+        let cell = self.cell(field);
+        let value = field.get_point_value(cell)?;
+        let taken = if value < amount { value.clone() } else { amount };
+        let taken = if taken < T::default() { T::default() } else { taken };
+        field.edit_point(cell, value - taken.clone())?;
+        Ok(taken)
+    }
+}
+
+/// Field of a lattice along with the agents living on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct World<F, S> {
+    pub field: F,
+    pub agents: Vec<Agent<S>>,
+}
+
+impl<F, S> World<F, S> {
+    pub fn new(field: F, agents: Vec<Agent<S>>) -> Self {
+        World {
+            field,
+            agents,
+        }
+    }
+}
+
+/// Colors the pixels of the cells holding agents on an image of the field, which must have one
+/// pixel per cell of the field.
+pub fn overlay<S, T: Clone>(image: &mut RgbaImage, field: &MatrixImage<T>, agents: &[Agent<S>], color: impl Fn(&Agent<S>) -> Rgba<u8>) -> Result<(), MatrixError> {
+    let (width, height) = (field.get_width(), field.get_height());
+    if (image.width() as usize, image.height() as usize) != (width, height) {
+        return Err(MatrixError::DimensionMismatch { expected: (width, height), found: (image.width() as usize, image.height() as usize) });
+    }
+    if width * height == 0 {
+        return Ok(());
+    }
+    for agent in agents {
+        let (x, y) = agent.cell(field);
+        image.put_pixel(x, y, color(agent));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::f64::consts::{
+        FRAC_PI_2,
+        PI,
+    };
+    use image::{
+        Rgba,
+        RgbaImage,
+    };
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        Neighborhood,
+        agents::{
+            self,
+            Agent,
+        },
+        traits::{
+            LatticeElement,
+            Matrix,
+        },
+    };
+
+    #[test]
+    fn agents_wrap_around_and_exchange_with_the_field() {
+        let mut field: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(4, 5)
+            .build();
+        let mut agent = Agent::new((4.5, 0.5), 0.0, ());
+        agent.advance(1.0, 5, 4);
+        assert_eq!(agent.cell(&field), (0, 0));
+        agent.turn(-FRAC_PI_2);
+        agent.advance_cell(5, 4);
+        assert_eq!(agent.position, (0.0, 3.0));
+        agent.turn(PI);
+        agent.advance_cell(5, 4);
+        agent.advance_cell(5, 4);
+        assert_eq!(agent.cell(&field), (0, 1));
+        agent.deposit(&mut field, LatticeElement(2.0)).unwrap();
+        assert_eq!(agent.consume(&mut field, LatticeElement(1.5)).unwrap(), LatticeElement(1.5));
+        assert_eq!(agent.consume(&mut field, LatticeElement(1.5)).unwrap(), LatticeElement(0.5));
+        agent.deposit(&mut field, LatticeElement(3.0)).unwrap();
+        let (sum, count) = Agent::new((4.0, 1.0), PI, ()).sense(&field, 4.0, 0.0, 1, Neighborhood::VonNeumann).unwrap();
+        assert_eq!((sum, count), (LatticeElement(3.0), 5));
+        assert!(agent.sense(&field, 1.0, 0.0, 1, Neighborhood::Margolus).is_err());
+        let mut image = RgbaImage::new(5, 4);
+        agents::overlay(&mut image, &field, &[agent], |_| Rgba([255, 0, 0, 255])).unwrap();
+        assert_eq!(*image.get_pixel(0, 1), Rgba([255, 0, 0, 255]));
+        assert!(agents::overlay(&mut RgbaImage::new(4, 5), &field, &[agent], |_| Rgba([255, 0, 0, 255])).is_err());
+        let empty: MatrixImage<LatticeElement<f32>> = MatrixImageBuilder::init().with_height_and_width(0, 0).build();
+        assert!(agents::overlay(&mut RgbaImage::new(0, 0), &empty, &[agent], |_| Rgba([255, 0, 0, 255])).is_ok());
+        assert_eq!(field.get_point_value((0_u32, 1_u32)).unwrap(), LatticeElement(3.0));
+    }
+}
@@ -0,0 +1,236 @@
+//!   Slime-mold model of Jones, agents following and reinforcing a chemical trail, whose collective
+//!   motion forms networks resembling those of Physarum polycephalum. Every agent senses the trail
+//!   ahead of it and at a sensor angle to its left and right, rotates towards the strongest reading,
+//!   or randomly when both sides are stronger than the front, moves forward and deposits on the
+//!   trail. The trail then diffuses over the 3×3 Moore neighborhood and decays.
+
+use core::f64::consts::{
+    FRAC_PI_4,
+    TAU,
+};
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    random::{
+        self,
+        Philox,
+    },
+    simulation::Rule,
+    traits::{
+        LatticeElement,
+        Matrix,
+    },
+    error::MatrixError,
+};
+use super::{
+    Agent,
+    World,
+    overlay,
+};
+
+pub type Mold = World<MatrixImage<LatticeElement<f32>>, ()>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Physarum {
+    sensor_angle: f64,
+    sensor_distance: f64,
+    sensor_size: usize,
+    sensor_type: Neighborhood,
+    rotation: f64,
+    step_size: f64,
+    deposit: f32,
+    decay: f32,
+    seed: u64,
+    step: u64,
+    palette: Palette,
+    agent_color: Option<Rgba<u8>>,
+}
+
+impl Physarum {
+    /// Agents sensing single cells 9 cells away at 45°, rotating by 45°, moving one cell per step
+    /// and depositing 5 units of a trail that decays by a tenth every step.
+    pub fn new() -> Self {
+        Physarum {
+            sensor_angle: FRAC_PI_4,
+            sensor_distance: 9.0,
+            sensor_size: 0,
+            sensor_type: Neighborhood::Moore,
+            rotation: FRAC_PI_4,
+            step_size: 1.0,
+            deposit: 5.0,
+            decay: 0.1,
+            seed: 0,
+            step: 0,
+            palette: Palette::gradient(Rgba([0, 0, 0, 255]), Rgba([255, 230, 120, 255]), 64),
+            agent_color: None,
+        }
+    }
+    pub fn with_sensor(mut self, angle: f64, distance: f64) -> Self {
+        self.sensor_angle = angle;
+        self.sensor_distance = distance;
+        self
+    }
+    /// Neighborhood of the sensed cells over which the trail is summed, a single cell by default.
+    pub fn with_sensor_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.sensor_type = hood_type;
+        self.sensor_size = hood_size;
+        self
+    }
+    pub fn with_rotation(mut self, rotation: f64) -> Self {
+        self.rotation = rotation;
+        self
+    }
+    pub fn with_step_size(mut self, step_size: f64) -> Self {
+        self.step_size = step_size;
+        self
+    }
+    /// Trail deposited by every agent at every step, and fraction of the trail lost every step.
+    pub fn with_trail(mut self, deposit: f32, decay: f32) -> Self {
+        self.deposit = deposit;
+        self.decay = decay;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    /// Color of the agents drawn over the trail, which are not drawn by default.
+    pub fn with_agent_color(mut self, agent_color: Option<Rgba<u8>>) -> Self {
+        self.agent_color = agent_color;
+        self
+    }
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+    /// Empty trail with agents at uniformly random positions and headings drawn from the seed.
+    pub fn initial_state(&self, width: usize, height: usize, agents: usize) -> Mold {
+        let mut rng = Philox::new(self.seed);
+        let field = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(height, width)
+            .build();
+        let agents = (0..agents)
+            .map(|_| {
+                let position = (random::unit_f64(&mut rng) * width as f64, random::unit_f64(&mut rng) * height as f64);
+                Agent::new(position, random::unit_f64(&mut rng) * TAU, ())
+            })
+            .collect();
+        World::new(field, agents)
+    }
+    /// Senses, rotates, moves and deposits every agent, from the trail of the previous step and the
+    /// stream of the agent at the given step, then diffuses and decays the trail.
+    pub fn next_state(&self, mold: &Mold, step: u64) -> Result<Mold, MatrixError> {
+        let (width, height) = (mold.field.get_width(), mold.field.get_height());
+        let mut agents = mold.agents.clone();
+        let mut trail = mold.field.clone();
+        for (index, agent) in agents.iter_mut().enumerate() {
+            let sense = |angle| agent.sense(&mold.field, self.sensor_distance, angle, self.sensor_size, self.sensor_type).map(|(sum, _)| sum.0);
+            let (front, left, right) = (sense(0.0)?, sense(-self.sensor_angle)?, sense(self.sensor_angle)?);
+            let rotation = if front > left && front > right {
+                0.0
+            } else if front < left && front < right {
+                if random::unit_f64(&mut Philox::for_cell(self.seed, step, index)) < 0.5 { -self.rotation } else { self.rotation }
+            } else if left < right {
+                self.rotation
+            } else if right < left {
+                -self.rotation
+            } else {
+                0.0
+            };
+            agent.turn(rotation);
+            agent.advance(self.step_size, width, height);
+            agent.deposit(&mut trail, LatticeElement(self.deposit))?;
+        }
+        let mut field = trail.clone();
+        for (index, value) in field.get_data_mut_ref().iter_mut().enumerate() {
+            let (sum, count) = trail.hood_sum(trail.into_2d_point(index)?, 1, Neighborhood::Moore)?;
+            *value = LatticeElement(sum.0 / count as f32 * (1.0 - self.decay));
+        }
+        Ok(World::new(field, agents))
+    }
+    /// Draws the trail from 0 to `max` through the palette, with the agents over it when they have
+    /// a color.
+    pub fn draw(&self, mold: &Mold, max: f32) -> Result<RgbaImage, MatrixError> {
+        let mut image = self.palette.draw_range(&mold.field, 0.0, max)?;
+        if let Some(color) = self.agent_color {
+            overlay(&mut image, &mold.field, &mold.agents, |_| color)?;
+        }
+        Ok(image)
+    }
+}
+
+impl Default for Physarum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule<Mold> for Physarum {
+    fn step(&mut self, state: &Mold) -> Result<Mold, MatrixError> {
+        let next = self.next_state(state, self.step)?;
+        self.step += 1;
+        Ok(next)
+    }
+}
+
+/// Total amount of trail.
+pub fn trail_total(mold: &Mold) -> f32 {
+    mold.field.get_data_ref().iter().map(|value| value.0).sum()
+}
+
+/// Fraction of the trail held by the given share of the cells holding the most trail, close to
+/// the share itself for a uniform trail and to 1 when the trail gathers into a network.
+pub fn trail_concentration(mold: &Mold, share: f64) -> f32 {
+    let mut values: Vec<f32> = mold.field.get_data_ref().iter().map(|value| value.0).collect();
+    values.sort_unstable_by(|first, second| second.total_cmp(first));
+    let top = ((values.len() as f64 * share).ceil() as usize).min(values.len());
+    values[..top].iter().sum::<f32>() / trail_total(mold).max(f32::MIN_POSITIVE)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        agents::physarum::{
+            self,
+            Physarum,
+        },
+        simulation::Rule,
+    };
+
+    #[test]
+    fn trail_balances_deposit_and_decay() {
+        let mut physarum = Physarum::new().with_trail(5.0, 0.5).with_seed(47);
+        let mut mold = physarum.initial_state(32, 32, 20);
+        for _ in 0..60 {
+            mold = physarum.step(&mold).unwrap();
+        }
+        assert_eq!(mold.agents.len(), 20);
+        assert!(mold.agents.iter().all(|agent| (0.0..32.0).contains(&agent.position.0) && (0.0..32.0).contains(&agent.position.1)));
+        let total = physarum::trail_total(&mold);
+        assert!((total - 100.0).abs() < 1.0, "{total}");
+    }
+
+    #[test]
+    fn agents_gather_into_a_network() {
+        let mut physarum = Physarum::new().with_seed(47);
+        let mut mold = physarum.initial_state(64, 64, 800);
+        let again = physarum.initial_state(64, 64, 800);
+        assert_eq!(mold, again);
+        for _ in 0..150 {
+            mold = physarum.step(&mold).unwrap();
+        }
+        let concentration = physarum::trail_concentration(&mold, 0.2);
+        assert!(concentration > 0.6, "{concentration}");
+    }
+}
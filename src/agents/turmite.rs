@@ -0,0 +1,211 @@
+//!   Turmites, agents moving on a lattice of colors whose transitions depend on their own state and
+//!   on the color of the cell they stand on: every step a turmite paints its cell, turns, changes
+//!   state and moves to the next cell. Langton's ant and its generalizations are single-state
+//!   turmites, written as the turns taken on each color, e.g. `RL` for Langton's ant or `LLRR`,
+//!   every color being painted with the next one.
+
+use core::f64::consts::{
+    FRAC_PI_2,
+    PI,
+};
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::{
+    Agent,
+    World,
+    overlay,
+};
+
+pub type Colony = World<MatrixImage<u8>, u8>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+    None,
+    Right,
+    UTurn,
+    Left,
+}
+
+impl Turn {
+    /// Angle of the turn, positive turns being clockwise on the rendered lattice.
+    pub fn angle(self) -> f64 {
+        match self {
+            Turn::None => 0.0,
+            Turn::Right => FRAC_PI_2,
+            Turn::UTurn => PI,
+            Turn::Left => -FRAC_PI_2,
+        }
+    }
+}
+
+/// Color painted, turn taken and next state of a turmite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub color: u8,
+    pub turn: Turn,
+    pub state: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turmite {
+    /// Transitions indexed by state, then by color.
+    table: Vec<Vec<Transition>>,
+    palette: Palette,
+}
+
+impl Turmite {
+    /// Turmite of the given transitions, indexed by state, then by color, every state having a
+    /// transition for each color and all states and colors being within the table.
+    pub fn new(table: Vec<Vec<Transition>>) -> Result<Self, MatrixError> {
+        let colors = table.first().map_or(0, Vec::len);
+        if colors == 0
+            || table.iter().any(|transitions| transitions.len() != colors)
+            || table.iter().flatten().any(|transition| transition.color as usize >= colors || transition.state as usize >= table.len())
+        {
+            return Err(MatrixError::InvalidRule(format!("a turmite needs a transition for every state and color, within {} states", table.len())));
+        }
+        let palette = Palette::gradient(Rgba([255, 255, 255, 255]), Rgba([20, 20, 80, 255]), colors);
+        Ok(Turmite {
+            table,
+            palette,
+        })
+    }
+    /// Single-state turmite taking the given turn on each color, `R`, `L`, `N` or `U`, and painting
+    /// the next color, e.g. `RL` for Langton's ant.
+    pub fn ant(turns: &str) -> Result<Self, MatrixError> {
+        let turns: Vec<Turn> = turns
+            .trim()
+            .chars()
+            .map(|turn| match turn.to_ascii_uppercase() {
+                'R' => Ok(Turn::Right),
+                'L' => Ok(Turn::Left),
+                'N' => Ok(Turn::None),
+                'U' => Ok(Turn::UTurn),
+                _ => Err(MatrixError::InvalidRule(format!("{turns} should only have the turns R, L, N and U"))),
+            })
+            .collect::<Result<_, _>>()?;
+        let colors = turns.len();
+        let transitions = turns
+            .iter()
+            .enumerate()
+            .map(|(color, turn)| Transition { color: ((color + 1) % colors.max(1)) as u8, turn: *turn, state: 0 })
+            .collect();
+        Self::new(vec![transitions])
+    }
+    pub fn langtons_ant() -> Self {
+        Self::ant("RL").expect("The rule is valid.")
+    }
+    /// Two-state turmite growing a square spiral whose arms follow the Fibonacci sequence.
+    pub fn fibonacci_spiral() -> Self {
+        let transition = |color, turn, state| Transition { color, turn, state };
+        Self::new(vec![
+            vec![transition(1, Turn::Left, 1), transition(1, Turn::Left, 1)],
+            vec![transition(1, Turn::Right, 1), transition(0, Turn::None, 0)],
+        ])
+        .expect("The table is complete.")
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_colors(&self) -> usize {
+        self.table[0].len()
+    }
+    pub fn get_states(&self) -> usize {
+        self.table.len()
+    }
+    /// Blank lattice with turmites in state 0 at the given cells, heading up.
+    pub fn initial_state(width: usize, height: usize, cells: &[(u32, u32)]) -> Colony {
+        let field = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(height, width)
+            .build();
+        let agents = cells.iter().map(|(x, y)| Agent::new((*x as f64, *y as f64), 3.0 * FRAC_PI_2, 0)).collect();
+        World::new(field, agents)
+    }
+    /// Moves every turmite once, one after the other, so that turmites standing on the same cell
+    /// see the colors painted by the previous ones.
+    pub fn next_state(&self, colony: &Colony) -> Result<Colony, MatrixError> {
+        let mut next = colony.clone();
+        let (width, height) = (next.field.get_width(), next.field.get_height());
+        for agent in next.agents.iter_mut() {
+            let cell = agent.cell(&next.field);
+            let color = next.field.get_point_value(cell)?;
+            let transition = self.table
+                .get(agent.state as usize)
+                .and_then(|transitions| transitions.get(color as usize))
+                .ok_or_else(|| MatrixError::InvalidRule(format!("a turmite has no transition for state {} on color {color}", agent.state)))?;
+            next.field.edit_point(cell, transition.color)?;
+            agent.turn(transition.turn.angle());
+            agent.state = transition.state;
+            agent.advance_cell(width, height);
+        }
+        Ok(next)
+    }
+    /// Draws the colors through the palette and the turmites in red.
+    pub fn draw(&self, colony: &Colony) -> Result<RgbaImage, MatrixError> {
+        let mut image = self.palette.draw(&colony.field)?;
+        overlay(&mut image, &colony.field, &colony.agents, |_| Rgba([255, 0, 0, 255]))?;
+        Ok(image)
+    }
+}
+
+impl Rule<Colony> for Turmite {
+    fn step(&mut self, state: &Colony) -> Result<Colony, MatrixError> {
+        self.next_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        agents::turmite::{
+            Transition,
+            Turmite,
+            Turn,
+        },
+        simulation::Rule,
+        traits::Matrix,
+    };
+
+    #[test]
+    fn langtons_ant_builds_its_highway() {
+        let mut ant = Turmite::langtons_ant();
+        let mut colony = Turmite::initial_state(80, 80, &[(40, 40)]);
+        colony = ant.step(&colony).unwrap();
+        assert_eq!(colony.field.get_point_value((40_u32, 40_u32)).unwrap(), 1);
+        assert_eq!(colony.agents[0].position, (41.0, 40.0));
+        for _ in 1..11000 {
+            colony = ant.step(&colony).unwrap();
+        }
+        let (x, y) = colony.agents[0].position;
+        assert!((x - 40.0).abs() + (y - 40.0).abs() > 20.0, "{:?}", colony.agents[0].position);
+        assert!(Turmite::ant("RLX").is_err());
+        assert_eq!(Turmite::ant("LLRR").unwrap().get_colors(), 4);
+    }
+
+    #[test]
+    fn turmite_tables_are_checked() {
+        let spiral = Turmite::fibonacci_spiral();
+        assert_eq!((spiral.get_states(), spiral.get_colors()), (2, 2));
+        let mut turmite = spiral.clone();
+        let mut colony = Turmite::initial_state(64, 64, &[(32, 32), (10, 10)]);
+        for _ in 0..200 {
+            colony = turmite.step(&colony).unwrap();
+        }
+        assert!(colony.field.get_data_ref().contains(&1));
+        assert!(Turmite::new(vec![]).is_err());
+        assert!(Turmite::new(vec![vec![Transition { color: 2, turn: Turn::Left, state: 0 }]]).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//!   Stochastic epidemic automata, SIS, SIR and SEIR. A susceptible cell is infected by each of its
+//!   infected neighbors with the infection probability, so that with k infected neighbors it falls
+//!   ill with probability 1 - (1 - β)^k. Exposed cells become infectious with the incubation
+//!   probability, infected cells recover with the recovery probability, to the susceptible state in
+//!   SIS and to the recovered state otherwise, and recovered cells may lose their immunity.
+//!
+//!   Cells draw from their own `Philox` stream at every step, so that a run is reproduced by its
+//!   seed, serially or in parallel.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    random::{
+        self,
+        Philox,
+    },
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+
+pub const SUSCEPTIBLE: u8 = 0;
+pub const EXPOSED: u8 = 1;
+pub const INFECTED: u8 = 2;
+pub const RECOVERED: u8 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Model {
+    /// Infected cells become susceptible again when they recover.
+    Sis,
+    Sir,
+    /// Infected susceptible cells are exposed, and not infectious, until their incubation ends.
+    Seir,
+}
+
+/// Number of cells in every state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Compartments {
+    pub susceptible: usize,
+    pub exposed: usize,
+    pub infected: usize,
+    pub recovered: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Epidemic {
+    model: Model,
+    infection: f64,
+    incubation: f64,
+    recovery: f64,
+    immunity_loss: f64,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    seed: u64,
+    step: u64,
+    series: Vec<Compartments>,
+    palette: Palette,
+}
+
+impl Epidemic {
+    /// Epidemic over the Moore neighborhood of size 1 with the given probabilities of infection by
+    /// one infected neighbor and of recovery, per step.
+    pub fn new(model: Model, infection: f64, recovery: f64) -> Self {
+        Epidemic {
+            model,
+            infection,
+            incubation: 1.0,
+            recovery,
+            immunity_loss: 0.0,
+            hood_type: Neighborhood::Moore,
+            hood_size: 1,
+            seed: 0,
+            step: 0,
+            series: Vec::new(),
+            palette: Palette::new(vec![
+                Rgba([40, 40, 60, 255]),
+                Rgba([250, 200, 40, 255]),
+                Rgba([220, 30, 30, 255]),
+                Rgba([60, 160, 220, 255]),
+            ]),
+        }
+    }
+    pub fn sis(infection: f64, recovery: f64) -> Self {
+        Self::new(Model::Sis, infection, recovery)
+    }
+    pub fn sir(infection: f64, recovery: f64) -> Self {
+        Self::new(Model::Sir, infection, recovery)
+    }
+    pub fn seir(infection: f64, incubation: f64, recovery: f64) -> Self {
+        Self::new(Model::Seir, infection, recovery).with_incubation(incubation)
+    }
+    /// Probability per step that an exposed cell becomes infectious, 1 by default.
+    pub fn with_incubation(mut self, incubation: f64) -> Self {
+        self.incubation = incubation;
+        self
+    }
+    /// Probability per step that a recovered cell becomes susceptible again, 0 by default.
+    pub fn with_immunity_loss(mut self, immunity_loss: f64) -> Self {
+        self.immunity_loss = immunity_loss;
+        self
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_model(&self) -> Model {
+        self.model
+    }
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+    /// Compartments of the initial state and after every step taken as a `Rule`.
+    pub fn get_series_ref(&self) -> &Vec<Compartments> {
+        &self.series
+    }
+    /// Susceptible lattice with infected cells at the given points.
+    pub fn initial_state(width: usize, height: usize, infected: &[(u32, u32)]) -> Result<MatrixImage<u8>, MatrixError> {
+        let mut matrix = MatrixImageBuilder::init()
+            .with_initial_value(SUSCEPTIBLE)
+            .with_height_and_width(height, width)
+            .build();
+        for point in infected {
+            matrix.edit_point(*point, INFECTED)?;
+        }
+        Ok(matrix)
+    }
+    /// Next state of a cell given its current state, its number of infected neighbors and a uniform
+    /// number in [0, 1).
+    pub fn next_state(&self, state: u8, infected: usize, uniform: f64) -> u8 {
+        match state {
+            SUSCEPTIBLE if uniform < 1.0 - (1.0 - self.infection).powi(infected as i32) => match self.model {
+                Model::Seir => EXPOSED,
+                _ => INFECTED,
+            },
+            EXPOSED if uniform < self.incubation => INFECTED,
+            INFECTED if uniform < self.recovery => match self.model {
+                Model::Sis => SUSCEPTIBLE,
+                _ => RECOVERED,
+            },
+            RECOVERED if uniform < self.immunity_loss => SUSCEPTIBLE,
+            state => state,
+        }
+    }
+    /// Applies the transitions to every cell, drawing from the streams of the cells at the given step.
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>, step: u64) -> Result<MatrixImage<u8>, MatrixError> {
+        let counts = matrix.hood_counts(self.hood_size, self.hood_type.per_cell()?, |state| *state == INFECTED);
+        Ok(random::map_cells(matrix, self.seed, step, |matrix, point, rng: &mut Philox| {
+            let index = point.1 as usize * matrix.get_width() + point.0 as usize;
+            let state = matrix.get_data_ref()[index];
+            let infected = counts.get_data_ref()[index] - (state == INFECTED) as usize;
+            self.next_state(state, infected, random::unit_f64(rng))
+        }))
+    }
+    pub fn compartments(matrix: &MatrixImage<u8>) -> Compartments {
+        let mut compartments = Compartments::default();
+        for state in matrix.get_data_ref() {
+            match *state {
+                SUSCEPTIBLE => compartments.susceptible += 1,
+                EXPOSED => compartments.exposed += 1,
+                INFECTED => compartments.infected += 1,
+                _ => compartments.recovered += 1,
+            }
+        }
+        compartments
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+impl Rule<MatrixImage<u8>> for Epidemic {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        if self.series.is_empty() {
+            self.series.push(Self::compartments(state));
+        }
+        let next = self.next_generation(state, self.step)?;
+        self.step += 1;
+        self.series.push(Self::compartments(&next));
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        automata::epidemic::{
+            Compartments,
+            Epidemic,
+            EXPOSED,
+            INFECTED,
+            RECOVERED,
+            SUSCEPTIBLE,
+        },
+        simulation::Rule,
+    };
+
+    #[test]
+    fn transitions_follow_the_model() {
+        let sir = Epidemic::sir(0.5, 0.2);
+        assert_eq!(sir.next_state(SUSCEPTIBLE, 0, 0.0), SUSCEPTIBLE);
+        assert_eq!(sir.next_state(SUSCEPTIBLE, 2, 0.74), INFECTED);
+        assert_eq!(sir.next_state(SUSCEPTIBLE, 2, 0.76), SUSCEPTIBLE);
+        assert_eq!(sir.next_state(INFECTED, 8, 0.1), RECOVERED);
+        assert_eq!(sir.next_state(RECOVERED, 8, 0.0), RECOVERED);
+        assert_eq!(Epidemic::sis(0.5, 0.2).next_state(INFECTED, 0, 0.1), SUSCEPTIBLE);
+        let seir = Epidemic::seir(1.0, 0.25, 0.2).with_immunity_loss(0.5);
+        assert_eq!(seir.next_state(SUSCEPTIBLE, 1, 0.9), EXPOSED);
+        assert_eq!(seir.next_state(EXPOSED, 0, 0.3), EXPOSED);
+        assert_eq!(seir.next_state(EXPOSED, 0, 0.2), INFECTED);
+        assert_eq!(seir.next_state(RECOVERED, 0, 0.4), SUSCEPTIBLE);
+    }
+
+    #[test]
+    fn sir_outbreak_conserves_cells_and_burns_out() {
+        let mut sir = Epidemic::sir(0.3, 0.2).with_neighborhood(Neighborhood::VonNeumann, 1).with_seed(44);
+        let mut matrix = Epidemic::initial_state(40, 40, &[(20, 20)]).unwrap();
+        for _ in 0..400 {
+            matrix = sir.step(&matrix).unwrap();
+        }
+        let series = sir.get_series_ref();
+        assert_eq!(series.len(), 401);
+        assert_eq!(series[0], Compartments { susceptible: 1599, exposed: 0, infected: 1, recovered: 0 });
+        assert!(series.iter().all(|compartments| compartments.susceptible + compartments.infected + compartments.recovered == 1600 && compartments.exposed == 0));
+        assert!(series.windows(2).all(|pair| pair[1].susceptible <= pair[0].susceptible));
+        assert_eq!(series[400].infected, 0);
+        assert!(series[400].recovered > 800, "{:?}", series[400]);
+        let mut again = Epidemic::sir(0.3, 0.2).with_neighborhood(Neighborhood::VonNeumann, 1).with_seed(44);
+        let first = again.step(&Epidemic::initial_state(40, 40, &[(20, 20)]).unwrap()).unwrap();
+        assert_eq!(Epidemic::compartments(&first), series[1]);
+    }
+}
@@ -0,0 +1,198 @@
+//!   Forest-fire model of Drossel and Schwabl. Trees grow on empty cells with the growth
+//!   probability, a burning tree leaves an empty cell, and a tree catches fire from each of its
+//!   burning neighbors with the ignition probability, or is struck by lightning with the lightning
+//!   probability. With a growth much larger than the lightning the forest settles into a
+//!   self-organized critical state of fires of all sizes.
+//!
+//!   Cells draw from their own `Philox` stream at every step, as in the `epidemic` automata.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    random::{
+        self,
+        Philox,
+    },
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+
+pub const EMPTY: u8 = 0;
+pub const TREE: u8 = 1;
+pub const BURNING: u8 = 2;
+
+/// Number of cells in every state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Forest {
+    pub empty: usize,
+    pub trees: usize,
+    pub burning: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForestFire {
+    growth: f64,
+    lightning: f64,
+    ignition: f64,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    seed: u64,
+    step: u64,
+    series: Vec<Forest>,
+    palette: Palette,
+}
+
+impl ForestFire {
+    /// Forest over the von Neumann neighborhood of size 1, where fire always spreads to neighboring
+    /// trees.
+    pub fn new(growth: f64, lightning: f64) -> Self {
+        ForestFire {
+            growth,
+            lightning,
+            ignition: 1.0,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            seed: 0,
+            step: 0,
+            series: Vec::new(),
+            palette: Palette::new(vec![
+                Rgba([30, 20, 10, 255]),
+                Rgba([30, 140, 40, 255]),
+                Rgba([255, 120, 0, 255]),
+            ]),
+        }
+    }
+    /// Probability that a tree catches fire from one burning neighbor, 1 by default.
+    pub fn with_ignition(mut self, ignition: f64) -> Self {
+        self.ignition = ignition;
+        self
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+    /// Counts of the initial state and after every step taken as a `Rule`.
+    pub fn get_series_ref(&self) -> &Vec<Forest> {
+        &self.series
+    }
+    pub fn initial_state(width: usize, height: usize) -> MatrixImage<u8> {
+        MatrixImageBuilder::init()
+            .with_initial_value(EMPTY)
+            .with_height_and_width(height, width)
+            .build()
+    }
+    /// Next state of a cell given its current state, its number of burning neighbors and a uniform
+    /// number in [0, 1).
+    pub fn next_state(&self, state: u8, burning: usize, uniform: f64) -> u8 {
+        match state {
+            EMPTY if uniform < self.growth => TREE,
+            EMPTY => EMPTY,
+            TREE => {
+                let spared = (1.0 - self.ignition).powi(burning as i32) * (1.0 - self.lightning);
+                if uniform < 1.0 - spared { BURNING } else { TREE }
+            },
+            _ => EMPTY,
+        }
+    }
+    /// Applies the transitions to every cell, drawing from the streams of the cells at the given step.
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>, step: u64) -> Result<MatrixImage<u8>, MatrixError> {
+        let counts = matrix.hood_counts(self.hood_size, self.hood_type.per_cell()?, |state| *state == BURNING);
+        Ok(random::map_cells(matrix, self.seed, step, |matrix, point, rng: &mut Philox| {
+            let index = point.1 as usize * matrix.get_width() + point.0 as usize;
+            let state = matrix.get_data_ref()[index];
+            let burning = counts.get_data_ref()[index] - (state == BURNING) as usize;
+            self.next_state(state, burning, random::unit_f64(rng))
+        }))
+    }
+    pub fn census(matrix: &MatrixImage<u8>) -> Forest {
+        let mut forest = Forest::default();
+        for state in matrix.get_data_ref() {
+            match *state {
+                EMPTY => forest.empty += 1,
+                TREE => forest.trees += 1,
+                _ => forest.burning += 1,
+            }
+        }
+        forest
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+impl Rule<MatrixImage<u8>> for ForestFire {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        if self.series.is_empty() {
+            self.series.push(Self::census(state));
+        }
+        let next = self.next_generation(state, self.step)?;
+        self.step += 1;
+        self.series.push(Self::census(&next));
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        automata::forest_fire::{
+            BURNING,
+            EMPTY,
+            ForestFire,
+            TREE,
+        },
+        simulation::Rule,
+        traits::Matrix,
+    };
+
+    #[test]
+    fn fire_spreads_to_neighboring_trees() {
+        let forest = ForestFire::new(0.0, 0.0);
+        let mut matrix = ForestFire::initial_state(5, 1);
+        for (x, state) in [TREE, TREE, BURNING, TREE, EMPTY].into_iter().enumerate() {
+            matrix.edit_point((x as u32, 0), state).unwrap();
+        }
+        let next = forest.next_generation(&matrix, 0).unwrap();
+        assert_eq!(next.get_data_ref(), &vec![TREE, BURNING, EMPTY, BURNING, EMPTY]);
+        assert!(forest.clone().with_neighborhood(Neighborhood::Margolus, 1).next_generation(&next, 1).is_err());
+        assert_eq!(forest.next_generation(&next, 1).unwrap().get_data_ref(), &vec![BURNING, EMPTY, EMPTY, EMPTY, EMPTY]);
+        assert_eq!(ForestFire::new(0.0, 1.0).next_state(TREE, 0, 0.99), BURNING);
+        assert_eq!(ForestFire::new(0.5, 0.0).with_ignition(0.5).next_state(TREE, 1, 0.6), TREE);
+    }
+
+    #[test]
+    fn driven_forest_keeps_burning() {
+        let mut forest = ForestFire::new(0.05, 0.0005).with_seed(44);
+        let mut matrix = ForestFire::initial_state(64, 64);
+        for _ in 0..600 {
+            matrix = forest.step(&matrix).unwrap();
+        }
+        let series = forest.get_series_ref();
+        assert_eq!(series.len(), 601);
+        assert!(series.iter().all(|forest| forest.empty + forest.trees + forest.burning == 4096));
+        assert!(series[300..].iter().any(|forest| forest.burning > 0));
+        let density = series[300..].iter().map(|forest| forest.trees).sum::<usize>() as f64 / (301.0 * 4096.0);
+        assert!((0.2..0.6).contains(&density), "{density}");
+    }
+}
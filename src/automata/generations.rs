@@ -0,0 +1,215 @@
+//!   Generations automata, an extension of the life-like rules where an alive cell that doesn't
+//!   survive goes through a number of refractory states before dying. State 0 is dead, state 1 is
+//!   alive and states 2 to C-1 are dying; only alive cells are counted as neighbors. Rules are
+//!   written `S/B/C`, e.g. `/2/3` for Brian's Brain, or `B2/S/C3`.
+
+use core::{
+    fmt::{
+        self,
+        Display,
+    },
+    str::FromStr,
+};
+use alloc::{
+    string::String,
+    vec::Vec,
+};
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::{
+    as_flags,
+    next_dying,
+    parse_counts,
+    write_counts,
+};
+
+pub const DEAD: u8 = 0;
+pub const ALIVE: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Generations {
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+    states: u8,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    palette: Palette,
+}
+
+impl Generations {
+    /// Rule over the Moore neighborhood of size 1 with the given number of states, at least 2.
+    pub fn new(birth: &[usize], survival: &[usize], states: u8) -> Self {
+        let states = states.max(2);
+        let mut colors = vec![Rgba([0, 0, 0, 255])];
+        colors.extend(Palette::gradient(Rgba([255, 255, 255, 255]), Rgba([96, 0, 0, 255]), states as usize - 1).get_colors_ref());
+        Generations {
+            birth: as_flags(birth),
+            survival: as_flags(survival),
+            states,
+            hood_type: Neighborhood::Moore,
+            hood_size: 1,
+            palette: Palette::new(colors),
+        }
+    }
+    pub fn brians_brain() -> Self {
+        Self::new(&[2], &[], 3)
+    }
+    pub fn star_wars() -> Self {
+        Self::new(&[2], &[3, 4, 5], 4)
+    }
+    pub fn get_states(&self) -> u8 {
+        self.states
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    /// Next state of a cell given its current state and its number of alive neighbors.
+    pub fn next_state(&self, state: u8, count: usize) -> u8 {
+        match state {
+            DEAD if self.birth.get(count).copied().unwrap_or(false) => ALIVE,
+            DEAD => DEAD,
+            ALIVE if self.survival.get(count).copied().unwrap_or(false) => ALIVE,
+            dying => next_dying(dying, self.states),
+        }
+    }
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let hood_type = self.hood_type.per_cell()?;
+        let mut new_matrix = MatrixImageBuilder::init()
+            .with_initial_value(DEAD)
+            .with_height_and_width(matrix.get_height(), matrix.get_width())
+            .build();
+        for point_y in 0..matrix.get_height() as u32 {
+            for point_x in 0..matrix.get_width() as u32 {
+                let center = (point_x, point_y);
+                let state = matrix.get_point_value(center)?;
+                let mut count = matrix.hood_count(center, self.hood_size, hood_type, |value| *value == ALIVE)?;
+                if state == ALIVE {
+                    count -= 1;
+                }
+                new_matrix.edit_point(center, self.next_state(state, count))?;
+            }
+        }
+        Ok(new_matrix)
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+impl Rule<MatrixImage<u8>> for Generations {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        self.next_generation(state)
+    }
+}
+
+impl FromStr for Generations {
+    type Err = MatrixError;
+    /// Parses `S/B/C`, e.g. `345/2/4`, or `B{birth}/S{survival}/C{states}` in any order, where `G` is
+    /// also accepted in place of `C`.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let segments: Vec<&str> = rule.trim().split('/').collect();
+        if segments.len() != 3 {
+            return Err(MatrixError::InvalidRule(format!("{rule} should have three segments separated by '/'")));
+        }
+        let parse_states = |states: &str| {
+            states
+                .parse::<u8>()
+                .ok()
+                .filter(|states| *states >= 2)
+                .ok_or_else(|| MatrixError::InvalidRule(format!("{rule} should have between 2 and 255 states")))
+        };
+        let (mut birth, mut survival, mut states) = (None, None, None);
+        for segment in &segments {
+            match segment.chars().next() {
+                Some('B') | Some('b') => birth = Some(parse_counts(&segment[1..], rule)?),
+                Some('S') | Some('s') => survival = Some(parse_counts(&segment[1..], rule)?),
+                Some('C') | Some('c') | Some('G') | Some('g') => states = Some(parse_states(&segment[1..])?),
+                _ => {},
+            }
+        }
+        match (birth, survival, states) {
+            (Some(birth), Some(survival), Some(states)) => Ok(Self::new(&birth, &survival, states)),
+            (None, None, None) => Ok(Self::new(
+                &parse_counts(segments[1], rule)?,
+                &parse_counts(segments[0], rule)?,
+                parse_states(segments[2])?,
+            )),
+            _ => Err(MatrixError::InvalidRule(format!("{rule} mixes B/S/C and S/B/C notations"))),
+        }
+    }
+}
+
+impl Display for Generations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rule = String::new();
+        write_counts(&mut rule, &self.survival);
+        rule += "/";
+        write_counts(&mut rule, &self.birth);
+        write!(f, "{rule}/{}", self.states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        automata::generations::{
+            Generations,
+            ALIVE,
+            DEAD,
+        },
+        traits::Matrix,
+    };
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!("/2/3".parse::<Generations>().unwrap(), Generations::brians_brain());
+        assert_eq!("B2/S345/C4".parse::<Generations>().unwrap(), Generations::star_wars());
+        assert_eq!(Generations::star_wars().to_string(), "345/2/4");
+        assert!("/2/1".parse::<Generations>().is_err());
+        assert!("B2/S/3".parse::<Generations>().is_err());
+    }
+
+    #[test]
+    fn brians_brain_cells_go_through_refractory_state() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(DEAD).with_height_and_width(6, 6).build();
+        matrix.edit_point((2_u32, 2_u32), ALIVE).unwrap();
+        matrix.edit_point((3_u32, 2_u32), ALIVE).unwrap();
+        let rule = Generations::brians_brain();
+        let next = rule.next_generation(&matrix).unwrap();
+        assert_eq!(next.get_point_value((2_u32, 2_u32)).unwrap(), 2);
+        assert_eq!(next.get_point_value((2_u32, 1_u32)).unwrap(), ALIVE);
+        assert_eq!(next.get_point_value((3_u32, 3_u32)).unwrap(), ALIVE);
+        assert_eq!(rule.next_generation(&next).unwrap().get_point_value((2_u32, 2_u32)).unwrap(), DEAD);
+    }
+
+    #[test]
+    fn out_of_range_states_die() {
+        let rule = Generations::star_wars();
+        assert_eq!(rule.next_state(u8::MAX, 0), DEAD);
+        assert_eq!(rule.next_state(3, 0), DEAD);
+        let uninitialized: MatrixImage<u8> = MatrixImageBuilder::init().with_height_and_width(4, 4).build();
+        assert_eq!(uninitialized.get_point_value((1_u32, 1_u32)).unwrap(), u8::MAX);
+        let next = rule.next_generation(&uninitialized).unwrap();
+        assert!(next.get_data_ref().iter().all(|state| *state == DEAD));
+    }
+}
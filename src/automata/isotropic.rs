@@ -0,0 +1,318 @@
+//!   Isotropic non-totalistic two-state rules over the Moore neighborhood, written in Hensel
+//!   notation, e.g. `B2-a/S12` or `B3/S2-i34q`. Each neighbor count is followed by letters that
+//!   select configurations of the alive neighbors up to rotations and reflections, or by `-` and
+//!   the letters to exclude; a count without letters selects all of its configurations.
+//!
+//!   Neighbor configurations are encoded as masks with one bit per neighbor, clockwise from the
+//!   north: N is bit 0, NE bit 1, E bit 2, SE bit 3, S bit 4, SW bit 5, W bit 6 and NW bit 7.
+
+use core::{
+    fmt::{
+        self,
+        Display,
+    },
+    str::FromStr,
+};
+use alloc::{
+    string::String,
+    vec::Vec,
+};
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::{
+    MOORE_OFFSETS,
+    life_like::{
+        ALIVE,
+        DEAD,
+    },
+};
+
+/// Letters of the configurations of each neighbor count, in Hensel's order.
+pub const LETTERS: [&str; 9] = ["", "ce", "ceaikn", "ceaiknjqry", "ceaiknjqrytwz", "ceaiknjqry", "ceaikn", "ce", ""];
+
+/// Representative configuration of each letter for 1 to 4 alive neighbors, the configurations
+/// for 5 to 7 alive neighbors being the complements of those for 3 to 1 with the same letter.
+const REPRESENTATIVES: [&[(char, &[u8])]; 4] = [
+    &[('c', &[1]), ('e', &[0])],
+    &[('c', &[1, 3]), ('e', &[0, 2]), ('a', &[0, 1]), ('i', &[0, 4]), ('k', &[0, 3]), ('n', &[1, 5])],
+    &[
+        ('c', &[1, 3, 5]), ('e', &[0, 2, 4]), ('a', &[0, 1, 2]), ('i', &[0, 1, 7]), ('k', &[0, 2, 5]),
+        ('n', &[0, 1, 3]), ('j', &[0, 1, 6]), ('q', &[0, 1, 5]), ('r', &[0, 1, 4]), ('y', &[0, 3, 5]),
+    ],
+    &[
+        ('c', &[1, 3, 5, 7]), ('e', &[0, 2, 4, 6]), ('a', &[0, 1, 2, 3]), ('i', &[0, 1, 3, 4]), ('k', &[0, 1, 3, 6]),
+        ('n', &[0, 1, 3, 7]), ('j', &[0, 1, 4, 6]), ('q', &[0, 1, 2, 5]), ('r', &[0, 1, 2, 4]), ('y', &[0, 1, 3, 5]),
+        ('t', &[0, 1, 4, 7]), ('w', &[0, 1, 5, 6]), ('z', &[0, 1, 4, 5]),
+    ],
+];
+
+/// Rotation of the neighbor mask by a number of eighths of a turn, clockwise.
+fn rotate(mask: u8, eighths: u32) -> u8 {
+    mask.rotate_left(eighths)
+}
+
+/// Reflection of the neighbor mask across the north-south axis.
+fn reflect(mask: u8) -> u8 {
+    (0..8).filter(|bit| mask & (1 << bit) != 0).fold(0, |reflected, bit| reflected | 1 << ((8 - bit) % 8))
+}
+
+/// Hensel class of a neighbor mask, as the number of alive neighbors and the letter of the
+/// configuration, `None` for 0 and 8 alive neighbors.
+pub fn classify(mask: u8) -> (u8, Option<char>) {
+    let count = mask.count_ones() as u8;
+    let (representatives, complement) = match count {
+        1..=4 => (REPRESENTATIVES[count as usize - 1], false),
+        5..=7 => (REPRESENTATIVES[7 - count as usize], true),
+        _ => return (count, None),
+    };
+    let mask = if complement { !mask } else { mask };
+    let letter = representatives.iter().find_map(|(letter, bits)| {
+        let representative = bits.iter().fold(0_u8, |representative, bit| representative | 1 << bit);
+        (0..8)
+            .step_by(2)
+            .any(|eighths| {
+                let rotated = rotate(representative, eighths);
+                rotated == mask || reflect(rotated) == mask
+            })
+            .then_some(*letter)
+    });
+    (count, letter)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Isotropic {
+    /// Birth and survival flags indexed by neighbor mask.
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+    palette: Palette,
+}
+
+impl Isotropic {
+    /// Rule from the Hensel classes that give birth to a dead cell and keep an alive cell alive.
+    pub fn new(birth: &[(u8, Option<char>)], survival: &[(u8, Option<char>)]) -> Self {
+        let flags = |classes: &[(u8, Option<char>)]| (0..=u8::MAX).map(|mask| classes.contains(&classify(mask))).collect();
+        Isotropic {
+            birth: flags(birth),
+            survival: flags(survival),
+            palette: Palette::new(vec![Rgba([0, 0, 0, 255]), Rgba([0, 255, 0, 255])]),
+        }
+    }
+    pub fn with_colors(mut self, alive: Rgba<u8>, dead: Rgba<u8>) -> Self {
+        self.palette = Palette::new(vec![dead, alive]);
+        self
+    }
+    pub fn is_birth(&self, mask: u8) -> bool {
+        self.birth[mask as usize]
+    }
+    pub fn is_survival(&self, mask: u8) -> bool {
+        self.survival[mask as usize]
+    }
+    /// Mask of the alive neighbors of the point.
+    pub fn neighbor_mask(matrix: &MatrixImage<u8>, point: (u32, u32)) -> Result<u8, MatrixError> {
+        let mut mask = 0;
+        for (bit, offset) in MOORE_OFFSETS.iter().enumerate() {
+            if matrix.get_point_value(matrix.offset_point(point, *offset))? == ALIVE {
+                mask |= 1 << bit;
+            }
+        }
+        Ok(mask)
+    }
+    pub fn next_state(&self, state: u8, mask: u8) -> u8 {
+        let alive = if state == ALIVE {
+            self.is_survival(mask)
+        } else {
+            self.is_birth(mask)
+        };
+        if alive { ALIVE } else { DEAD }
+    }
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let mut new_matrix = MatrixImageBuilder::init()
+            .with_initial_value(DEAD)
+            .with_height_and_width(matrix.get_height(), matrix.get_width())
+            .build();
+        for point_y in 0..matrix.get_height() as u32 {
+            for point_x in 0..matrix.get_width() as u32 {
+                let center = (point_x, point_y);
+                let mask = Self::neighbor_mask(matrix, center)?;
+                new_matrix.edit_point(center, self.next_state(matrix.get_point_value(center)?, mask))?;
+            }
+        }
+        Ok(new_matrix)
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+impl Rule<MatrixImage<u8>> for Isotropic {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        self.next_generation(state)
+    }
+}
+
+/// Parses the classes of a `B` or `S` segment, e.g. `2-a` or `34q`.
+fn parse_classes(segment: &str, rule: &str) -> Result<Vec<(u8, Option<char>)>, MatrixError> {
+    let invalid = |reason: &str| MatrixError::InvalidRule(format!("{rule} {reason}"));
+    let mut classes = Vec::new();
+    let mut characters = segment.chars().peekable();
+    while let Some(count) = characters.next() {
+        let count = count
+            .to_digit(10)
+            .filter(|count| *count <= 8)
+            .ok_or_else(|| invalid("has a count outside of 0 to 8"))? as usize;
+        let negated = characters.next_if_eq(&'-').is_some();
+        let mut letters = String::new();
+        while let Some(letter) = characters.next_if(|letter| letter.is_ascii_lowercase()) {
+            if !LETTERS[count].contains(letter) {
+                return Err(invalid("has a letter that doesn't apply to its count"));
+            }
+            letters.push(letter);
+        }
+        if negated && letters.is_empty() {
+            return Err(invalid("has a '-' without letters"));
+        }
+        if LETTERS[count].is_empty() {
+            classes.push((count as u8, None));
+        }
+        for letter in LETTERS[count].chars() {
+            if letters.is_empty() || letters.contains(letter) != negated {
+                classes.push((count as u8, Some(letter)));
+            }
+        }
+    }
+    Ok(classes)
+}
+
+/// Writes the selected classes of every count, with the shortest of the positive and negated letters.
+fn write_classes(rule: &mut String, flags: &[bool]) {
+    for (count, letters) in LETTERS.iter().enumerate() {
+        let is_selected = |letter: Option<char>| (0..=u8::MAX).any(|mask| flags[mask as usize] && classify(mask) == (count as u8, letter));
+        if letters.is_empty() {
+            if is_selected(None) {
+                *rule += &format!("{count}");
+            }
+            continue;
+        }
+        let included: String = letters.chars().filter(|letter| is_selected(Some(*letter))).collect();
+        let excluded: String = letters.chars().filter(|letter| !is_selected(Some(*letter))).collect();
+        if included.is_empty() {
+            continue;
+        }
+        *rule += &format!("{count}");
+        if !excluded.is_empty() {
+            if excluded.len() < included.len() {
+                *rule += &format!("-{excluded}");
+            } else {
+                *rule += &included;
+            }
+        }
+    }
+}
+
+impl FromStr for Isotropic {
+    type Err = MatrixError;
+    /// Parses `B{classes}/S{classes}`, in any order and case of the `B` and `S` prefixes.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let segments: Vec<&str> = rule.trim().split('/').collect();
+        let (mut birth, mut survival) = (None, None);
+        for segment in &segments {
+            match segment.chars().next() {
+                Some('B') | Some('b') => birth = Some(parse_classes(&segment[1..], rule)?),
+                Some('S') | Some('s') => survival = Some(parse_classes(&segment[1..], rule)?),
+                _ => return Err(MatrixError::InvalidRule(format!("{rule} should be written as B.../S..."))),
+            }
+        }
+        match (birth, survival, segments.len()) {
+            (Some(birth), Some(survival), 2) => Ok(Self::new(&birth, &survival)),
+            _ => Err(MatrixError::InvalidRule(format!("{rule} should be written as B.../S..."))),
+        }
+    }
+}
+
+impl Display for Isotropic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rule = String::from("B");
+        write_classes(&mut rule, &self.birth);
+        rule += "/S";
+        write_classes(&mut rule, &self.survival);
+        write!(f, "{rule}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        traits::Matrix,
+        automata::{
+            LifeLike,
+            isotropic::{
+                Isotropic,
+                LETTERS,
+                classify,
+            },
+        },
+    };
+    use alloc::{
+        string::ToString,
+        vec::Vec,
+    };
+
+    #[test]
+    fn classification_covers_all_masks() {
+        let mut sizes = Vec::new();
+        for (count, letters) in LETTERS.iter().enumerate() {
+            let letters: Vec<Option<char>> = if letters.is_empty() { vec![None] } else { letters.chars().map(Some).collect() };
+            for letter in letters {
+                sizes.push((0..=u8::MAX).filter(|mask| classify(*mask) == (count as u8, letter)).count());
+            }
+        }
+        assert_eq!(sizes.len(), 51);
+        assert!(sizes.iter().all(|size| [1, 2, 4, 8].contains(size)));
+        assert_eq!(sizes.iter().sum::<usize>(), 256);
+        assert_eq!(classify(0b0001_0001), (2, Some('i')));
+        assert_eq!(classify(0b0010_0010), (2, Some('n')));
+        assert_eq!(classify(0b1001_0011), (4, Some('t')));
+        assert_eq!(classify(!0b0000_0010), (7, Some('c')));
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let rule: Isotropic = "B2-a/S12".parse().unwrap();
+        assert!(rule.is_birth(0b0000_0101));
+        assert!(!rule.is_birth(0b0000_0011));
+        assert!(rule.is_survival(0b0000_0001));
+        assert_eq!(rule.to_string(), "B2-a/S12");
+        assert_eq!("B3/S2-i34q".parse::<Isotropic>().unwrap().to_string(), "B3/S2-i34q");
+        assert_eq!("B2ce/S".parse::<Isotropic>().unwrap().to_string(), "B2ce/S");
+        assert!("B2x/S".parse::<Isotropic>().is_err());
+        assert!("B9/S".parse::<Isotropic>().is_err());
+    }
+
+    #[test]
+    fn totalistic_rules_match_life_like() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(12, 12).build();
+        for (x, y) in [(1_u32, 0_u32), (2, 1), (0, 2), (1, 2), (2, 2), (6, 6), (7, 6), (8, 6)] {
+            matrix.edit_point((x, y), 1).unwrap();
+        }
+        let isotropic: Isotropic = "B3/S23".parse().unwrap();
+        let life = LifeLike::conway();
+        let mut expected = matrix.clone();
+        for _ in 0..8 {
+            expected = life.next_generation(&expected).unwrap();
+            matrix = isotropic.next_generation(&matrix).unwrap();
+            assert_eq!(matrix, expected);
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//!   Larger-than-Life automata, outer-totalistic rules over neighborhoods of large radius where
+//!   births and survivals happen within ranges of alive neighbor counts. Rules are written in the
+//!   `R,C,M,S,B,N` notation, e.g. `R5,C0,M1,S34..58,B34..45,NM` for Bosco's rule: the radius, the
+//!   number of states as in Generations (0 and 2 both meaning two states), whether the middle cell
+//!   counts itself, the survival and birth ranges and the neighborhood, `NM` for Moore or `NN` for
+//!   von Neumann.
+
+use core::{
+    fmt::{
+        self,
+        Display,
+    },
+    ops::RangeInclusive,
+    str::FromStr,
+};
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::next_dying;
+
+pub const DEAD: u8 = 0;
+pub const ALIVE: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LargerThanLife {
+    radius: usize,
+    states: u8,
+    middle: bool,
+    survival: RangeInclusive<usize>,
+    birth: RangeInclusive<usize>,
+    hood_type: Neighborhood,
+    palette: Palette,
+}
+
+impl LargerThanLife {
+    /// Two-state rule over the Moore neighborhood of the given radius, middle cell included.
+    pub fn new(radius: usize, survival: RangeInclusive<usize>, birth: RangeInclusive<usize>) -> Self {
+        LargerThanLife {
+            radius,
+            states: 2,
+            middle: true,
+            survival,
+            birth,
+            hood_type: Neighborhood::Moore,
+            palette: Palette::new(vec![Rgba([0, 0, 0, 255]), Rgba([0, 255, 0, 255])]),
+        }
+    }
+    pub fn bosco() -> Self {
+        Self::new(5, 34..=58, 34..=45)
+    }
+    pub fn majority() -> Self {
+        Self::new(4, 41..=81, 41..=81)
+    }
+    pub fn waffle() -> Self {
+        Self::new(7, 100..=200, 75..=170)
+    }
+    pub fn globe() -> Self {
+        Self::new(8, 163..=223, 74..=252).with_middle(false)
+    }
+    pub fn get_radius(&self) -> usize {
+        self.radius
+    }
+    pub fn get_states(&self) -> u8 {
+        self.states
+    }
+    /// Number of states, at least 2; alive cells that don't survive go through the dying states
+    /// 2 to `states - 1` as in Generations. Replaces the palette with one color per state.
+    pub fn with_states(mut self, states: u8) -> Self {
+        self.states = states.max(2);
+        self.palette = if self.states == 2 {
+            Palette::new(vec![Rgba([0, 0, 0, 255]), Rgba([0, 255, 0, 255])])
+        } else {
+            let mut colors = vec![Rgba([0, 0, 0, 255])];
+            colors.extend(Palette::gradient(Rgba([255, 255, 255, 255]), Rgba([96, 0, 0, 255]), self.states as usize - 1).get_colors_ref());
+            Palette::new(colors)
+        };
+        self
+    }
+    /// Whether an alive cell counts itself among its alive neighbors.
+    pub fn with_middle(mut self, middle: bool) -> Self {
+        self.middle = middle;
+        self
+    }
+    /// Moore or von Neumann neighborhood of the radius. The Margolus block, which has no radius, is
+    /// ignored and the current neighborhood kept.
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood) -> Self {
+        if let Ok(hood_type) = hood_type.per_cell() {
+            self.hood_type = hood_type;
+        }
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    /// Next state of a cell given its current state and its number of alive neighbors.
+    pub fn next_state(&self, state: u8, count: usize) -> u8 {
+        match state {
+            DEAD if self.birth.contains(&count) => ALIVE,
+            DEAD => DEAD,
+            ALIVE if self.survival.contains(&count) => ALIVE,
+            dying => next_dying(dying, self.states),
+        }
+    }
+    /// Applies the rule synchronously to every cell, counting the neighborhoods of all cells at once
+    /// with `hood_counts`.
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let counts = matrix.hood_counts(self.radius, self.hood_type, |value| *value == ALIVE);
+        let mut new_matrix = MatrixImageBuilder::init()
+            .with_initial_value(DEAD)
+            .with_height_and_width(matrix.get_height(), matrix.get_width())
+            .build();
+        for point_y in 0..matrix.get_height() as u32 {
+            for point_x in 0..matrix.get_width() as u32 {
+                let center = (point_x, point_y);
+                let state = matrix.get_point_value(center)?;
+                let mut count = counts.get_point_value(center)?;
+                if state == ALIVE && !self.middle {
+                    count -= 1;
+                }
+                new_matrix.edit_point(center, self.next_state(state, count))?;
+            }
+        }
+        Ok(new_matrix)
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+impl Rule<MatrixImage<u8>> for LargerThanLife {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        self.next_generation(state)
+    }
+}
+
+impl FromStr for LargerThanLife {
+    type Err = MatrixError;
+    /// Parses `R{radius},C{states},M{0|1},S{min}..{max},B{min}..{max},N{M|N}`, in any case. The
+    /// states, middle and neighborhood may be omitted and default to `C0`, `M0` and `NM`.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| MatrixError::InvalidRule(format!("{rule} {reason}"));
+        let parse_number = |number: &str| number.trim().parse::<usize>().map_err(|_| invalid("has an invalid number"));
+        let parse_range = |range: &str| -> Result<RangeInclusive<usize>, MatrixError> {
+            let (min, max) = range.split_once("..").ok_or_else(|| invalid("has a range without '..'"))?;
+            Ok(parse_number(min)?..=parse_number(max)?)
+        };
+        let (mut radius, mut states, mut middle, mut survival, mut birth, mut hood_type) = (None, 2, false, None, None, Neighborhood::Moore);
+        for segment in rule.trim().split(',') {
+            let segment = segment.trim();
+            let value = segment.get(1..).unwrap_or("");
+            match segment.chars().next().map(|key| key.to_ascii_uppercase()) {
+                Some('R') => radius = Some(parse_number(value)?),
+                Some('C') => states = u8::try_from(parse_number(value)?).map_err(|_| invalid("should have at most 255 states"))?,
+                Some('M') => middle = match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(invalid("should have M0 or M1")),
+                },
+                Some('S') => survival = Some(parse_range(value)?),
+                Some('B') => birth = Some(parse_range(value)?),
+                Some('N') => hood_type = match value {
+                    "M" | "m" => Neighborhood::Moore,
+                    "N" | "n" => Neighborhood::VonNeumann,
+                    _ => return Err(invalid("should have the NM or NN neighborhood")),
+                },
+                _ => return Err(invalid("has an unknown segment")),
+            }
+        }
+        match (radius, survival, birth) {
+            (Some(radius), Some(survival), Some(birth)) => Ok(Self::new(radius, survival, birth)
+                .with_states(states)
+                .with_middle(middle)
+                .with_neighborhood(hood_type)),
+            _ => Err(invalid("should have R, S and B segments")),
+        }
+    }
+}
+
+impl Display for LargerThanLife {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let states = if self.states == 2 { 0 } else { self.states };
+        let hood_type = if self.hood_type == Neighborhood::VonNeumann { "N" } else { "M" };
+        write!(
+            f,
+            "R{},C{states},M{},S{}..{},B{}..{},N{hood_type}",
+            self.radius,
+            self.middle as u8,
+            self.survival.start(),
+            self.survival.end(),
+            self.birth.start(),
+            self.birth.end(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        Neighborhood,
+        automata::{
+            LifeLike,
+            larger_than_life::{
+                LargerThanLife,
+                ALIVE,
+                DEAD,
+            },
+        },
+        traits::Matrix,
+    };
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!("R5,C0,M1,S34..58,B34..45,NM".parse::<LargerThanLife>().unwrap(), LargerThanLife::bosco());
+        assert_eq!("r8,m0,s163..223,b74..252".parse::<LargerThanLife>().unwrap(), LargerThanLife::globe());
+        assert_eq!(LargerThanLife::waffle().to_string(), "R7,C0,M1,S100..200,B75..170,NM");
+        let rule = LargerThanLife::new(3, 2..=9, 4..=6).with_states(5).with_neighborhood(Neighborhood::VonNeumann);
+        assert_eq!(rule.to_string().parse::<LargerThanLife>().unwrap(), rule);
+        assert!("R5,C0,M1,S34-58,B34..45,NM".parse::<LargerThanLife>().is_err());
+        assert!("R5,C0,M1,B34..45,NM".parse::<LargerThanLife>().is_err());
+        assert!("R5,C0,M1,S34..58,B34..45,NC".parse::<LargerThanLife>().is_err());
+        assert!("R5,C0,M1,S34..58,B34..45,NB".parse::<LargerThanLife>().is_err());
+    }
+
+    #[test]
+    fn radius_one_matches_life_like() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(DEAD).with_height_and_width(12, 12).build();
+        for (x, y) in [(1_u32, 0_u32), (2, 1), (0, 2), (1, 2), (2, 2), (6, 6), (7, 6), (8, 6), (7, 9), (8, 9)] {
+            matrix.edit_point((x, y), ALIVE).unwrap();
+        }
+        let larger_than_life: LargerThanLife = "R1,C0,M0,S2..3,B3..3,NM".parse().unwrap();
+        let life_like = LifeLike::conway();
+        let (mut expected, mut actual) = (matrix.clone(), matrix);
+        for _ in 0..8 {
+            expected = life_like.next_generation(&expected).unwrap();
+            actual = larger_than_life.next_generation(&actual).unwrap();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn out_of_range_states_die() {
+        let rule = LargerThanLife::bosco().with_states(4);
+        assert_eq!(rule.next_state(u8::MAX, 0), DEAD);
+        assert_eq!(rule.next_state(2, 0), 3);
+        let uninitialized: MatrixImage<u8> = MatrixImageBuilder::init().with_height_and_width(6, 6).build();
+        let next = rule.next_generation(&uninitialized).unwrap();
+        assert!(next.get_data_ref().iter().all(|state| *state == DEAD));
+        let von_neumann = rule.with_neighborhood(Neighborhood::VonNeumann);
+        assert_eq!(von_neumann.clone().with_neighborhood(Neighborhood::Margolus), von_neumann);
+        assert_eq!(von_neumann.to_string().parse::<LargerThanLife>().unwrap(), von_neumann);
+    }
+}
@@ -0,0 +1,202 @@
+//!   Outer-totalistic two-state automata described by a B/S rulestring, e.g. `B3/S23` for Conway's
+//!   Game of Life, `B36/S23` for HighLife or `B3678/S34678` for Day & Night. The number of alive
+//!   cells is counted over any `Neighborhood` and radius, center excluded.
+
+use core::{
+    fmt::{
+        self,
+        Display,
+    },
+    str::FromStr,
+};
+use alloc::{
+    string::String,
+    vec::Vec,
+};
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::{
+    as_flags,
+    parse_counts,
+    write_counts,
+};
+
+pub const DEAD: u8 = 0;
+pub const ALIVE: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LifeLike {
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    palette: Palette,
+}
+
+impl LifeLike {
+    /// Rule from the alive neighbor counts that give birth to a dead cell and keep an alive cell alive,
+    /// over the Moore neighborhood of size 1.
+    pub fn new(birth: &[usize], survival: &[usize]) -> Self {
+        LifeLike {
+            birth: as_flags(birth),
+            survival: as_flags(survival),
+            hood_type: Neighborhood::Moore,
+            hood_size: 1,
+            palette: Palette::new(vec![Rgba([0, 0, 0, 255]), Rgba([0, 255, 0, 255])]),
+        }
+    }
+    pub fn conway() -> Self {
+        Self::new(&[3], &[2, 3])
+    }
+    pub fn high_life() -> Self {
+        Self::new(&[3, 6], &[2, 3])
+    }
+    pub fn day_and_night() -> Self {
+        Self::new(&[3, 6, 7, 8], &[3, 4, 6, 7, 8])
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_colors(mut self, alive: Rgba<u8>, dead: Rgba<u8>) -> Self {
+        self.palette = Palette::new(vec![dead, alive]);
+        self
+    }
+    pub fn is_birth(&self, count: usize) -> bool {
+        self.birth.get(count).copied().unwrap_or(false)
+    }
+    pub fn is_survival(&self, count: usize) -> bool {
+        self.survival.get(count).copied().unwrap_or(false)
+    }
+    /// Next state of a cell given its current state and its number of alive neighbors.
+    pub fn next_state(&self, state: u8, count: usize) -> u8 {
+        let alive = if state == ALIVE {
+            self.is_survival(count)
+        } else {
+            self.is_birth(count)
+        };
+        if alive { ALIVE } else { DEAD }
+    }
+    /// Applies the rule synchronously to every cell. Cells with any value other than `ALIVE` are dead.
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let hood_type = self.hood_type.per_cell()?;
+        let mut new_matrix = MatrixImageBuilder::init()
+            .with_initial_value(DEAD)
+            .with_height_and_width(matrix.get_height(), matrix.get_width())
+            .build();
+        for point_y in 0..matrix.get_height() as u32 {
+            for point_x in 0..matrix.get_width() as u32 {
+                let center = (point_x, point_y);
+                let state = matrix.get_point_value(center)?;
+                let mut count = matrix.hood_count(center, self.hood_size, hood_type, |value| *value == ALIVE)?;
+                if state == ALIVE {
+                    count -= 1;
+                }
+                new_matrix.edit_point(center, self.next_state(state, count))?;
+            }
+        }
+        Ok(new_matrix)
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+impl Rule<MatrixImage<u8>> for LifeLike {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        self.next_generation(state)
+    }
+}
+
+impl FromStr for LifeLike {
+    type Err = MatrixError;
+    /// Parses `B{birth}/S{survival}` in any order and case, or the older `{survival}/{birth}` notation.
+    /// Counts are single digits, or comma separated numbers for larger neighborhoods, e.g. `B10,11/S9,10,11`.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let segments: Vec<&str> = rule.trim().split('/').collect();
+        if segments.len() != 2 {
+            return Err(MatrixError::InvalidRule(format!("{rule} should have two segments separated by '/'")));
+        }
+        let (mut birth, mut survival) = (None, None);
+        for segment in &segments {
+            match segment.chars().next() {
+                Some('B') | Some('b') => birth = Some(parse_counts(&segment[1..], rule)?),
+                Some('S') | Some('s') => survival = Some(parse_counts(&segment[1..], rule)?),
+                _ => {},
+            }
+        }
+        match (birth, survival) {
+            (Some(birth), Some(survival)) => Ok(Self::new(&birth, &survival)),
+            (None, None) => Ok(Self::new(&parse_counts(segments[1], rule)?, &parse_counts(segments[0], rule)?)),
+            _ => Err(MatrixError::InvalidRule(format!("{rule} mixes B/S and S/B notations"))),
+        }
+    }
+}
+
+impl Display for LifeLike {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rule = String::from("B");
+        write_counts(&mut rule, &self.birth);
+        rule += "/S";
+        write_counts(&mut rule, &self.survival);
+        write!(f, "{rule}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        Neighborhood,
+        automata::life_like::{
+            LifeLike,
+            ALIVE,
+            DEAD,
+        },
+        traits::Matrix,
+    };
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!("B36/S23".parse::<LifeLike>().unwrap(), LifeLike::high_life());
+        assert_eq!("s34678/b3678".parse::<LifeLike>().unwrap(), LifeLike::day_and_night());
+        assert_eq!("23/3".parse::<LifeLike>().unwrap(), LifeLike::conway());
+        assert_eq!(LifeLike::day_and_night().to_string(), "B3678/S34678");
+        assert_eq!("B10,12/S9".parse::<LifeLike>().unwrap().to_string(), "B10,12/S9");
+        assert!("B3/S2x".parse::<LifeLike>().is_err());
+        assert!("B3".parse::<LifeLike>().is_err());
+    }
+
+    #[test]
+    fn blinker_oscillates() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(DEAD).with_height_and_width(5, 5).build();
+        for x in 1..4_u32 {
+            matrix.edit_point((x, 2), ALIVE).unwrap();
+        }
+        let rule = LifeLike::conway();
+        let next = rule.next_generation(&matrix).unwrap();
+        for y in 1..4_u32 {
+            assert_eq!(next.get_point_value((2, y)).unwrap(), ALIVE);
+        }
+        assert_eq!(next.get_point_value((1_u32, 2_u32)).unwrap(), DEAD);
+        assert_eq!(rule.next_generation(&next).unwrap(), matrix);
+        let von_neumann = LifeLike::new(&[2], &[2]).with_neighborhood(Neighborhood::VonNeumann, 1);
+        assert_eq!(von_neumann.next_generation(&matrix).unwrap().get_point_value((2_u32, 1_u32)).unwrap(), DEAD);
+        assert_eq!(von_neumann.next_generation(&matrix).unwrap().get_point_value((2_u32, 2_u32)).unwrap(), ALIVE);
+        assert!(rule.with_neighborhood(Neighborhood::Margolus, 1).next_generation(&matrix).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//!   Block cellular automata over the Margolus neighborhood. The lattice is partitioned into 2×2
+//!   blocks, offset by one cell on every other step, and a block rule replaces every block at once.
+//!   Cells are alive or dead, any non-zero state being alive, and a block is encoded as the sum of
+//!   1 for its top left cell, 2 for the top right, 4 for the bottom left and 8 for the bottom right,
+//!   so that a rule is a table of the 16 blocks. Rules are written in the MCell notation, e.g.
+//!   `MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15` for the Billiard Ball Machine.
+//!
+//!   A rule whose table is a permutation of the blocks is reversible: its inverse table undoes a
+//!   step, so that a run can be stepped backwards to its initial lattice.
+
+use core::{
+    fmt::{
+        self,
+        Display,
+    },
+    str::FromStr,
+};
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    palette::Palette,
+    simulation::Rule,
+    error::MatrixError,
+};
+
+pub const DEAD: u8 = 0;
+pub const ALIVE: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Margolus {
+    table: [u8; 16],
+    step: u64,
+    palette: Palette,
+}
+
+impl Margolus {
+    /// Rule replacing every block by its entry in the table, starting with the blocks aligned on
+    /// even coordinates.
+    pub fn new(table: [u8; 16]) -> Self {
+        Margolus {
+            table: table.map(|block| block & 15),
+            step: 0,
+            palette: Palette::new(vec![Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255])]),
+        }
+    }
+    /// Balls travel diagonally and bounce off each other, conserving their number.
+    pub fn billiard_ball_machine() -> Self {
+        Self::new([0, 8, 4, 3, 2, 5, 9, 7, 1, 6, 10, 11, 12, 13, 14, 15])
+    }
+    /// Blocks that don't hold exactly two alive cells are complemented, then rotated by a half turn
+    /// when they held three.
+    pub fn critters() -> Self {
+        Self::new([15, 14, 13, 3, 11, 5, 6, 1, 7, 9, 10, 2, 12, 4, 8, 0])
+    }
+    /// Blocks that are all dead or all alive are complemented.
+    pub fn tron() -> Self {
+        Self::new([15, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 0])
+    }
+    /// Grains fall down and slide off the top of piles, an irreversible rule. There is no floor on
+    /// the wrapped lattice, where grains fall through the bottom edge back to the top.
+    pub fn sand() -> Self {
+        Self::new([0, 4, 8, 12, 4, 12, 12, 13, 8, 12, 12, 14, 12, 13, 14, 15])
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_table_ref(&self) -> &[u8; 16] {
+        &self.table
+    }
+    /// Number of steps taken, whose parity is the phase of the partition of the next step.
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+    /// Whether the table is a permutation of the blocks, so that every step can be undone.
+    pub fn is_reversible(&self) -> bool {
+        let mut seen = [false; 16];
+        self.table.iter().all(|block| !core::mem::replace(&mut seen[*block as usize], true))
+    }
+    /// Rule undoing the steps of this one, failing when the rule isn't reversible.
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        if !self.is_reversible() {
+            return Err(MatrixError::InvalidRule(format!("{self} is not reversible")));
+        }
+        let mut table = [0; 16];
+        for (block, image) in self.table.iter().enumerate() {
+            table[*image as usize] = block as u8;
+        }
+        Ok(Margolus { table, step: self.step, palette: self.palette.clone() })
+    }
+    /// Applies the table to every block of the partition of the given phase.
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>, phase: u64) -> Result<MatrixImage<u8>, MatrixError> {
+        apply(&self.table, matrix, phase)
+    }
+    /// Undoes the last step taken as a `Rule`, from the lattice it returned.
+    pub fn step_back(&mut self, matrix: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let inverse = self.inverse()?;
+        if self.step == 0 {
+            return Err(MatrixError::InvalidRule(format!("{self} has no step to undo")));
+        }
+        self.step -= 1;
+        apply(&inverse.table, matrix, self.step)
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+}
+
+fn apply(table: &[u8; 16], matrix: &MatrixImage<u8>, phase: u64) -> Result<MatrixImage<u8>, MatrixError> {
+    matrix.map_blocks(phase, |cells| {
+        let block = cells.iter().enumerate().fold(0, |block, (bit, cell)| block | (((*cell != DEAD) as u8) << bit));
+        let image = table[block as usize];
+        [0, 1, 2, 3].map(|bit| (image >> bit) & 1)
+    })
+}
+
+impl Rule<MatrixImage<u8>> for Margolus {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let next = self.next_generation(state, self.step)?;
+        self.step += 1;
+        Ok(next)
+    }
+}
+
+impl FromStr for Margolus {
+    type Err = MatrixError;
+    /// Parses `MS,D{16 blocks separated by ';'}`, the `MS,D` prefix being optional.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || MatrixError::InvalidRule(format!("{rule} should list the images of the 16 blocks, from 0 to 15"));
+        let trimmed = rule.trim();
+        let blocks = trimmed.strip_prefix("MS,D").or_else(|| trimmed.strip_prefix("ms,d")).unwrap_or(trimmed);
+        let blocks: Vec<u8> = blocks
+            .split(';')
+            .map(|block| block.trim().parse::<u8>().ok().filter(|block| *block < 16).ok_or_else(invalid))
+            .collect::<Result<_, _>>()?;
+        let table: [u8; 16] = blocks.try_into().map_err(|_| invalid())?;
+        Ok(Self::new(table))
+    }
+}
+
+impl Display for Margolus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MS,D")?;
+        for (block, image) in self.table.iter().enumerate() {
+            if block > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{image}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        Neighborhood,
+        automata::margolus::{
+            ALIVE,
+            Margolus,
+        },
+        random,
+        simulation::Rule,
+        traits::Matrix,
+    };
+
+    #[test]
+    fn block_rules_and_their_inverses() {
+        let critters = Margolus::critters();
+        assert!(critters.is_reversible());
+        assert!(Margolus::billiard_ball_machine().is_reversible());
+        assert!(Margolus::tron().is_reversible());
+        assert!(!Margolus::sand().is_reversible());
+        assert!(Margolus::sand().inverse().is_err());
+        let inverse = critters.inverse().unwrap();
+        assert!((0..16).all(|block| inverse.get_table_ref()[critters.get_table_ref()[block] as usize] == block as u8));
+        let bbm: Margolus = "MS,D0;8;4;3;2;5;9;7;1;6;10;11;12;13;14;15".parse().unwrap();
+        assert_eq!(bbm, Margolus::billiard_ball_machine());
+        assert_eq!(format!("{bbm}").parse::<Margolus>().unwrap(), bbm);
+        assert!("MS,D0;8;4".parse::<Margolus>().is_err());
+        let odd: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(3, 4).build();
+        assert!(bbm.next_generation(&odd, 0).is_err());
+        let mut lattice: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(4, 4).build();
+        lattice.edit_point((3_u32, 3_u32), ALIVE).unwrap();
+        assert_eq!(lattice.get_lattice_neighborhood((2_u32, 3_u32), 5, Neighborhood::Margolus), vec![(2, 2), (3, 2), (2, 3), (3, 3)]);
+        assert_eq!(lattice.hood_counts(1, Neighborhood::Margolus, |cell| *cell == ALIVE).get_data_ref().iter().sum::<usize>(), 4);
+        assert_eq!(bbm.next_generation(&lattice, 1).unwrap().get_point_value((0_u32, 0_u32)).unwrap(), ALIVE);
+    }
+
+    #[test]
+    fn reversible_runs_step_back_to_their_initial_lattice() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init()
+            .with_height_and_width(16, 16)
+            .with_seeded_generator(46, |rng| (random::unit_f64(rng) < 0.3) as u8)
+            .build();
+        let initial = matrix.clone();
+        let mut critters = Margolus::critters();
+        for _ in 0..50 {
+            matrix = critters.step(&matrix).unwrap();
+        }
+        assert_ne!(matrix, initial);
+        for _ in 0..50 {
+            matrix = critters.step_back(&matrix).unwrap();
+        }
+        assert_eq!(matrix, initial);
+        assert!(critters.step_back(&matrix).is_err());
+        let mut sand = Margolus::sand();
+        let mut column: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(8, 4).build();
+        column.edit_point((1_u32, 0), ALIVE).unwrap();
+        for _ in 0..7 {
+            column = sand.step(&column).unwrap();
+        }
+        assert_eq!(column.get_point_value((1_u32, 7)).unwrap(), ALIVE);
+        assert_eq!(column.get_data_ref().iter().filter(|cell| **cell == ALIVE).count(), 1);
+    }
+}
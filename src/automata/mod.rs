@@ -0,0 +1,83 @@
+//!   Built-in cellular automata over `MatrixImage<u8>` lattices, where every cell holds the index
+//!   of its state and is drawn through a `Palette`, some of them stochastic, along with the
+//!   sandpile over grain counts.
+
+pub mod life_like;
+pub use life_like::LifeLike;
+
+pub mod generations;
+pub use generations::Generations;
+
+pub mod rule_table;
+pub use rule_table::RuleTable;
+
+pub mod isotropic;
+pub use isotropic::Isotropic;
+
+pub mod larger_than_life;
+pub use larger_than_life::LargerThanLife;
+
+pub mod one_dimensional;
+pub use one_dimensional::OneDimensional;
+
+pub mod sandpile;
+pub use sandpile::Sandpile;
+
+pub mod epidemic;
+pub use epidemic::Epidemic;
+
+pub mod forest_fire;
+pub use forest_fire::ForestFire;
+
+pub mod margolus;
+pub use margolus::Margolus;
+
+use alloc::{
+    string::String,
+    vec::Vec,
+};
+use crate::error::MatrixError;
+
+/// Offsets of the Moore neighbors, clockwise from the north: N, NE, E, SE, S, SW, W, NW.
+pub(crate) const MOORE_OFFSETS: [(i64, i64); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+/// Offsets of the von Neumann neighbors, clockwise from the north: N, E, S, W.
+pub(crate) const VON_NEUMANN_OFFSETS: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Parses neighbor counts written as single digits, e.g. `236`, or as comma separated numbers, e.g. `2,10,11`.
+pub(crate) fn parse_counts(counts: &str, rule: &str) -> Result<Vec<usize>, MatrixError> {
+    let invalid = || MatrixError::InvalidRule(format!("{rule} has an invalid count list '{counts}'"));
+    if counts.contains(',') {
+        counts
+            .split(',')
+            .map(|count| count.trim().parse::<usize>().map_err(|_| invalid()))
+            .collect()
+    } else {
+        counts
+            .chars()
+            .map(|count| count.to_digit(10).map(|count| count as usize).ok_or_else(invalid))
+            .collect()
+    }
+}
+
+/// Writes the counts whose flag is set, as digits or comma separated when any count has two digits.
+pub(crate) fn write_counts(rule: &mut String, flags: &[bool]) {
+    let counts: Vec<usize> = (0..flags.len()).filter(|count| flags[*count]).collect();
+    let separator = if counts.iter().any(|count| *count > 9) { "," } else { "" };
+    let counts: Vec<String> = counts.iter().map(|count| format!("{count}")).collect();
+    *rule += &counts.join(separator);
+}
+
+/// State following a dying state, the dead state 0 past the last of the given number of states,
+/// including for states out of range such as the 255 of an uninitialized lattice.
+pub(crate) fn next_dying(state: u8, states: u8) -> u8 {
+    state.checked_add(1).filter(|next| *next < states).unwrap_or(0)
+}
+
+/// Flags indexed by neighbor count.
+pub(crate) fn as_flags(counts: &[usize]) -> Vec<bool> {
+    let mut flags = vec![false; counts.iter().max().map_or(0, |max| max + 1)];
+    for count in counts {
+        flags[*count] = true;
+    }
+    flags
+}
@@ -0,0 +1,232 @@
+//!   One-dimensional automata over a row of cells, either elementary rules given by their Wolfram
+//!   number, e.g. rule 30 or rule 110, or k-color totalistic rules of any radius where the next
+//!   state depends on the sum of the states around a cell. Successive generations are stacked as
+//!   the rows of a `MatrixImage<u8>`, giving the space-time diagram of the automaton.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+
+/// State of the cells beyond the ends of the row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// The row wraps around, its first and last cells being neighbors.
+    Periodic,
+    /// Every cell beyond the ends holds the given state.
+    Fixed(u8),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Table {
+    /// Wolfram number of a two-color rule of radius 1.
+    Elementary(u8),
+    /// Next state indexed by the sum of the states of the neighborhood, center included.
+    Totalistic(Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OneDimensional {
+    colors: u8,
+    radius: usize,
+    table: Table,
+    boundary: Boundary,
+    palette: Palette,
+}
+
+impl OneDimensional {
+    /// Elementary rule given by its Wolfram number, over a periodic row.
+    pub fn elementary(rule: u8) -> Self {
+        Self::with_table(2, 1, Table::Elementary(rule))
+    }
+    /// Totalistic rule with the next state of every sum of the `2 * radius + 1` states around a
+    /// cell, from sum 0 to sum `(2 * radius + 1) * (colors - 1)`.
+    pub fn totalistic(colors: u8, radius: usize, next_states: &[u8]) -> Result<Self, MatrixError> {
+        let sums = (2 * radius + 1) * (colors.max(1) as usize - 1) + 1;
+        if colors < 2 || next_states.len() != sums || next_states.iter().any(|state| *state >= colors) {
+            return Err(MatrixError::InvalidRule(format!(
+                "a totalistic rule of {colors} colors and radius {radius} should have {sums} next states below {colors}"
+            )));
+        }
+        Ok(Self::with_table(colors, radius, Table::Totalistic(next_states.to_vec())))
+    }
+    /// Totalistic rule given by its Wolfram code, whose base `colors` digits are the next states
+    /// of the sums, the least significant digit being the next state of sum 0.
+    pub fn totalistic_code(colors: u8, radius: usize, code: u64) -> Result<Self, MatrixError> {
+        if colors < 2 {
+            return Err(MatrixError::InvalidRule(format!("a totalistic rule should have at least 2 colors, not {colors}")));
+        }
+        let sums = (2 * radius + 1) * (colors as usize - 1) + 1;
+        let mut remainder = code;
+        let next_states: Vec<u8> = (0..sums)
+            .map(|_| {
+                let state = (remainder % colors as u64) as u8;
+                remainder /= colors as u64;
+                state
+            })
+            .collect();
+        if remainder != 0 {
+            return Err(MatrixError::InvalidRule(format!("code {code} has more than {sums} base {colors} digits")));
+        }
+        Self::totalistic(colors, radius, &next_states)
+    }
+    fn with_table(colors: u8, radius: usize, table: Table) -> Self {
+        OneDimensional {
+            colors,
+            radius,
+            table,
+            boundary: Boundary::Periodic,
+            palette: Palette::gradient(Rgba([255, 255, 255, 255]), Rgba([0, 0, 0, 255]), colors as usize),
+        }
+    }
+    pub fn get_colors(&self) -> u8 {
+        self.colors
+    }
+    pub fn get_radius(&self) -> usize {
+        self.radius
+    }
+    pub fn get_boundary(&self) -> Boundary {
+        self.boundary
+    }
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    /// Row of the given width with a single cell in state 1 at its center.
+    pub fn single_cell_seed(&self, width: usize) -> Vec<u8> {
+        let mut row = vec![0; width];
+        if let Some(center) = row.get_mut(width / 2) {
+            *center = 1;
+        }
+        row
+    }
+    /// Row of the given width with states drawn from the generator, taken modulo the number of colors.
+    pub fn random_seed(&self, width: usize, mut generator: impl FnMut() -> u8) -> Vec<u8> {
+        (0..width).map(|_| generator() % self.colors).collect()
+    }
+    /// Next state of a cell given the `2 * radius + 1` states around it, from left to right.
+    pub fn next_state(&self, neighborhood: &[u8]) -> u8 {
+        match &self.table {
+            Table::Elementary(rule) => {
+                let index = neighborhood.iter().fold(0, |index, state| index << 1 | (*state != 0) as u8);
+                rule >> index & 1
+            },
+            Table::Totalistic(next_states) => {
+                let sum: usize = neighborhood.iter().map(|state| *state as usize).sum();
+                next_states.get(sum).copied().unwrap_or(0)
+            },
+        }
+    }
+    /// Applies the rule synchronously to every cell of the row.
+    pub fn next_row(&self, row: &[u8]) -> Vec<u8> {
+        let radius = self.radius as i64;
+        let state = |position: i64| match self.boundary {
+            Boundary::Periodic => row[position.rem_euclid(row.len() as i64) as usize],
+            Boundary::Fixed(state) => usize::try_from(position).ok().and_then(|position| row.get(position)).copied().unwrap_or(state),
+        };
+        let mut neighborhood = Vec::with_capacity(2 * self.radius + 1);
+        (0..row.len() as i64)
+            .map(|position| {
+                neighborhood.clear();
+                neighborhood.extend((position - radius..=position + radius).map(state));
+                self.next_state(&neighborhood)
+            })
+            .collect()
+    }
+    /// Space-time diagram with the seed as its first row followed by the given number of generations.
+    pub fn space_time(&self, seed: &[u8], generations: usize) -> Result<MatrixImage<u8>, MatrixError> {
+        let mut diagram = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(generations + 1, seed.len())
+            .build();
+        let mut row = seed.to_vec();
+        for point_y in 0..=generations as u32 {
+            if point_y > 0 {
+                row = self.next_row(&row);
+            }
+            for (point_x, state) in row.iter().enumerate() {
+                diagram.edit_point((point_x as u32, point_y), *state)?;
+            }
+        }
+        Ok(diagram)
+    }
+    /// Scales the states of a diagram to the full range of a channel, from 0 to 255, to draw it
+    /// with `Draw`.
+    pub fn to_intensity(&self, diagram: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let mut intensity = diagram.clone();
+        for point in 0..(diagram.get_width()*diagram.get_height()) {
+            let state = diagram.get_absolute_point_data(point).min(self.colors - 1) as usize;
+            intensity.edit_point(diagram.into_2d_point(point)?, (state * 255 / (self.colors as usize - 1)) as u8)?;
+        }
+        Ok(intensity)
+    }
+    pub fn draw(&self, diagram: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(diagram)
+    }
+}
+
+impl Rule<Vec<u8>> for OneDimensional {
+    fn step(&mut self, state: &Vec<u8>) -> Result<Vec<u8>, MatrixError> {
+        Ok(self.next_row(state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        automata::one_dimensional::{
+            Boundary,
+            OneDimensional,
+        },
+        traits::Matrix,
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn rule_90_draws_pascal_triangle_modulo_2() {
+        let rule = OneDimensional::elementary(90);
+        let diagram = rule.space_time(&rule.single_cell_seed(33), 16).unwrap();
+        let mut pascal = vec![1_u32];
+        for y in 0..=16_u32 {
+            for x in 0..33_u32 {
+                let offset = x as i64 - 16 + y as i64;
+                let expected = if offset >= 0 && offset % 2 == 0 && offset / 2 <= y as i64 { pascal[offset as usize / 2] % 2 } else { 0 };
+                assert_eq!(diagram.get_point_value((x, y)).unwrap() as u32, expected, "at {x}, {y}");
+            }
+            pascal = (0..pascal.len() + 1)
+                .map(|k| if k == 0 || k == pascal.len() { 1 } else { (pascal[k - 1] + pascal[k]) % 2 })
+                .collect();
+        }
+    }
+
+    #[test]
+    fn totalistic_parity_matches_rule_150() {
+        let parity = OneDimensional::totalistic_code(2, 1, 0b1010).unwrap();
+        let elementary = OneDimensional::elementary(150);
+        let seed: Vec<u8> = (0..20_u32).map(|x| (x * x % 7 % 2) as u8).collect();
+        assert_eq!(parity.space_time(&seed, 10).unwrap(), elementary.space_time(&seed, 10).unwrap());
+        assert!(OneDimensional::totalistic_code(2, 1, 16).is_err());
+        assert!(OneDimensional::totalistic(3, 1, &[0, 1, 2, 1, 0, 1, 3]).is_err());
+    }
+
+    #[test]
+    fn fixed_boundary_feeds_the_edges() {
+        let rule = OneDimensional::elementary(254).with_boundary(Boundary::Fixed(1));
+        assert_eq!(rule.next_row(&[0, 0, 0, 0, 0]), vec![1, 0, 0, 0, 1]);
+        let periodic = OneDimensional::elementary(254);
+        assert_eq!(periodic.next_row(&[1, 0, 0, 0, 0]), vec![1, 1, 0, 0, 1]);
+    }
+}
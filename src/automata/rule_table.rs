@@ -0,0 +1,459 @@
+//!   Multi-state automata given by an explicit table of transitions, in the format of Golly's
+//!   `.rule` files. A table lists, for the center cell and its neighbors, the input states and the
+//!   output state of the center cell; entries are states or variables ranging over a set of
+//!   states, and a variable used more than once in a transition is bound to a single value.
+//!   The first matching transition applies and cells without a match are left unchanged.
+//!
+//!   Neighbors are listed clockwise from the north, as `C,N,NE,E,SE,S,SW,W,NW,C'` for the Moore
+//!   neighborhood and `C,N,E,S,W,C'` for the von Neumann neighborhood, and transitions may be
+//!   matched under the symmetries `none`, `rotate4`, `rotate8`, `reflect_horizontal`,
+//!   `rotate4reflect`, `rotate8reflect` or `permute`.
+
+use core::{
+    cell::RefCell,
+    str::FromStr,
+};
+use alloc::{
+    collections::btree_map::BTreeMap,
+    string::{
+        String,
+        ToString,
+    },
+    vec::Vec,
+};
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    Neighborhood,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::{
+    MOORE_OFFSETS,
+    VON_NEUMANN_OFFSETS,
+};
+
+/// Golly's description of the WireWorld rule: 0 is empty, 1 an electron head, 2 an electron tail and 3 a wire.
+pub const WIREWORLD: &str = "@RULE WireWorld
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+var a={0,1,2,3}
+var b={0,1,2,3}
+var c={0,1,2,3}
+var d={0,1,2,3}
+var e={0,1,2,3}
+var f={0,1,2,3}
+var g={0,1,2,3}
+var h={0,1,2,3}
+var i={0,2,3}
+var j={0,2,3}
+var k={0,2,3}
+var l={0,2,3}
+var m={0,2,3}
+var n={0,2,3}
+var o={0,2,3}
+# electron head becomes a tail
+1,a,b,c,d,e,f,g,h,2
+# tail becomes a wire
+2,a,b,c,d,e,f,g,h,3
+# wire with one or two heads around becomes a head
+3,1,i,j,k,l,m,n,o,1
+3,1,1,i,j,k,l,m,n,1
+@COLORS
+1 0 128 255
+2 255 255 255
+3 255 128 0
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Symmetry {
+    None,
+    Rotate4,
+    Rotate8,
+    ReflectHorizontal,
+    Rotate4Reflect,
+    Rotate8Reflect,
+    Permute,
+}
+
+impl FromStr for Symmetry {
+    type Err = MatrixError;
+    fn from_str(symmetry: &str) -> Result<Self, Self::Err> {
+        match symmetry {
+            "none" => Ok(Self::None),
+            "rotate4" => Ok(Self::Rotate4),
+            "rotate8" => Ok(Self::Rotate8),
+            "reflect_horizontal" => Ok(Self::ReflectHorizontal),
+            "rotate4reflect" => Ok(Self::Rotate4Reflect),
+            "rotate8reflect" => Ok(Self::Rotate8Reflect),
+            "permute" => Ok(Self::Permute),
+            _ => Err(MatrixError::InvalidRule(format!("unsupported symmetries '{symmetry}'"))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Entry {
+    State(u8),
+    /// Index into the variables of the table.
+    Variable(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    /// Center followed by the neighbors.
+    inputs: Vec<Entry>,
+    output: Entry,
+}
+
+#[derive(Clone, Debug)]
+pub struct RuleTable {
+    name: String,
+    states: u8,
+    /// Moore or von Neumann, the only neighborhoods tables are parsed for.
+    hood_type: Neighborhood,
+    symmetry: Symmetry,
+    variables: Vec<Vec<u8>>,
+    transitions: Vec<Transition>,
+    /// Neighbor orderings under which a transition is matched, unused for `Symmetry::Permute`.
+    orderings: Vec<Vec<usize>>,
+    /// Next state of every configuration met so far, keyed by center followed by the neighbors.
+    cache: RefCell<BTreeMap<Vec<u8>, u8>>,
+    palette: Palette,
+}
+
+impl RuleTable {
+    pub fn wireworld() -> Self {
+        WIREWORLD.parse().expect("Built-in rule table is valid.")
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_states(&self) -> u8 {
+        self.states
+    }
+    pub fn get_neighborhood(&self) -> Neighborhood {
+        self.hood_type
+    }
+    pub fn get_symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    fn offsets(&self) -> &'static [(i64, i64)] {
+        match self.hood_type {
+            Neighborhood::Moore => &MOORE_OFFSETS,
+            _ => &VON_NEUMANN_OFFSETS,
+        }
+    }
+    /// Next state of a cell given its state followed by the states of its neighbors, in Golly's order.
+    pub fn next_state(&self, configuration: &[u8]) -> u8 {
+        if let Some(state) = self.cache.borrow().get(configuration) {
+            return *state;
+        }
+        let state = self.transitions
+            .iter()
+            .find_map(|transition| self.apply(transition, configuration))
+            .unwrap_or(configuration[0]);
+        self.cache.borrow_mut().insert(configuration.to_vec(), state);
+        state
+    }
+    pub fn next_generation(&self, matrix: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        let mut new_matrix = matrix.clone();
+        let mut configuration = vec![0; self.offsets().len() + 1];
+        for point_y in 0..matrix.get_height() as u32 {
+            for point_x in 0..matrix.get_width() as u32 {
+                let center = (point_x, point_y);
+                configuration[0] = matrix.get_point_value(center)?;
+                for (index, offset) in self.offsets().iter().enumerate() {
+                    configuration[index + 1] = matrix.get_point_value(matrix.offset_point(center, *offset))?;
+                }
+                new_matrix.edit_point(center, self.next_state(&configuration))?;
+            }
+        }
+        Ok(new_matrix)
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(matrix)
+    }
+    /// Output of the transition if it matches the configuration under the symmetries of the table.
+    fn apply(&self, transition: &Transition, configuration: &[u8]) -> Option<u8> {
+        let mut bindings = vec![None; self.variables.len()];
+        if !self.bind(transition.inputs[0], configuration[0], &mut bindings) {
+            return None;
+        }
+        let bindings = if self.symmetry == Symmetry::Permute {
+            let mut used = vec![false; configuration.len() - 1];
+            self.permuted_match(&transition.inputs[1..], &configuration[1..], &mut used, bindings)?
+        } else {
+            self.orderings.iter().find_map(|ordering| {
+                let mut bindings = bindings.clone();
+                ordering
+                    .iter()
+                    .enumerate()
+                    .all(|(entry, neighbor)| self.bind(transition.inputs[entry + 1], configuration[neighbor + 1], &mut bindings))
+                    .then_some(bindings)
+            })?
+        };
+        match transition.output {
+            Entry::State(state) => Some(state),
+            Entry::Variable(variable) => bindings[variable],
+        }
+    }
+    /// Matches the first entry with any unused neighbor, then the remaining entries recursively.
+    fn permuted_match(&self, entries: &[Entry], neighbors: &[u8], used: &mut [bool], bindings: Vec<Option<u8>>) -> Option<Vec<Option<u8>>> {
+        let Some((entry, remaining)) = entries.split_first() else {
+            return Some(bindings);
+        };
+        for neighbor in 0..neighbors.len() {
+            if used[neighbor] {
+                continue;
+            }
+            let mut candidate = bindings.clone();
+            if self.bind(*entry, neighbors[neighbor], &mut candidate) {
+                used[neighbor] = true;
+                let matched = self.permuted_match(remaining, neighbors, used, candidate);
+                used[neighbor] = false;
+                if matched.is_some() {
+                    return matched;
+                }
+            }
+        }
+        None
+    }
+    fn bind(&self, entry: Entry, state: u8, bindings: &mut [Option<u8>]) -> bool {
+        match entry {
+            Entry::State(expected) => expected == state,
+            Entry::Variable(variable) => match bindings[variable] {
+                Some(bound) => bound == state,
+                None if self.variables[variable].contains(&state) => {
+                    bindings[variable] = Some(state);
+                    true
+                },
+                None => false,
+            },
+        }
+    }
+}
+
+/// Neighbor orderings generated by the rotations and reflections of a ring of neighbors.
+fn orderings(symmetry: Symmetry, hood_type: Neighborhood) -> Result<Vec<Vec<usize>>, MatrixError> {
+    let length = match hood_type {
+        Neighborhood::Moore => 8,
+        _ => 4,
+    };
+    let quarter_turn = length / 4;
+    let (rotation_step, reflect) = match (symmetry, hood_type) {
+        (Symmetry::None, _) | (Symmetry::Permute, _) => (length, false),
+        (Symmetry::ReflectHorizontal, _) => (length, true),
+        (Symmetry::Rotate4, _) => (quarter_turn, false),
+        (Symmetry::Rotate4Reflect, _) => (quarter_turn, true),
+        (Symmetry::Rotate8, Neighborhood::Moore) => (1, false),
+        (Symmetry::Rotate8Reflect, Neighborhood::Moore) => (1, true),
+        _ => return Err(MatrixError::InvalidRule(format!("{symmetry:?} requires the Moore neighborhood"))),
+    };
+    let mut orderings = Vec::new();
+    for rotation in (0..length).step_by(rotation_step) {
+        orderings.push((0..length).map(|entry| (entry + rotation) % length).collect::<Vec<usize>>());
+        if reflect {
+            orderings.push((0..length).map(|entry| (length - entry + rotation) % length).collect());
+        }
+    }
+    Ok(orderings)
+}
+
+impl FromStr for RuleTable {
+    type Err = MatrixError;
+    /// Parses the `@TABLE` and optional `@COLORS` sections of a Golly `.rule` file, or a bare table.
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = |line: &str, reason: &str| MatrixError::InvalidRule(format!("'{line}' {reason}"));
+        let mut name = String::new();
+        let mut section = if rule.contains("@TABLE") { "" } else { "@TABLE" };
+        let mut states: Option<u8> = None;
+        let mut hood_type = Neighborhood::Moore;
+        let mut symmetry = Symmetry::None;
+        let mut names: Vec<String> = Vec::new();
+        let mut variables: Vec<Vec<u8>> = Vec::new();
+        let mut transitions = Vec::new();
+        let mut colors: BTreeMap<u8, Rgba<u8>> = BTreeMap::new();
+        for line in rule.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('@') {
+                let mut words = line.split_whitespace();
+                section = words.next().unwrap_or("");
+                if section == "@RULE" {
+                    name = words.next().unwrap_or("").to_string();
+                }
+                continue;
+            }
+            match section {
+                "@TABLE" => {},
+                "@COLORS" => {
+                    let values: Vec<u8> = line
+                        .split_whitespace()
+                        .map(|value| value.parse::<u8>().map_err(|_| invalid(line, "is not a 'state red green blue' color")))
+                        .collect::<Result<_, _>>()?;
+                    let [state, red, green, blue] = values[..] else {
+                        return Err(invalid(line, "is not a 'state red green blue' color"));
+                    };
+                    colors.insert(state, Rgba([red, green, blue, 255]));
+                    continue;
+                },
+                _ => continue,
+            }
+            let n_states = |states: Option<u8>| states.ok_or_else(|| invalid(line, "appears before n_states"));
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim();
+                match key.trim() {
+                    "n_states" => {
+                        states = Some(value.parse::<u8>().ok().filter(|states| *states >= 2).ok_or_else(|| invalid(line, "should be between 2 and 255"))?);
+                    },
+                    "neighborhood" => {
+                        hood_type = match value {
+                            "Moore" => Neighborhood::Moore,
+                            "vonNeumann" => Neighborhood::VonNeumann,
+                            _ => return Err(invalid(line, "is not a supported neighborhood")),
+                        };
+                    },
+                    "symmetries" => symmetry = value.parse()?,
+                    _ => return Err(invalid(line, "is not a known table setting")),
+                }
+            } else if let Some(declaration) = line.strip_prefix("var ") {
+                let (variable, values) = declaration
+                    .split_once('=')
+                    .ok_or_else(|| invalid(line, "should be 'var name={...}'"))?;
+                let values = values
+                    .trim()
+                    .strip_prefix('{')
+                    .and_then(|values| values.strip_suffix('}'))
+                    .ok_or_else(|| invalid(line, "should be 'var name={...}'"))?;
+                let mut set = Vec::new();
+                for value in values.split(',').map(str::trim) {
+                    match parse_entry(value, &names, n_states(states)?) {
+                        Some(Entry::State(state)) => set.push(state),
+                        Some(Entry::Variable(other)) => set.extend(variables[other].iter().copied()),
+                        None => return Err(invalid(line, "has an unknown state or variable")),
+                    }
+                }
+                set.sort_unstable();
+                set.dedup();
+                names.push(variable.trim().to_string());
+                variables.push(set);
+            } else {
+                let tokens: Vec<String> = if line.contains(',') {
+                    line.split(',').map(|token| token.trim().to_string()).collect()
+                } else {
+                    line.chars().filter(|token| !token.is_whitespace()).map(|token| token.to_string()).collect()
+                };
+                let neighbors = match hood_type {
+                    Neighborhood::Moore => 8,
+                    _ => 4,
+                };
+                if tokens.len() != neighbors + 2 {
+                    return Err(invalid(line, "has the wrong number of entries for the neighborhood"));
+                }
+                let mut entries = Vec::new();
+                for token in &tokens {
+                    entries.push(parse_entry(token, &names, n_states(states)?).ok_or_else(|| invalid(line, "has an unknown state or variable"))?);
+                }
+                let output = entries.pop().expect("Entries are not empty.");
+                if matches!(output, Entry::Variable(_)) && !entries.contains(&output) {
+                    return Err(invalid(line, "outputs a variable that is not bound by the inputs"));
+                }
+                transitions.push(Transition {
+                    inputs: entries,
+                    output,
+                });
+            }
+        }
+        let states = states.ok_or_else(|| MatrixError::InvalidRule("rule table without n_states".to_string()))?;
+        let mut palette = Palette::gradient(Rgba([255, 255, 255, 255]), Rgba([255, 0, 0, 255]), states as usize).get_colors_ref().clone();
+        palette[0] = Rgba([0, 0, 0, 255]);
+        for (state, color) in colors {
+            if let Some(entry) = palette.get_mut(state as usize) {
+                *entry = color;
+            }
+        }
+        Ok(RuleTable {
+            name,
+            states,
+            hood_type,
+            symmetry,
+            variables,
+            transitions,
+            orderings: orderings(symmetry, hood_type)?,
+            cache: RefCell::new(BTreeMap::new()),
+            palette: Palette::new(palette),
+        })
+    }
+}
+
+fn parse_entry(token: &str, names: &[String], states: u8) -> Option<Entry> {
+    match token.parse::<u8>() {
+        Ok(state) if state < states => Some(Entry::State(state)),
+        Ok(_) => None,
+        Err(_) => names.iter().position(|name| name == token).map(Entry::Variable),
+    }
+}
+
+impl Rule<MatrixImage<u8>> for RuleTable {
+    fn step(&mut self, state: &MatrixImage<u8>) -> Result<MatrixImage<u8>, MatrixError> {
+        self.next_generation(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        automata::rule_table::RuleTable,
+        traits::Matrix,
+    };
+
+    #[test]
+    fn wireworld_diode_moves_electron() {
+        let rule = RuleTable::wireworld();
+        assert_eq!(rule.get_name(), "WireWorld");
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(3, 8).build();
+        for x in 0..8_u32 {
+            matrix.edit_point((x, 1), 3).unwrap();
+        }
+        matrix.edit_point((1_u32, 1_u32), 2).unwrap();
+        matrix.edit_point((2_u32, 1_u32), 1).unwrap();
+        let next = rule.next_generation(&matrix).unwrap();
+        let row: alloc::vec::Vec<u8> = (0..8_u32).map(|x| next.get_point_value((x, 1)).unwrap()).collect();
+        assert_eq!(row, vec![3, 3, 2, 1, 3, 3, 3, 3]);
+        assert_eq!(rule.next_state(&[3, 1, 1, 1, 0, 0, 0, 0, 0]), 3);
+    }
+
+    #[test]
+    fn bound_variables_and_rotations() {
+        let rule: RuleTable = "n_states:3
+neighborhood:vonNeumann
+symmetries:rotate4
+var a={1,2}
+0,a,a,0,0,a
+0,1,0,2,0,2"
+            .parse()
+            .unwrap();
+        assert_eq!(rule.next_state(&[0, 0, 0, 2, 2]), 2);
+        assert_eq!(rule.next_state(&[0, 1, 2, 0, 0]), 0);
+        assert_eq!(rule.next_state(&[0, 2, 0, 1, 0]), 2);
+        assert_eq!(rule.next_state(&[0, 2, 0, 2, 0]), 0);
+        assert!("n_states:2\nneighborhood:vonNeumann\nsymmetries:rotate8\n0,1,0,0,0,1".parse::<RuleTable>().is_err());
+        assert!("n_states:2\n0,1,0,0,0,1".parse::<RuleTable>().is_err());
+    }
+}
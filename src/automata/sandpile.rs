@@ -0,0 +1,311 @@
+//!   Abelian sandpile of Bak, Tang and Wiesenfeld over a `MatrixImage<u32>` of grain counts. A site
+//!   holding at least `threshold` grains topples, losing `threshold` grains and giving one to each
+//!   of its neighbors, grains falling off the edges of the lattice being lost. Dropping grains one
+//!   at a time and letting the pile relax drives it to a critical state, where avalanche sizes,
+//!   durations and areas follow power laws.
+//!
+//!   Toppling happens in waves, every unstable site toppling at once, the number of waves being
+//!   the duration of the avalanche. The final pile doesn't depend on the order of the topplings.
+
+use alloc::{
+    collections::BTreeMap,
+    string::String,
+    vec::Vec,
+};
+use core::fmt::Write;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    random::{
+        self,
+        RngCore,
+    },
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+
+/// Relaxation of the pile after a grain was dropped.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Avalanche {
+    /// Number of topplings.
+    pub size: usize,
+    /// Number of toppling waves.
+    pub duration: usize,
+    /// Number of distinct sites that toppled.
+    pub area: usize,
+    /// Grains lost through the edges or by sites toppling more grains than they have neighbors.
+    pub dissipated: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sandpile {
+    threshold: u32,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    palette: Palette,
+}
+
+impl Sandpile {
+    /// Pile over the von Neumann neighborhood, toppling at 4 grains.
+    pub fn new() -> Self {
+        Sandpile {
+            threshold: 4,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            palette: Palette::new(vec![
+                Rgba([0, 0, 0, 255]),
+                Rgba([40, 90, 200, 255]),
+                Rgba([250, 200, 40, 255]),
+                Rgba([200, 30, 30, 255]),
+                Rgba([255, 255, 255, 255]),
+            ]),
+        }
+    }
+    /// Grains at which a site topples, at least its number of neighbors; the excess over the
+    /// neighbors is dissipated at every toppling.
+    pub fn with_threshold(mut self, threshold: u32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+    /// Neighborhood receiving the grains of a toppling site, e.g. Moore of size 1 with a threshold
+    /// of 8.
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_threshold(&self) -> u32 {
+        self.threshold
+    }
+    /// Offsets of the neighbors, center excluded, failing when the threshold doesn't cover them.
+    fn offsets(&self) -> Result<Vec<(i64, i64)>, MatrixError> {
+        self.hood_type.per_cell()?;
+        let size = self.hood_size as i64;
+        let offsets: Vec<(i64, i64)> = (-size..=size)
+            .flat_map(|y| (-size..=size).map(move |x| (x, y)))
+            .filter(|(x, y)| (*x, *y) != (0, 0) && (self.hood_type == Neighborhood::Moore || x.abs() + y.abs() <= size))
+            .collect();
+        if (self.threshold as usize) < offsets.len().max(1) {
+            return Err(MatrixError::InvalidRule(format!(
+                "a sandpile toppling to {} neighbors needs a threshold of at least {}, not {}",
+                offsets.len(), offsets.len().max(1), self.threshold,
+            )));
+        }
+        Ok(offsets)
+    }
+    /// Empty pile.
+    pub fn empty(width: usize, height: usize) -> MatrixImage<u32> {
+        MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(height, width)
+            .build()
+    }
+    /// Topples every unstable site until the pile is stable.
+    pub fn stabilize(&self, pile: &mut MatrixImage<u32>) -> Result<Avalanche, MatrixError> {
+        let offsets = self.offsets()?;
+        let (width, height) = (pile.get_width() as i64, pile.get_height() as i64);
+        let threshold = self.threshold;
+        let grains = pile.get_data_mut_ref();
+        let mut toppled = vec![false; grains.len()];
+        let mut unstable: Vec<usize> = (0..grains.len()).filter(|index| grains[*index] >= threshold).collect();
+        let mut avalanche = Avalanche::default();
+        while !unstable.is_empty() {
+            avalanche.duration += 1;
+            let mut next = Vec::new();
+            for index in unstable {
+                let topplings = grains[index] / threshold;
+                if topplings == 0 {
+                    continue;
+                }
+                grains[index] -= topplings * threshold;
+                avalanche.size += topplings as usize;
+                if !toppled[index] {
+                    toppled[index] = true;
+                    avalanche.area += 1;
+                }
+                avalanche.dissipated += (threshold as u64 - offsets.len() as u64) * topplings as u64;
+                let (x, y) = (index as i64 % width, index as i64 / width);
+                for (offset_x, offset_y) in &offsets {
+                    let (neighbor_x, neighbor_y) = (x + offset_x, y + offset_y);
+                    if !(0..width).contains(&neighbor_x) || !(0..height).contains(&neighbor_y) {
+                        avalanche.dissipated += topplings as u64;
+                        continue;
+                    }
+                    let neighbor = (neighbor_y * width + neighbor_x) as usize;
+                    let before = grains[neighbor];
+                    grains[neighbor] += topplings;
+                    if before < threshold && grains[neighbor] >= threshold {
+                        next.push(neighbor);
+                    }
+                }
+            }
+            unstable = next;
+        }
+        Ok(avalanche)
+    }
+    /// Drops a grain at a point and relaxes the pile.
+    pub fn drop_grain(&self, pile: &mut MatrixImage<u32>, point: (u32, u32)) -> Result<Avalanche, MatrixError> {
+        let grains = pile.get_point_value(point)?;
+        pile.edit_point(point, grains + 1)?;
+        self.stabilize(pile)
+    }
+    /// Drops a grain at a uniformly random point and relaxes the pile.
+    pub fn drop_random(&self, pile: &mut MatrixImage<u32>, rng: &mut impl RngCore) -> Result<Avalanche, MatrixError> {
+        let sites = pile.get_width() * pile.get_height();
+        let index = ((random::unit_f64(rng) * sites as f64) as usize).min(sites.max(1) - 1);
+        self.drop_grain(pile, pile.into_2d_point(index)?)
+    }
+    pub fn draw(&self, pile: &MatrixImage<u32>) -> Result<RgbaImage, MatrixError> {
+        let mut states: MatrixImage<u8> = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(pile.get_height(), pile.get_width())
+            .build();
+        for (state, grains) in states.get_data_mut_ref().iter_mut().zip(pile.get_data_ref()) {
+            *state = (*grains).min(u8::MAX as u32) as u8;
+        }
+        self.palette.draw(&states)
+    }
+}
+
+impl Default for Sandpile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sandpile driven by random grain drops, as a `Rule` dropping one grain per step and recording
+/// its avalanche.
+pub struct Driven<R: RngCore> {
+    sandpile: Sandpile,
+    rng: R,
+    avalanches: Avalanches,
+}
+
+impl<R: RngCore> Driven<R> {
+    pub fn new(sandpile: Sandpile, rng: R) -> Self {
+        Driven {
+            sandpile,
+            rng,
+            avalanches: Avalanches::default(),
+        }
+    }
+    pub fn get_avalanches_ref(&self) -> &Avalanches {
+        &self.avalanches
+    }
+}
+
+impl<R: RngCore> Rule<MatrixImage<u32>> for Driven<R> {
+    fn step(&mut self, state: &MatrixImage<u32>) -> Result<MatrixImage<u32>, MatrixError> {
+        let mut next = state.clone();
+        let avalanche = self.sandpile.drop_random(&mut next, &mut self.rng)?;
+        self.avalanches.push(avalanche);
+        Ok(next)
+    }
+}
+
+/// Avalanches in the order they happened, with their distributions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Avalanches {
+    avalanches: Vec<Avalanche>,
+}
+
+impl Avalanches {
+    pub fn push(&mut self, avalanche: Avalanche) {
+        self.avalanches.push(avalanche);
+    }
+    pub fn get_avalanches_ref(&self) -> &Vec<Avalanche> {
+        &self.avalanches
+    }
+    /// Number of avalanches of every value of an observable, e.g. `|avalanche| avalanche.size`,
+    /// drops that toppled no site included with size 0.
+    pub fn distribution(&self, observable: impl Fn(&Avalanche) -> usize) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        for avalanche in &self.avalanches {
+            *counts.entry(observable(avalanche)).or_insert(0) += 1;
+        }
+        counts
+    }
+    /// One line per avalanche, `drop,size,duration,area,dissipated`, after a header.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("drop,size,duration,area,dissipated\n");
+        for (drop, avalanche) in self.avalanches.iter().enumerate() {
+            let _ = writeln!(csv, "{drop},{},{},{},{}", avalanche.size, avalanche.duration, avalanche.area, avalanche.dissipated);
+        }
+        csv
+    }
+    /// Distributions of size, duration and area, one line per value, `value,size,duration,area`,
+    /// with the number of avalanches taking the value for every observable.
+    pub fn distributions_csv(&self) -> String {
+        let distributions = [
+            self.distribution(|avalanche| avalanche.size),
+            self.distribution(|avalanche| avalanche.duration),
+            self.distribution(|avalanche| avalanche.area),
+        ];
+        let mut values: Vec<usize> = distributions.iter().flat_map(|distribution| distribution.keys().copied()).collect();
+        values.sort_unstable();
+        values.dedup();
+        let mut csv = String::from("value,size,duration,area\n");
+        for value in values {
+            let [size, duration, area] = distributions.each_ref().map(|distribution| distribution.get(&value).copied().unwrap_or(0));
+            let _ = writeln!(csv, "{value},{size},{duration},{area}");
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        automata::sandpile::{
+            Avalanche,
+            Driven,
+            Sandpile,
+        },
+        random::Philox,
+        simulation::Rule,
+    };
+
+    #[test]
+    fn toppling_conserves_grains_and_stabilizes() {
+        let sandpile = Sandpile::new();
+        let mut pile = Sandpile::empty(3, 3);
+        pile.get_data_mut_ref().iter_mut().for_each(|grains| *grains = 3);
+        let avalanche = sandpile.drop_grain(&mut pile, (1, 1)).unwrap();
+        assert_eq!(pile.get_data_ref(), &vec![1, 3, 1, 3, 0, 3, 1, 3, 1]);
+        assert_eq!(avalanche, Avalanche { size: 10, duration: 3, area: 9, dissipated: 12 });
+        let grains: u32 = pile.get_data_ref().iter().sum();
+        assert_eq!(grains as u64 + avalanche.dissipated, 28);
+        assert!(Sandpile::new().with_neighborhood(Neighborhood::Moore, 1).drop_grain(&mut pile, (0, 0)).is_err());
+    }
+
+    #[test]
+    fn driven_pile_reaches_the_critical_density() {
+        let mut driven = Driven::new(Sandpile::new(), Philox::new(43));
+        let mut pile = Sandpile::empty(24, 24);
+        for _ in 0..8000 {
+            pile = driven.step(&pile).unwrap();
+        }
+        assert!(pile.get_data_ref().iter().all(|grains| *grains < 4));
+        let density = pile.get_data_ref().iter().sum::<u32>() as f64 / 576.0;
+        assert!((2.0..2.25).contains(&density), "{density}");
+        let avalanches = driven.get_avalanches_ref();
+        let sizes = avalanches.distribution(|avalanche| avalanche.size);
+        assert_eq!(sizes.values().sum::<usize>(), 8000);
+        assert!(sizes.keys().last().unwrap() > &100);
+        assert!(avalanches.get_avalanches_ref().iter().all(|avalanche| avalanche.area <= avalanche.size && avalanche.duration <= avalanche.size));
+        assert_eq!(avalanches.to_csv().lines().count(), 8001);
+        assert!(avalanches.distributions_csv().starts_with("value,size,duration,area\n0,"));
+    }
+}
@@ -0,0 +1,380 @@
+//!   Connected-component labeling and percolation. Sites satisfying a predicate are joined to their
+//!   neighbors by a union-find pass, so that every cluster gets a label, numbered from 1 in the
+//!   order its first site appears row after row, and 0 marks the other sites.
+//!
+//!   On open lattices a cluster spans a direction when it touches both opposite edges. On wrapping
+//!   lattices every site keeps its displacement from the root of its cluster, unwrapped across the
+//!   edges, and a cluster spans a direction when it joins a site to itself through a displacement
+//!   along that direction, winding around the torus, rather than by merely touching both edges.
+//!
+//!   Percolation sweeps estimate, over random lattices of each occupation probability, the
+//!   probability that a cluster spans the lattice vertically and the fraction of sites held by the
+//!   largest cluster, for site percolation, where sites are occupied, and bond percolation, where
+//!   the bonds between neighbors are open.
+
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    random::{
+        self,
+        Philox,
+    },
+    traits::Matrix,
+    error::MatrixError,
+};
+
+/// Directions along which a cluster spans the lattice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spanning {
+    pub horizontal: bool,
+    pub vertical: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clusters {
+    labels: MatrixImage<u32>,
+    sizes: Vec<usize>,
+    spans: Vec<Spanning>,
+}
+
+impl Clusters {
+    /// Labels of the sites, 0 for the sites outside of any cluster.
+    pub fn get_labels_ref(&self) -> &MatrixImage<u32> {
+        &self.labels
+    }
+    /// Number of sites of every cluster, the cluster of label l at index l - 1.
+    pub fn get_sizes_ref(&self) -> &Vec<usize> {
+        &self.sizes
+    }
+    /// Spanning directions of every cluster, the cluster of label l at index l - 1.
+    pub fn get_spans_ref(&self) -> &Vec<Spanning> {
+        &self.spans
+    }
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+    /// Label and size of the largest cluster, the first one found among equals.
+    pub fn largest(&self) -> Option<(u32, usize)> {
+        self.sizes
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, size)| **size)
+            .map(|(index, size)| (index as u32 + 1, *size))
+    }
+    /// Labels of the clusters spanning the lattice in any direction.
+    pub fn spanning(&self) -> Vec<u32> {
+        (0..self.spans.len())
+            .filter(|index| self.spans[*index].horizontal || self.spans[*index].vertical)
+            .map(|index| index as u32 + 1)
+            .collect()
+    }
+}
+
+/// Offsets of the neighbors of a site, center excluded, as counted by `get_lattice_neighborhood`.
+fn offsets(size: usize, hood_type: Neighborhood) -> Result<Vec<(i64, i64)>, MatrixError> {
+    hood_type.per_cell()?;
+    let size = size as i64;
+    Ok((-size..=size)
+        .flat_map(|y| (-size..=size).map(move |x| (x, y)))
+        .filter(|(x, y)| (*x, *y) != (0, 0) && (hood_type == Neighborhood::Moore || x.abs() + y.abs() <= size))
+        .collect())
+}
+
+/// Union-find over the sites of a lattice, with the unwrapped displacement of every site from its
+/// parent.
+struct Forest {
+    parents: Vec<usize>,
+    shifts: Vec<(i64, i64)>,
+    ranks: Vec<u8>,
+    windings: Vec<Spanning>,
+}
+
+impl Forest {
+    fn new(sites: usize) -> Self {
+        Forest {
+            parents: (0..sites).collect(),
+            shifts: vec![(0, 0); sites],
+            ranks: vec![0; sites],
+            windings: vec![Spanning::default(); sites],
+        }
+    }
+    /// Root of a site and the displacement of the site from the root, compressing the path.
+    fn find(&mut self, site: usize) -> (usize, (i64, i64)) {
+        let parent = self.parents[site];
+        if parent == site {
+            return (site, (0, 0));
+        }
+        let (root, shift) = self.find(parent);
+        self.parents[site] = root;
+        self.shifts[site] = (self.shifts[site].0 + shift.0, self.shifts[site].1 + shift.1);
+        (root, self.shifts[site])
+    }
+    /// Joins two sites, the second lying at the given unwrapped step from the first.
+    fn union(&mut self, first: usize, second: usize, step: (i64, i64)) {
+        let (first_root, first_shift) = self.find(first);
+        let (second_root, second_shift) = self.find(second);
+        let shift = (first_shift.0 + step.0 - second_shift.0, first_shift.1 + step.1 - second_shift.1);
+        if first_root == second_root {
+            let winding = &mut self.windings[first_root];
+            winding.horizontal |= shift.0 != 0;
+            winding.vertical |= shift.1 != 0;
+            return;
+        }
+        let (parent, child, shift) = if self.ranks[first_root] >= self.ranks[second_root] {
+            (first_root, second_root, shift)
+        } else {
+            (second_root, first_root, (-shift.0, -shift.1))
+        };
+        self.parents[child] = parent;
+        self.shifts[child] = shift;
+        if self.ranks[parent] == self.ranks[child] {
+            self.ranks[parent] += 1;
+        }
+        let winding = self.windings[child];
+        self.windings[parent].horizontal |= winding.horizontal;
+        self.windings[parent].vertical |= winding.vertical;
+    }
+}
+
+/// Labels the clusters of occupied sites joined by connected pairs of neighbors, `connected`
+/// receiving the site, the neighbor and the index of the offset between them.
+fn label_with(
+    width: usize,
+    height: usize,
+    offsets: &[(i64, i64)],
+    wrapping: bool,
+    occupied: impl Fn(usize) -> bool,
+    connected: impl Fn(usize, usize, usize) -> bool,
+) -> Clusters {
+    let mut forest = Forest::new(width * height);
+    for site in (0..width * height).filter(|site| occupied(*site)) {
+        let (x, y) = ((site % width) as i64, (site / width) as i64);
+        for (position, (offset_x, offset_y)) in offsets.iter().enumerate() {
+            let (neighbor_x, neighbor_y) = (x + offset_x, y + offset_y);
+            let inside = (0..width as i64).contains(&neighbor_x) && (0..height as i64).contains(&neighbor_y);
+            if !inside && !wrapping {
+                continue;
+            }
+            let neighbor = neighbor_y.rem_euclid(height as i64) as usize * width + neighbor_x.rem_euclid(width as i64) as usize;
+            if occupied(neighbor) && connected(site, neighbor, position) {
+                forest.union(site, neighbor, (*offset_x, *offset_y));
+            }
+        }
+    }
+    let mut labels: MatrixImage<u32> = MatrixImageBuilder::init()
+        .with_initial_value(0)
+        .with_height_and_width(height, width)
+        .build();
+    let mut root_labels = vec![0; width * height];
+    let (mut sizes, mut spans, mut extents) = (Vec::new(), Vec::new(), Vec::new());
+    for site in (0..width * height).filter(|site| occupied(*site)) {
+        let (root, _) = forest.find(site);
+        if root_labels[root] == 0 {
+            sizes.push(0);
+            spans.push(forest.windings[root]);
+            extents.push([false; 4]);
+            root_labels[root] = sizes.len() as u32;
+        }
+        let label = root_labels[root];
+        labels.get_data_mut_ref()[site] = label;
+        sizes[label as usize - 1] += 1;
+        let extent = &mut extents[label as usize - 1];
+        let (x, y) = (site % width, site / width);
+        extent[0] |= x == 0;
+        extent[1] |= x + 1 == width;
+        extent[2] |= y == 0;
+        extent[3] |= y + 1 == height;
+    }
+    if !wrapping {
+        for (span, extent) in spans.iter_mut().zip(&extents) {
+            *span = Spanning { horizontal: extent[0] && extent[1], vertical: extent[2] && extent[3] };
+        }
+    }
+    Clusters {
+        labels,
+        sizes,
+        spans,
+    }
+}
+
+/// Labels the clusters of the sites satisfying the predicate, joined to their neighbors within the
+/// neighborhood of the given size, across the edges when the lattice wraps.
+pub fn label<T: Clone>(matrix: &MatrixImage<T>, size: usize, hood_type: Neighborhood, wrapping: bool, predicate: impl Fn(&T) -> bool) -> Result<Clusters, MatrixError> {
+    let offsets = offsets(size, hood_type)?;
+    let occupied: Vec<bool> = matrix.get_data_ref().iter().map(predicate).collect();
+    Ok(label_with(matrix.get_width(), matrix.get_height(), &offsets, wrapping, |site| occupied[site], |_, _, _| true))
+}
+
+/// Estimates at one occupation probability, averaged over the trials of a sweep.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SweepPoint {
+    pub probability: f64,
+    /// Fraction of the trials with a cluster spanning the lattice vertically.
+    pub spanning: f64,
+    /// Mean fraction of the sites held by the largest cluster.
+    pub largest: f64,
+    /// Mean number of clusters.
+    pub clusters: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Percolation {
+    width: usize,
+    height: usize,
+    wrapping: bool,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    trials: usize,
+    seed: u64,
+}
+
+impl Percolation {
+    /// Sweeps over open lattices of the given width and height with the von Neumann neighborhood,
+    /// of 100 trials per probability.
+    pub fn new(width: usize, height: usize) -> Self {
+        Percolation {
+            width,
+            height,
+            wrapping: false,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            trials: 100,
+            seed: 0,
+        }
+    }
+    pub fn with_wrapping(mut self, wrapping: bool) -> Self {
+        self.wrapping = wrapping;
+        self
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_trials(mut self, trials: usize) -> Self {
+        self.trials = trials.max(1);
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    /// Random lattice of the given trial, whose sites are occupied with the given probability,
+    /// drawn from the streams of the sites so that every trial is reproduced by its seed.
+    pub fn site_lattice(&self, probability: f64, trial: usize) -> MatrixImage<u8> {
+        let empty = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(self.height, self.width)
+            .build();
+        random::map_cells(&empty, self.seed, trial as u64, |_, _, rng| (random::unit_f64(rng) < probability) as u8)
+    }
+    /// Clusters of a site percolation trial.
+    pub fn site_clusters(&self, probability: f64, trial: usize) -> Result<Clusters, MatrixError> {
+        label(&self.site_lattice(probability, trial), self.hood_size, self.hood_type, self.wrapping, |site| *site == 1)
+    }
+    /// Clusters of a bond percolation trial, every site being occupied and every bond between
+    /// neighbors open with the given probability.
+    pub fn bond_clusters(&self, probability: f64, trial: usize) -> Result<Clusters, MatrixError> {
+        let offsets = offsets(self.hood_size, self.hood_type)?;
+        let sites = self.width * self.height;
+        let open = |site: usize, neighbor: usize, position: usize| {
+            let (bond, direction) = if (offsets[position].1, offsets[position].0) > (0, 0) {
+                (site, position)
+            } else {
+                (neighbor, offsets.len() - 1 - position)
+            };
+            let stream = direction * sites + bond;
+            random::unit_f64(&mut Philox::for_cell(self.seed, trial as u64, stream)) < probability
+        };
+        Ok(label_with(self.width, self.height, &offsets, self.wrapping, |_| true, open))
+    }
+    fn sweep(&self, probabilities: &[f64], clusters: impl Fn(f64, usize) -> Result<Clusters, MatrixError>) -> Result<Vec<SweepPoint>, MatrixError> {
+        let sites = (self.width * self.height).max(1) as f64;
+        probabilities
+            .iter()
+            .map(|probability| {
+                let mut point = SweepPoint { probability: *probability, ..SweepPoint::default() };
+                for trial in 0..self.trials {
+                    let clusters = clusters(*probability, trial)?;
+                    point.spanning += clusters.get_spans_ref().iter().any(|span| span.vertical) as usize as f64;
+                    point.largest += clusters.largest().map_or(0, |(_, size)| size) as f64 / sites;
+                    point.clusters += clusters.count() as f64;
+                }
+                let trials = self.trials as f64;
+                point.spanning /= trials;
+                point.largest /= trials;
+                point.clusters /= trials;
+                Ok(point)
+            })
+            .collect()
+    }
+    /// Site percolation estimates at every probability.
+    pub fn site_sweep(&self, probabilities: &[f64]) -> Result<Vec<SweepPoint>, MatrixError> {
+        self.sweep(probabilities, |probability, trial| self.site_clusters(probability, trial))
+    }
+    /// Bond percolation estimates at every probability.
+    pub fn bond_sweep(&self, probabilities: &[f64]) -> Result<Vec<SweepPoint>, MatrixError> {
+        self.sweep(probabilities, |probability, trial| self.bond_clusters(probability, trial))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        Neighborhood,
+        clusters::{
+            self,
+            Percolation,
+            Spanning,
+        },
+        traits::Matrix,
+    };
+
+    #[test]
+    fn labels_follow_the_neighborhood_and_wrapping() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(5, 5).build();
+        for point in [(0_u32, 0_u32), (1, 1), (4, 0), (2, 0), (2, 1), (2, 2), (2, 3), (2, 4), (0, 4)] {
+            matrix.edit_point(point, 1_u8).unwrap();
+        }
+        let open = clusters::label(&matrix, 1, Neighborhood::VonNeumann, false, |site| *site == 1).unwrap();
+        assert_eq!(open.get_sizes_ref(), &vec![1, 6, 1, 1]);
+        assert_eq!(open.get_labels_ref().get_data_ref()[..5], [1, 0, 2, 0, 3]);
+        assert_eq!(open.largest(), Some((2, 6)));
+        assert_eq!(open.get_spans_ref()[1], Spanning { horizontal: false, vertical: true });
+        assert_eq!(open.spanning(), vec![2]);
+        let moore = clusters::label(&matrix, 1, Neighborhood::Moore, false, |site| *site == 1).unwrap();
+        assert_eq!(moore.get_sizes_ref(), &vec![7, 1, 1]);
+        let torus = clusters::label(&matrix, 1, Neighborhood::VonNeumann, true, |site| *site == 1).unwrap();
+        assert_eq!(torus.get_sizes_ref(), &vec![3, 6]);
+        assert_eq!(torus.get_spans_ref(), &vec![Spanning::default(), Spanning { horizontal: false, vertical: true }]);
+        let mut ring: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(6, 6).build();
+        for point in [(1_u32, 0_u32), (1, 5), (2, 5), (3, 5), (3, 0)] {
+            ring.edit_point(point, 1_u8).unwrap();
+        }
+        let ring = clusters::label(&ring, 1, Neighborhood::VonNeumann, true, |site| *site == 1).unwrap();
+        assert_eq!(ring.get_sizes_ref(), &vec![5]);
+        assert!(ring.spanning().is_empty());
+        assert!(clusters::label(&matrix, 1, Neighborhood::Margolus, false, |site| *site == 1).is_err());
+    }
+
+    #[test]
+    fn sweeps_cross_the_percolation_thresholds() {
+        let percolation = Percolation::new(24, 24).with_trials(40).with_seed(49);
+        let sites = percolation.site_sweep(&[0.45, 0.75]).unwrap();
+        assert!(sites[0].spanning < 0.15 && sites[1].spanning > 0.85, "{sites:?}");
+        assert!(sites[1].largest > 0.6 && sites[0].largest < 0.2);
+        let bonds = percolation.bond_sweep(&[0.35, 0.65]).unwrap();
+        assert!(bonds[0].spanning < 0.15 && bonds[1].spanning > 0.85, "{bonds:?}");
+        assert_eq!(percolation.bond_clusters(1.0, 0).unwrap().count(), 1);
+        assert_eq!(percolation.bond_clusters(0.0, 0).unwrap().count(), 576);
+        let torus = percolation.clone().with_wrapping(true).with_trials(10);
+        assert_eq!(torus.site_sweep(&[1.0]).unwrap()[0].spanning, 1.0);
+        assert_eq!(torus.site_clusters(0.6, 3).unwrap().get_labels_ref().get_data_ref().iter().filter(|label| **label > 0).count(),
+            percolation.site_lattice(0.6, 3).get_data_ref().iter().filter(|site| **site == 1).count());
+    }
+}
@@ -1,16 +1,44 @@
 use core::{
     num::TryFromIntError,
+    error::Error,
     fmt::{
         Display,
         Formatter,
         self,
     },
 };
+use alloc::string::String;
 use image::error::ImageError;
 
 #[derive(Debug)]
 pub enum MatrixError {
-    Overflow,
+    /// The point lies outside of a lattice of the given width and height.
+    OutOfBounds {
+        point: (u32, u32),
+        width: usize,
+        height: usize,
+    },
+    /// Two lattices that should share their (width, height) do not.
+    DimensionMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// Two values could not be ordered, e.g. when one of them is NaN.
+    NonComparable,
+    /// A rule description could not be parsed, with the reason.
+    InvalidRule(String),
+    /// An iterative method gave up after the given number of iterations without meeting its
+    /// tolerance, with the last residual or error estimate.
+    NotConverged {
+        iterations: usize,
+        residual: f64,
+    },
+    /// An explicit scheme would exceed its stability limit, with the offending stability number,
+    /// e.g. D·dt/dx² for diffusion, and its limit.
+    Unstable {
+        number: f64,
+        limit: f64,
+    },
     TryFromIntError(TryFromIntError),
     ImageError(ImageError),
 }
@@ -30,8 +58,23 @@ impl From<ImageError> for MatrixError {
 impl Display for MatrixError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         match self {
-            Self::Overflow => {
-                write!(f, "Overflow Image from Matrix")
+            Self::OutOfBounds { point, width, height } => {
+                write!(f, "Point {point:?} out of bounds for a {width}x{height} lattice")
+            },
+            Self::DimensionMismatch { expected, found } => {
+                write!(f, "Dimension mismatch, expected {}x{} but found {}x{}", expected.0, expected.1, found.0, found.1)
+            },
+            Self::NonComparable => {
+                write!(f, "Values can not be compared")
+            },
+            Self::InvalidRule(reason) => {
+                write!(f, "Invalid rule: {reason}")
+            },
+            Self::NotConverged { iterations, residual } => {
+                write!(f, "Not converged after {iterations} iterations, residual {residual}")
+            },
+            Self::Unstable { number, limit } => {
+                write!(f, "Unstable explicit scheme, stability number {number} above the limit {limit}")
             },
             Self::TryFromIntError(e) => {
                 write!(f, "TryFromIntError {e}")
@@ -41,4 +84,14 @@ impl Display for MatrixError {
             },
         }
     }
-}
\ No newline at end of file
+}
+
+impl Error for MatrixError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::TryFromIntError(e) => Some(e),
+            Self::ImageError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -21,10 +21,15 @@ use crate::{
         Matrix,
         Max,
         Optimal,
+        FromChannel,
+        TryMaxBy,
+        OptimalPeer,
     },
     Channel,
+    SourceChannel,
 };
 use image::{
+    DynamicImage,
     Rgba,
     RgbaImage,
 };
@@ -82,11 +87,16 @@ impl<T: Clone + Default + Max + Mul<Output=T>> FourChannelMatrix<T>
     }
     fn check_point_bounds(&self, point: (u32, u32)) -> Result<bool, MatrixError> {
         if point.0 >= *self.get_width() as u32 || point.1 >= *self.get_height() as u32 { 
-            Err(error::MatrixError::Overflow)
+            Err(error::MatrixError::OutOfBounds {
+                point,
+                width: *self.get_width(),
+                height: *self.get_height(),
+            })
         } else {
             Ok(true)
         }
     }
+    #[allow(clippy::wrong_self_convention)]
     fn into_2d_point(&self, absolute_point: usize) -> Result<(u32, u32), MatrixError> {
         let x: u32 = (absolute_point % self.get_width()) as u32;
         let y: u32 = absolute_point as u32 / *self.get_width() as u32; 
@@ -94,14 +104,14 @@ impl<T: Clone + Default + Max + Mul<Output=T>> FourChannelMatrix<T>
         Ok((x,y))
     }
     pub fn multi_channel_image(&self, channel_order:Option<&[Channel; 4]>) -> Result<RgbaImage, MatrixError> {
-        let mut length_holder = 0_usize;
-        let have_same_length = match self.get_data_ref() {
-            [head, tail @ ..] => tail.iter().all(|matrix| {
-                length_holder = head.get_data().len();  // holds the last length value
-                head.get_data().len() == matrix.get_data().len()
-            }),
-        };
-        assert!(have_same_length, "Matrices should have the same length.");
+        let expected = (*self.get_width(), *self.get_height());
+        for matrix in self.get_data_ref() {
+            let found = (matrix.get_width(), matrix.get_height());
+            if found != expected {
+                return Err(error::MatrixError::DimensionMismatch { expected, found });
+            }
+        }
+        let length_holder = expected.0 * expected.1;
         let matrix_order: Vec<MatrixImage<T>> = if let Some(channel_order) = channel_order {
             let mut order = channel_order
                 .iter()
@@ -120,7 +130,7 @@ impl<T: Clone + Default + Max + Mul<Output=T>> FourChannelMatrix<T>
             self.get_data_ref().to_vec()
         };
         
-        let mut image = RgbaImage::new(self.get_width().clone().try_into()?, self.get_height().clone().try_into()?);
+        let mut image = RgbaImage::new((*self.get_width()).try_into()?, (*self.get_height()).try_into()?);
 
         for i in 0..length_holder {
             let (x,y) = self.into_2d_point(i)?;
@@ -137,23 +147,48 @@ impl<T: Clone + Default + Max + Mul<Output=T>> FourChannelMatrix<T>
     }
 }
 
-impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd> From<[MatrixImage<T>; 4]> for FourChannelMatrix<T> {
-    fn from(value: [MatrixImage<T>; 4]) -> Self {
-        let height = value[0].get_height();
-        let width = value[1].get_width();
-        let have_same_length = match value {
-            [ref head, ref tail @ ..] => tail.iter().all(|matrix| {
-                head.get_data().len() == matrix.get_data().len()
-                && head.get_width() == matrix.get_width()
-                && head.get_height() == matrix.get_height()
-            }),
-        };
-        assert!(have_same_length);
+impl<T: Clone + Mul<Output=T>> FourChannelMatrix<T> {
+    /// Reads the red, green, blue and alpha components of the image into the four channels,
+    /// mapping each sample into T with the given function.
+    pub fn from_rgba_image_with(image: &RgbaImage, tone_map: impl Fn(u8) -> T) -> Self {
+        let image = DynamicImage::from(image.clone());
+        let channel = |component| MatrixImage::from_image_with(&image, SourceChannel::Component(component), &tone_map);
         FourChannelMatrix {
+            height: image.height() as usize,
+            width: image.width() as usize,
+            data: [
+                channel(Channel::Red),
+                channel(Channel::Green),
+                channel(Channel::Blue),
+                channel(Channel::Alpha),
+            ],
+        }
+    }
+    /// Reads the four components of the image, inverting the tone mapping used by `multi_channel_image`.
+    pub fn from_rgba_image(image: &RgbaImage) -> Self
+    where T: FromChannel
+    {
+        Self::from_rgba_image_with(image, T::from_channel)
+    }
+}
+
+impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd> TryFrom<[MatrixImage<T>; 4]> for FourChannelMatrix<T> {
+    type Error = MatrixError;
+    /// Fails with `MatrixError::DimensionMismatch` when the channels don't share their width and height.
+    fn try_from(value: [MatrixImage<T>; 4]) -> Result<Self, Self::Error> {
+        let height = value[0].get_height();
+        let width = value[0].get_width();
+        for matrix in &value {
+            let found = (matrix.get_width(), matrix.get_height());
+            if found != (width, height) {
+                return Err(MatrixError::DimensionMismatch { expected: (width, height), found });
+            }
+        }
+        Ok(FourChannelMatrix {
             height,
             width,
             data: value
-        }
+        })
     }
 }
 
@@ -166,17 +201,15 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_size: usize, 
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32)) -> T 
-    ) -> Option<((u32, u32), T)>
+    ) -> OptimalPeer<T>
     {
-        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor))
             })
-            .max_by(|a, b| {
-                a.1.partial_cmp(&b.1).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1)
     }
     fn optimal_peer_internal_values<V>(
         &self, 
@@ -184,17 +217,15 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_size: usize, 
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32)) -> (T,V) 
-    ) -> Option<((u32, u32), (T,V))>
+    ) -> OptimalPeer<(T,V)>
     {
-        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor))
             })
-            .max_by(|a, b| {
-                a.1.0.partial_cmp(&b.1.0).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1.0)
     }
     fn optimal_peer_with_coefficients<U: Copy>(
         &self, 
@@ -203,17 +234,15 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32), U) -> T,
         c: U,
-    ) -> Option<((u32, u32), T)>
+    ) -> OptimalPeer<T>
     {
-        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor, c))
             })
-            .max_by(|a, b| {
-                a.1.partial_cmp(&b.1).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1)
     }
     fn optimal_peer_internal_values_with_coefficients<U, V, F>(
         &self, 
@@ -222,19 +251,17 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_type: Neighborhood, 
         objective: F,
         c: &mut U,
-    ) -> Option<((u32, u32), (T, V))>
+    ) -> OptimalPeer<(T, V)>
     where
         F: for<'a> Fn(&'a Self, (u32, u32), (u32, u32), &'a mut U) -> (T, V),
     {
-        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_data_ref()[0].get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor, c))
             })
-            .max_by(move |a, b| {
-                a.1.0.partial_cmp(&b.1.0).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1.0)
     }
     fn optimal_peer_internal_values_with_coefficients_and_hood<U, V, F>(
         &self, 
@@ -242,7 +269,7 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood: Vec<(u32, u32)>, 
         objective: F,
         c: &mut U,
-    ) -> Option<((u32, u32), (T, V))> 
+    ) -> OptimalPeer<(T, V)> 
     where 
         F: for<'a> Fn(&'a Self, (u32, u32), (u32, u32), &'a mut U) -> (T, V),
         T: PartialOrd,
@@ -252,8 +279,6 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor, c))
             })
-            .max_by(move |a, b| {
-                a.1.0.partial_cmp(&b.1.0).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1.0)
     }
 }
\ No newline at end of file
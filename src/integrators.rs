@@ -0,0 +1,463 @@
+//!   Explicit Runge-Kutta integrators of lattice fields, du/dt = f(u), for the fields of
+//!   `MatrixImage`, `NChannelMatrix` and `FourChannelMatrix` over `f32`, `f64` and their
+//!   `LatticeElement`. The stage buffers are allocated on the first step and reused afterwards.
+
+use core::{
+    fmt::Debug,
+    ops::{
+        Add,
+        Div,
+        Mul,
+        Sub,
+    },
+};
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    four_channel::FourChannelMatrix,
+    n_channel::NChannelMatrix,
+    simulation::Rule,
+    traits::{
+        Matrix,
+        Max,
+        LatticeElement,
+    },
+    error::MatrixError,
+};
+
+/// Real value of a field point.
+pub trait Scalar: Clone {
+    fn to_f64(&self) -> f64;
+    fn from_f64(value: f64) -> Self;
+    /// Adds `scale * other` to the value.
+    fn add_scaled(&mut self, other: &Self, scale: f64);
+}
+
+impl Scalar for f32 {
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) {
+        *self += (*other as f64 * scale) as f32;
+    }
+}
+
+impl Scalar for f64 {
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) {
+        *self += other * scale;
+    }
+}
+
+impl Scalar for LatticeElement<f32> {
+    fn to_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+    fn from_f64(value: f64) -> Self {
+        LatticeElement(value as f32)
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) {
+        self.0.add_scaled(&other.0, scale);
+    }
+}
+
+impl Scalar for LatticeElement<f64> {
+    fn to_f64(&self) -> f64 {
+        self.0
+    }
+    fn from_f64(value: f64) -> Self {
+        LatticeElement(value)
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) {
+        self.0.add_scaled(&other.0, scale);
+    }
+}
+
+/// State of a lattice that can be integrated in time, as a vector of real values.
+pub trait Field: Clone {
+    /// Copies the values of a field of the same size without allocating.
+    fn assign(&mut self, other: &Self) -> Result<(), MatrixError>;
+    /// Adds `scale * other` to the field, point by point.
+    fn add_scaled(&mut self, other: &Self, scale: f64) -> Result<(), MatrixError>;
+    /// Largest ratio, over the points, between the value of `error` and the tolerance
+    /// `absolute + relative * |value|` at the same point of the field.
+    fn error_ratio(&self, error: &Self, absolute: f64, relative: f64) -> Result<f64, MatrixError>;
+}
+
+fn check_dimensions<T: Clone>(matrix: &MatrixImage<T>, other: &MatrixImage<T>) -> Result<(), MatrixError> {
+    let (expected, found) = ((matrix.get_width(), matrix.get_height()), (other.get_width(), other.get_height()));
+    if expected != found {
+        return Err(MatrixError::DimensionMismatch { expected, found });
+    }
+    Ok(())
+}
+
+impl<T: Scalar> Field for MatrixImage<T> {
+    fn assign(&mut self, other: &Self) -> Result<(), MatrixError> {
+        check_dimensions(self, other)?;
+        self.get_data_mut_ref().clone_from_slice(other.get_data_ref());
+        Ok(())
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) -> Result<(), MatrixError> {
+        check_dimensions(self, other)?;
+        for (value, other) in self.get_data_mut_ref().iter_mut().zip(other.get_data_ref()) {
+            value.add_scaled(other, scale);
+        }
+        Ok(())
+    }
+    fn error_ratio(&self, error: &Self, absolute: f64, relative: f64) -> Result<f64, MatrixError> {
+        check_dimensions(self, error)?;
+        Ok(self.get_data_ref()
+            .iter()
+            .zip(error.get_data_ref())
+            .map(|(value, error)| error.to_f64().abs() / (absolute + relative * value.to_f64().abs()))
+            .fold(0.0, f64::max))
+    }
+}
+
+impl<T, const N: usize> Field for NChannelMatrix<T, N>
+where T: Scalar + Mul<Output=T>
+{
+    fn assign(&mut self, other: &Self) -> Result<(), MatrixError> {
+        for (channel, other) in self.get_data_mut_ref().iter_mut().zip(other.get_data_ref()) {
+            channel.assign(other)?;
+        }
+        Ok(())
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) -> Result<(), MatrixError> {
+        for (channel, other) in self.get_data_mut_ref().iter_mut().zip(other.get_data_ref()) {
+            channel.add_scaled(other, scale)?;
+        }
+        Ok(())
+    }
+    fn error_ratio(&self, error: &Self, absolute: f64, relative: f64) -> Result<f64, MatrixError> {
+        let mut ratio: f64 = 0.0;
+        for (channel, error) in self.get_data_ref().iter().zip(error.get_data_ref()) {
+            ratio = ratio.max(channel.error_ratio(error, absolute, relative)?);
+        }
+        Ok(ratio)
+    }
+}
+
+impl<T> Field for FourChannelMatrix<T>
+where
+    T: Scalar + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd,
+    u8: From<T>,
+{
+    fn assign(&mut self, other: &Self) -> Result<(), MatrixError> {
+        for (channel, other) in self.get_data_mut_ref().iter_mut().zip(other.get_data_ref()) {
+            channel.assign(other)?;
+        }
+        Ok(())
+    }
+    fn add_scaled(&mut self, other: &Self, scale: f64) -> Result<(), MatrixError> {
+        for (channel, other) in self.get_data_mut_ref().iter_mut().zip(other.get_data_ref()) {
+            channel.add_scaled(other, scale)?;
+        }
+        Ok(())
+    }
+    fn error_ratio(&self, error: &Self, absolute: f64, relative: f64) -> Result<f64, MatrixError> {
+        let mut ratio: f64 = 0.0;
+        for (channel, error) in self.get_data_ref().iter().zip(error.get_data_ref()) {
+            ratio = ratio.max(channel.error_ratio(error, absolute, relative)?);
+        }
+        Ok(ratio)
+    }
+}
+
+/// Right-hand side f of du/dt = f(u), implemented by any field-to-field function
+/// `FnMut(&F) -> Result<F, MatrixError>`.
+pub trait RightHandSide<F> {
+    /// Writes the derivative of the state into a buffer of the same size.
+    fn evaluate(&mut self, state: &F, derivative: &mut F) -> Result<(), MatrixError>;
+}
+
+impl<F, G> RightHandSide<F> for G
+where G: FnMut(&F) -> Result<F, MatrixError>
+{
+    fn evaluate(&mut self, state: &F, derivative: &mut F) -> Result<(), MatrixError> {
+        *derivative = self(state)?;
+        Ok(())
+    }
+}
+
+/// Right-hand side that writes the derivative into the stage buffer, `FnMut(&F, &mut F)`, to avoid
+/// allocating a field per stage.
+pub struct InPlace<G>(pub G);
+
+impl<F, G> RightHandSide<F> for InPlace<G>
+where G: FnMut(&F, &mut F) -> Result<(), MatrixError>
+{
+    fn evaluate(&mut self, state: &F, derivative: &mut F) -> Result<(), MatrixError> {
+        (self.0)(state, derivative)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    /// Forward Euler, first order.
+    Euler,
+    /// Heun's method, the explicit trapezoidal rule, second order.
+    Heun,
+    /// Classic fourth order Runge-Kutta.
+    RungeKutta4,
+    /// Dormand-Prince 5(4), adapting the time step to keep the error estimate within tolerance.
+    RungeKutta45,
+}
+
+/// Butcher tableau of an explicit Runge-Kutta method, with the weights of the error estimate for
+/// embedded pairs.
+struct Tableau {
+    a: &'static [&'static [f64]],
+    b: &'static [f64],
+    error: Option<&'static [f64]>,
+}
+
+const EULER: Tableau = Tableau {
+    a: &[&[]],
+    b: &[1.0],
+    error: None,
+};
+
+const HEUN: Tableau = Tableau {
+    a: &[&[], &[1.0]],
+    b: &[0.5, 0.5],
+    error: None,
+};
+
+const RUNGE_KUTTA_4: Tableau = Tableau {
+    a: &[&[], &[0.5], &[0.0, 0.5], &[0.0, 0.0, 1.0]],
+    b: &[1.0 / 6.0, 1.0 / 3.0, 1.0 / 3.0, 1.0 / 6.0],
+    error: None,
+};
+
+const DORMAND_PRINCE: Tableau = Tableau {
+    a: &[
+        &[],
+        &[1.0 / 5.0],
+        &[3.0 / 40.0, 9.0 / 40.0],
+        &[44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0],
+        &[19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0],
+        &[9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0],
+        &[35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+    ],
+    b: &[35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0],
+    error: Some(&[71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0]),
+};
+
+impl Method {
+    fn tableau(self) -> &'static Tableau {
+        match self {
+            Self::Euler => &EULER,
+            Self::Heun => &HEUN,
+            Self::RungeKutta4 => &RUNGE_KUTTA_4,
+            Self::RungeKutta45 => &DORMAND_PRINCE,
+        }
+    }
+}
+
+/// Steps of an adaptive method rejected in a row before giving up.
+const MAX_REJECTIONS: usize = 50;
+
+pub struct Integrator<F, R> {
+    method: Method,
+    rhs: R,
+    time: f64,
+    time_step: f64,
+    absolute_tolerance: f64,
+    relative_tolerance: f64,
+    stages: Vec<F>,
+    stage_state: Option<F>,
+    error: Option<F>,
+}
+
+impl<F: Field, R: RightHandSide<F>> Integrator<F, R> {
+    /// Integrator of du/dt = rhs(u) from time 0 with the given time step, the initial one for
+    /// adaptive methods, which must be positive and finite.
+    pub fn new(method: Method, time_step: f64, rhs: R) -> Result<Self, MatrixError> {
+        if !(time_step > 0.0 && time_step.is_finite()) {
+            return Err(MatrixError::InvalidRule(format!("the time step {time_step} should be positive and finite")));
+        }
+        Ok(Integrator {
+            method,
+            rhs,
+            time: 0.0,
+            time_step,
+            absolute_tolerance: 1e-6,
+            relative_tolerance: 1e-3,
+            stages: Vec::new(),
+            stage_state: None,
+            error: None,
+        })
+    }
+    /// Tolerance of adaptive methods on the error estimate of every point, `absolute + relative * |value|`.
+    pub fn with_tolerance(mut self, absolute: f64, relative: f64) -> Self {
+        self.absolute_tolerance = absolute;
+        self.relative_tolerance = relative;
+        self
+    }
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+    pub fn get_time(&self) -> f64 {
+        self.time
+    }
+    /// Time step of the next step, as adapted by adaptive methods.
+    pub fn get_time_step(&self) -> f64 {
+        self.time_step
+    }
+    pub fn get_rhs_mut_ref(&mut self) -> &mut R {
+        &mut self.rhs
+    }
+    /// Advances the state by one step in place and returns the time step taken. Adaptive methods
+    /// retry with smaller steps until the error estimate is within tolerance.
+    pub fn step(&mut self, state: &mut F) -> Result<f64, MatrixError> {
+        let tableau = self.method.tableau();
+        if self.stages.len() != tableau.b.len() {
+            self.stages = vec![state.clone(); tableau.b.len()];
+            self.stage_state = Some(state.clone());
+            self.error = tableau.error.map(|_| state.clone());
+        }
+        let mut rejections = 0;
+        loop {
+            let time_step = self.time_step;
+            self.evaluate_stages(tableau, state, time_step)?;
+            let (Some(weights), Some(error)) = (tableau.error, self.error.as_mut()) else {
+                self.advance(tableau, state, time_step)?;
+                return Ok(time_step);
+            };
+            // Difference of the embedded solutions, h Σ e_i k_i, accumulated on top of the state.
+            error.assign(state)?;
+            for (stage, weight) in self.stages.iter().zip(weights) {
+                error.add_scaled(stage, time_step * weight)?;
+            }
+            error.add_scaled(state, -1.0)?;
+            let ratio = state.error_ratio(error, self.absolute_tolerance, self.relative_tolerance)?;
+            let factor = if ratio == 0.0 { 5.0 } else { (0.9 * ratio.powf(-0.2)).clamp(0.2, 5.0) };
+            if ratio.is_finite() && ratio <= 1.0 {
+                self.advance(tableau, state, time_step)?;
+                self.time_step = time_step * factor;
+                return Ok(time_step);
+            }
+            rejections += 1;
+            if rejections >= MAX_REJECTIONS || ratio.is_nan() {
+                return Err(MatrixError::NotConverged { iterations: rejections, residual: ratio });
+            }
+            self.time_step = time_step * factor;
+        }
+    }
+    /// Steps until the given duration has elapsed, shortening the last step to land on it.
+    pub fn integrate(&mut self, state: &mut F, duration: f64) -> Result<(), MatrixError> {
+        let end = self.time + duration;
+        while self.time < end {
+            let remaining = end - self.time;
+            if self.time_step > remaining {
+                let time_step = self.time_step;
+                self.time_step = remaining;
+                self.step(state)?;
+                if self.method != Method::RungeKutta45 {
+                    self.time_step = time_step;
+                }
+            } else {
+                self.step(state)?;
+            }
+        }
+        Ok(())
+    }
+    /// Evaluates the derivative at every stage, from the state and the previous stages.
+    fn evaluate_stages(&mut self, tableau: &Tableau, state: &F, time_step: f64) -> Result<(), MatrixError> {
+        let stage_state = self.stage_state.as_mut().expect("Allocated on the first step.");
+        for (index, coefficients) in tableau.a.iter().enumerate() {
+            stage_state.assign(state)?;
+            for (previous, coefficient) in self.stages.iter().zip(coefficients.iter()) {
+                if *coefficient != 0.0 {
+                    stage_state.add_scaled(previous, time_step * coefficient)?;
+                }
+            }
+            self.rhs.evaluate(stage_state, &mut self.stages[index])?;
+        }
+        Ok(())
+    }
+    fn advance(&mut self, tableau: &Tableau, state: &mut F, time_step: f64) -> Result<(), MatrixError> {
+        for (stage, weight) in self.stages.iter().zip(tableau.b) {
+            if *weight != 0.0 {
+                state.add_scaled(stage, time_step * weight)?;
+            }
+        }
+        self.time += time_step;
+        Ok(())
+    }
+}
+
+impl<F: Field, R: RightHandSide<F>> Rule<F> for Integrator<F, R> {
+    fn step(&mut self, state: &F) -> Result<F, MatrixError> {
+        let mut next = state.clone();
+        Integrator::step(self, &mut next)?;
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        integrators::{
+            Field,
+            InPlace,
+            Integrator,
+            Method,
+        },
+        traits::Matrix,
+        error::MatrixError,
+    };
+
+    fn decay(state: &MatrixImage<f64>) -> Result<MatrixImage<f64>, MatrixError> {
+        let mut derivative = state.clone();
+        for value in derivative.get_data_mut_ref() {
+            *value = -*value;
+        }
+        Ok(derivative)
+    }
+
+    fn error_after_unit_time(method: Method, time_step: f64) -> f64 {
+        let mut state: MatrixImage<f64> = MatrixImageBuilder::init().with_initial_value(1.0).with_height_and_width(2, 3).build();
+        let mut integrator = Integrator::new(method, time_step, decay).unwrap();
+        integrator.integrate(&mut state, 1.0).unwrap();
+        (state.get_point_value((1_u32, 1_u32)).unwrap() - (-1.0_f64).exp()).abs()
+    }
+
+    #[test]
+    fn fixed_step_methods_converge_at_their_order() {
+        for (method, order) in [(Method::Euler, 1), (Method::Heun, 2), (Method::RungeKutta4, 4)] {
+            let ratio = error_after_unit_time(method, 0.1) / error_after_unit_time(method, 0.05);
+            let expected = 2_f64.powi(order);
+            assert!((ratio / expected - 1.0).abs() < 0.15, "{method:?} error ratio {ratio}");
+        }
+    }
+
+    #[test]
+    fn adaptive_method_meets_tolerance_and_grows_its_step() {
+        let mut state: MatrixImage<f64> = MatrixImageBuilder::init().with_initial_value(1.0).with_height_and_width(2, 2).build();
+        let rhs = InPlace(|state: &MatrixImage<f64>, derivative: &mut MatrixImage<f64>| {
+            derivative.assign(state)?;
+            derivative.add_scaled(state, -2.0)
+        });
+        let mut integrator = Integrator::new(Method::RungeKutta45, 1e-3, rhs).unwrap().with_tolerance(1e-9, 1e-9);
+        integrator.integrate(&mut state, 2.0).unwrap();
+        assert!((integrator.get_time() - 2.0).abs() < 1e-12);
+        assert!((state.get_point_value((0_u32, 1_u32)).unwrap() - (-2.0_f64).exp()).abs() < 1e-7);
+        assert!(integrator.get_time_step() > 1e-3);
+        assert!(Integrator::new(Method::Euler, 0.0, decay).is_err());
+        assert!(Integrator::new(Method::Heun, -0.1, decay).is_err());
+        assert!(Integrator::new(Method::RungeKutta4, f64::NAN, decay).is_err());
+    }
+}
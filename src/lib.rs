@@ -6,6 +6,15 @@ pub mod traits;
 pub mod four_channel;
 pub mod n_channel;
 pub mod matrix_image;
+pub mod simulation;
+pub mod palette;
+pub mod automata;
+pub mod agents;
+pub mod models;
+pub mod integrators;
+pub mod random;
+pub mod schedule;
+pub mod clusters;
 pub use matrix_image::{
     MatrixImageBuilder,
     MatrixImage,
@@ -29,21 +38,78 @@ pub enum Channel {
     Alpha,
 }
 
-#[derive(Clone, Copy)]
+impl Channel {
+    /// Position of the channel within an RGBA pixel.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Red => 0,
+            Self::Green => 1,
+            Self::Blue => 2,
+            Self::Alpha => 3,
+        }
+    }
+}
+
+/// Selects the sample read from a decoded image when importing it into a lattice.
+pub enum SourceChannel {
+    /// Luminance of the pixel, as computed by `image`.
+    Luma,
+    /// A single RGBA component of the pixel.
+    Component(Channel),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Neighborhood {
     VonNeumann,
     Moore,
+    /// 2×2 blocks partitioning the lattice, aligned on even coordinates on even steps and offset by
+    /// one cell on odd steps, as stepped by `MatrixImage::map_blocks`. Neighborhood methods see the
+    /// block of the point on even steps, whatever the size; rules that update every cell from its
+    /// neighbors reject it.
+    Margolus,
 }
 
 impl Neighborhood {
+    /// Number of points returned by `get_lattice_neighborhood` for the given size, center included,
+    /// on a lattice large enough for the neighborhood not to wrap onto itself: (2·size + 1)² for
+    /// the Moore neighborhood.
     pub fn length(self, size: usize) -> usize {
         match self {
             Self::VonNeumann => {
                 size * size + ((1 + size) * (1 + size))
             },
             Self::Moore => {
-                3_usize.pow(size as u32) - 1
+                (2 * size + 1) * (2 * size + 1)
             },
+            Self::Margolus => 4,
+        }
+    }
+    /// Factor between the sum of differences computed by `laplace_operator` and the Laplacian on a
+    /// unit grid, half the sum of the squared horizontal offsets of the neighborhood: 1 for the
+    /// von Neumann neighborhood of size 1 and 3 for the Moore neighborhood of size 1. None for the
+    /// Margolus block, which lies on one side of the point and whose differences do not approximate
+    /// a Laplacian.
+    pub fn laplace_scale(self, size: usize) -> Option<usize> {
+        if self == Self::Margolus {
+            return None;
+        }
+        let size = size as i64;
+        let mut sum = 0;
+        for y_diff in -size..=size {
+            for x_diff in -size..=size {
+                if self == Self::Moore || x_diff.abs() + y_diff.abs() <= size {
+                    sum += x_diff * x_diff;
+                }
+            }
+        }
+        Some((sum / 2) as usize)
+    }
+    /// The neighborhood itself, unless it is the Margolus block, which partitions the lattice and is
+    /// stepped by `MatrixImage::map_blocks` rather than surrounding every cell with neighbors.
+    pub(crate) fn per_cell(self) -> Result<Self, error::MatrixError> {
+        match self {
+            Self::Margolus => Err(error::MatrixError::InvalidRule(alloc::string::String::from("the Margolus block partitions the lattice and has no per-cell neighbors"))),
+            hood_type => Ok(hood_type),
         }
     }
 }
\ No newline at end of file
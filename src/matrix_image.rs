@@ -14,13 +14,21 @@ use core::ops::{
     Add,
     Sub,
 };
+use image::DynamicImage;
 use crate::{
     Neighborhood,
+    SourceChannel,
     Matrix,
     Draw,
     DrawMultiChannel,
     Optimal,
-    traits,
+    traits::{
+        self,
+        FromChannel,
+        TryMaxBy,
+        OptimalPeer,
+    },
+    random,
     error,
 };
 
@@ -84,11 +92,166 @@ impl<T: Clone + Default + traits::Max> MatrixImageBuilder<T> {
         }
         self
     }
+    /// Draws every value from the stream of its cell at step 0 of the given seed, see
+    /// `random::Philox::for_cell`, so that the lattice only depends on the seed.
+    pub fn with_seeded_generator(mut self, seed: u64, generator: impl Fn(&mut random::Philox) -> T) -> Self {
+        for (index, value) in self.template.data.iter_mut().enumerate() {
+            *value = generator(&mut random::Philox::for_cell(seed, 0, index));
+        }
+        self
+    }
     pub fn build(&self) -> MatrixImage<T> {
         self.template.clone()
     }
 }
 
+impl<T: Clone> MatrixImage<T> {
+    /// Values of the points, row after row.
+    pub fn get_data_ref(&self) -> &Vec<T> {
+        &self.data
+    }
+    /// Values of the points, row after row, editable in place.
+    pub fn get_data_mut_ref(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+    /// Point at the given offset from `point`, wrapped around the lattice edges as in `get_lattice_neighborhood`.
+    pub fn offset_point(&self, point: (u32, u32), offset: (i64, i64)) -> (u32, u32) {
+        let x = (point.0 as i64 + offset.0).rem_euclid(self.width as i64);
+        let y = (point.1 as i64 + offset.1).rem_euclid(self.height as i64);
+        (x as u32, y as u32)
+    }
+    /// Reads the selected channel of a decoded image into a lattice with the image's width and height,
+    /// mapping each sample into T with the given function.
+    pub fn from_image_with(image: &DynamicImage, channel: SourceChannel, tone_map: impl Fn(u8) -> T) -> Self {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let data = match channel {
+            SourceChannel::Luma => image
+                .to_luma8()
+                .pixels()
+                .map(|pixel| tone_map(pixel.0[0]))
+                .collect(),
+            SourceChannel::Component(component) => image
+                .to_rgba8()
+                .pixels()
+                .map(|pixel| tone_map(pixel.0[component.index()]))
+                .collect(),
+        };
+        MatrixImage {
+            height,
+            width,
+            data,
+        }
+    }
+    /// Reads the selected channel of a decoded image, inverting the tone mapping used by `Draw`.
+    pub fn from_image(image: &DynamicImage, channel: SourceChannel) -> Self
+    where T: FromChannel
+    {
+        Self::from_image_with(image, channel, T::from_channel)
+    }
+    /// Decodes an encoded image in any format supported by `image` and reads the selected channel.
+    pub fn from_image_bytes(bytes: &[u8], channel: SourceChannel) -> Result<Self, error::MatrixError>
+    where T: FromChannel
+    {
+        let image = image::load_from_memory(bytes)?;
+        Ok(Self::from_image(&image, channel))
+    }
+    /// Counts, for every point, the points of its neighborhood, center included, whose value satisfies
+    /// the predicate, as `hood_count` would. Rows are summed once with periodic prefix sums, so the cost
+    /// doesn't depend on the radius of Moore neighborhoods and is linear in the radius of von Neumann ones.
+    /// Neighborhoods larger than the lattice wrap around it and count points as many times as they cover them.
+    pub fn hood_counts(&self, size: usize, hood_type: Neighborhood, predicate: impl Fn(&T) -> bool) -> MatrixImage<usize> {
+        let (width, height) = (self.width, self.height);
+        let mut counts = vec![0; width * height];
+        if counts.is_empty() {
+            return MatrixImage { height, width, data: counts };
+        }
+        let row_prefixes: Vec<Vec<usize>> = (0..height)
+            .map(|y| periodic_prefix(width, |x| predicate(&self.data[y * width + x]) as usize))
+            .collect();
+        match hood_type {
+            Neighborhood::Moore => {
+                let row_sums: Vec<usize> = (0..width * height)
+                    .map(|absolute_point| periodic_window(&row_prefixes[absolute_point / width], width, absolute_point % width, size))
+                    .collect();
+                for x in 0..width {
+                    let column_prefix = periodic_prefix(height, |y| row_sums[y * width + x]);
+                    for y in 0..height {
+                        counts[y * width + x] = periodic_window(&column_prefix, height, y, size);
+                    }
+                }
+            },
+            Neighborhood::VonNeumann => {
+                for y in 0..height {
+                    for x in 0..width {
+                        counts[y * width + x] = (0..=2 * size)
+                            .map(|row| {
+                                let y_diff = row as i64 - size as i64;
+                                let hood_y = (y as i64 + y_diff).rem_euclid(height as i64) as usize;
+                                periodic_window(&row_prefixes[hood_y], width, x, size - y_diff.unsigned_abs() as usize)
+                            })
+                            .sum();
+                    }
+                }
+            },
+            Neighborhood::Margolus => {
+                for y in 0..height {
+                    for x in 0..width {
+                        let block = block_indices(width, height, x - x % 2, y - y % 2);
+                        counts[y * width + x] = block.iter().filter(|index| predicate(&self.data[**index])).count();
+                    }
+                }
+            },
+        }
+        MatrixImage { height, width, data: counts }
+    }
+    /// Replaces every 2×2 block of the Margolus partition of the given phase, aligned on even
+    /// coordinates on even phases and offset by one cell, wrapping around the edges, on odd ones.
+    /// Blocks are passed and returned as [top left, top right, bottom left, bottom right]. The
+    /// lattice must have an even width and height for the blocks to partition it.
+    pub fn map_blocks(&self, phase: u64, update: impl Fn([T; 4]) -> [T; 4]) -> Result<Self, error::MatrixError> {
+        let (width, height) = (self.width, self.height);
+        if width % 2 == 1 || height % 2 == 1 {
+            return Err(error::MatrixError::DimensionMismatch { expected: (width + width % 2, height + height % 2), found: (width, height) });
+        }
+        let offset = (phase % 2) as usize;
+        let mut next = self.clone();
+        for y in (offset..height + offset).step_by(2) {
+            for x in (offset..width + offset).step_by(2) {
+                let indices = block_indices(width, height, x, y);
+                let block = update(indices.map(|index| self.data[index].clone()));
+                for (index, value) in indices.into_iter().zip(block) {
+                    next.data[index] = value;
+                }
+            }
+        }
+        Ok(next)
+    }
+}
+
+/// Absolute indices of the 2×2 block with the given top left corner, wrapped around the edges.
+fn block_indices(width: usize, height: usize, x: usize, y: usize) -> [usize; 4] {
+    let (right, bottom) = ((x + 1) % width, (y + 1) % height);
+    let (x, y) = (x % width, y % height);
+    [y * width + x, y * width + right, bottom * width + x, bottom * width + right]
+}
+
+/// Prefix sums of a sequence with the given period, over two periods so that windows can wrap once.
+fn periodic_prefix(period: usize, value: impl Fn(usize) -> usize) -> Vec<usize> {
+    let mut prefix = vec![0; 2 * period + 1];
+    for index in 0..2 * period {
+        prefix[index + 1] = prefix[index] + value(index % period);
+    }
+    prefix
+}
+
+/// Sum of a periodic sequence over the window of the given half width around the center, counting
+/// whole periods as many times as the window covers them.
+fn periodic_window(prefix: &[usize], period: usize, center: usize, half_width: usize) -> usize {
+    let length = 2 * half_width + 1;
+    let start = (center + period - half_width % period) % period;
+    (length / period) * prefix[period] + prefix[start + length % period] - prefix[start]
+}
+
 impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd> MatrixImage<T> {
     pub fn get_lattice_neighborhood<U: Into<i64>>(&self, point: (U, U), distance: usize, hood_type: Neighborhood) -> Vec<(u32, u32)> {
         let distance = distance as i64;
@@ -132,7 +295,17 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
                         point_set.push((x_left.try_into().unwrap(), y_left.try_into().unwrap())); // TODO: manage overflow error.
                     };
                 }
-            }
+            },
+            Neighborhood::Margolus => {
+                let (block_x, block_y) = (point_x - point_x.rem_euclid(2), point_y - point_y.rem_euclid(2));
+                for y_diff in 0..2 {
+                    for x_diff in 0..2 {
+                        let x = (block_x + x_diff).rem_euclid(self.width as i64);
+                        let y = (block_y + y_diff).rem_euclid(self.height as i64);
+                        point_set.push((x as u32, y as u32));
+                    }
+                }
+            },
         };
         point_set
     }
@@ -149,6 +322,17 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         };
         Ok(( sum - T::default(), neighborhood.len() ))
     }
+    /// Counts the points of the neighborhood, center included, whose value satisfies the predicate.
+    pub fn hood_count(&self, point: (u32, u32), size: usize, hood_type: Neighborhood, predicate: impl Fn(&T) -> bool) -> Result<usize, error::MatrixError> {
+        let neighborhood = self.get_lattice_neighborhood(point, size, hood_type);
+        let mut count = 0;
+        for hood_point in &neighborhood {
+            if predicate(&self.get_point_value(*hood_point)?) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
     /// Evaluates the Discrete Laplace Operator for the given point coordinates and the size of the neighborhood.
     /// Given that the Neighborhood includes the value of the point being evaluated, we need to substract it from
     /// the neighborhood summation too.
@@ -228,17 +412,15 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_size: usize, 
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32)) -> T 
-    ) -> Option<((u32, u32), T)>
+    ) -> OptimalPeer<T>
     {
-        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor))
             })
-            .max_by(|a, b| {
-                a.1.partial_cmp(&b.1).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1)
     }
     fn optimal_peer_internal_values<V>(
         &self, 
@@ -246,17 +428,15 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_size: usize, 
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32)) -> (T,V) 
-    ) -> Option<((u32, u32), (T,V))>
+    ) -> OptimalPeer<(T,V)>
     {
-        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor))
             })
-            .max_by(|a, b| {
-                a.1.0.partial_cmp(&b.1.0).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1.0)
     }
     fn optimal_peer_with_coefficients<U: Copy>(
         &self, 
@@ -265,17 +445,15 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32), U) -> T,
         c: U,
-    ) -> Option<((u32, u32), T)>
+    ) -> OptimalPeer<T>
     {
-        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor, c))
             })
-            .max_by(|a, b| {
-                a.1.partial_cmp(&b.1).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1)
     }
     fn optimal_peer_internal_values_with_coefficients<U, V, F>(
         &self, 
@@ -284,19 +462,17 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood_type: Neighborhood, 
         objective: F,
         c: &mut U,
-    ) -> Option<((u32, u32), (T, V))>
+    ) -> OptimalPeer<(T, V)>
     where 
         F: for<'a> Fn(&'a Self, (u32, u32), (u32, u32), &'a mut U) -> (T, V),
     {
-        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type);
+        let hood = self.get_lattice_neighborhood(self_point, hood_size, hood_type.per_cell()?);
         hood
             .into_iter()
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor, c))
             })
-            .max_by(move |a, b| {
-                a.1.0.partial_cmp(&b.1.0).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1.0)
     }
     fn optimal_peer_internal_values_with_coefficients_and_hood<U, V, F>(
         &self, 
@@ -304,7 +480,7 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
         hood: Vec<(u32, u32)>, 
         objective: F,
         c: &mut U,
-    ) -> Option<((u32, u32), (T, V))>
+    ) -> OptimalPeer<(T, V)>
     where 
         F: for<'a> Fn(&'a Self, (u32, u32), (u32, u32), &'a mut U) -> (T, V),
     {
@@ -313,8 +489,112 @@ impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> +
             .map( |neighbor| {
                 (neighbor, objective(self, self_point, neighbor, c))
             })
-            .max_by(move |a, b| {
-                a.1.0.partial_cmp(&b.1.0).expect("PartialOrd not implemented for type T.")
-            })
+            .try_max_by(|evaluated| &evaluated.1.0)
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImageBuilder,
+        MatrixImage,
+        Neighborhood,
+        Channel,
+        SourceChannel,
+        four_channel::FourChannelMatrix,
+        traits::{
+            Matrix,
+            Draw,
+            Optimal,
+            LatticeElement,
+        },
+        error::MatrixError,
+    };
+    use image::DynamicImage;
+
+    type Atom = LatticeElement<f32>;
+
+    #[test]
+    fn out_of_bounds_reports_point_and_size() {
+        let matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_height_and_width(3, 4).build();
+        match matrix.get_point_value((4_u32, 1_u32)) {
+            Err(MatrixError::OutOfBounds { point, width, height }) => {
+                assert_eq!((point, width, height), ((4, 1), 4, 3));
+            },
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn four_channel_dimension_mismatch() {
+        let channel = |height, width| MatrixImageBuilder::<u8>::init().with_height_and_width(height, width).build();
+        let channels = [channel(3, 3), channel(3, 3), channel(3, 2), channel(3, 3)];
+        assert!(matches!(
+            FourChannelMatrix::try_from(channels),
+            Err(MatrixError::DimensionMismatch { expected: (3, 3), found: (2, 3) })
+        ));
+    }
+
+    #[test]
+    fn optimal_peer_non_comparable() {
+        let mut matrix: MatrixImage<Atom> = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(1.0))
+            .with_height_and_width(3, 3)
+            .build();
+        let objective = |m: &MatrixImage<Atom>, _: (u32, u32), y: (u32, u32)| m.get_point_value(y).unwrap();
+        assert_eq!(matrix.optimal_peer((1, 1), 1, Neighborhood::VonNeumann, objective).unwrap().map(|o| o.1), Some(LatticeElement(1.0)));
+        matrix.edit_point((1_u32, 0_u32), f32::NAN).unwrap();
+        assert!(matches!(
+            matrix.optimal_peer((1, 1), 1, Neighborhood::VonNeumann, objective),
+            Err(MatrixError::NonComparable)
+        ));
+    }
+
+    #[test]
+    fn draw_and_import_round_trip() {
+        let matrix: MatrixImage<LatticeElement<u32>> = MatrixImageBuilder::init()
+            .with_height_and_width(4, 6)
+            .with_generator(|| LatticeElement(u32::MAX / 3))
+            .build();
+        let image = matrix.draw(Channel::Blue).unwrap();
+        let imported: MatrixImage<LatticeElement<u32>> = MatrixImage::from_image(&DynamicImage::from(image.clone()), SourceChannel::Component(Channel::Blue));
+        assert_eq!((imported.get_width(), imported.get_height()), (6, 4));
+        assert_eq!(imported.draw(Channel::Blue).unwrap(), image);
+        let multi_channel: FourChannelMatrix<u8> = FourChannelMatrix::from_rgba_image(&image);
+        assert_eq!(multi_channel.get_data_ref()[3].get_point_value((0_u32, 0_u32)).unwrap(), 255);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn hood_counts_match_hood_count() {
+        let mut matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(9, 7).build();
+        for absolute_point in (0..63_usize).filter(|point| (point * 7 + point / 5) % 3 == 0) {
+            let point = matrix.into_2d_point(absolute_point).unwrap();
+            matrix.edit_point(point, 1).unwrap();
+        }
+        for (hood_type, sizes) in [(Neighborhood::Moore, 0..=5), (Neighborhood::VonNeumann, 0..=3)] {
+            for size in sizes {
+                let counts = matrix.hood_counts(size, hood_type, |value| *value == 1);
+                for absolute_point in 0..63 {
+                    let point = matrix.into_2d_point(absolute_point).unwrap();
+                    assert_eq!(
+                        counts.get_point_value(point).unwrap(),
+                        matrix.hood_count(point, size, hood_type, |value| *value == 1).unwrap(),
+                        "{hood_type:?} of size {size} at {point:?}",
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighborhood_length_counts_the_returned_points() {
+        assert_eq!(Neighborhood::Moore.length(1), 9);
+        assert_eq!(Neighborhood::Moore.length(2), 25);
+        assert_eq!(Neighborhood::VonNeumann.length(1), 5);
+        let matrix: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(11, 11).build();
+        for hood_type in [Neighborhood::Moore, Neighborhood::VonNeumann] {
+            for size in 0..=3 {
+                assert_eq!(matrix.get_lattice_neighborhood((5, 5), size, hood_type).len(), hood_type.length(size), "{hood_type:?} of size {size}");
+            }
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//!   Spatial evolutionary games. Every site plays a strategy against each of its neighbors, and
+//!   itself when self-interaction is on as in the lattice of Nowak and May, accumulating the payoffs
+//!   of the game's matrix. Every site then finds its best-scoring peer with `Optimal::optimal_peer`
+//!   over the payoffs, itself included, and imitates its strategy, keeping its own on ties.
+//!
+//!   With Fermi noise K > 0 the strategy of the best peer is adopted with the probability
+//!   1 / (1 + exp((Pₓ - Pᵧ) / K)), where Pₓ is the payoff of the site and Pᵧ the one of the peer,
+//!   drawing from the stream of the site at every step.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    random::{
+        self,
+        Philox,
+    },
+    simulation::Rule,
+    traits::{
+        Matrix,
+        Optimal,
+    },
+    error::MatrixError,
+};
+use super::stencil_neighbors;
+
+pub const COOPERATE: u8 = 0;
+pub const DEFECT: u8 = 1;
+
+pub const ROCK: u8 = 0;
+pub const PAPER: u8 = 1;
+pub const SCISSORS: u8 = 2;
+
+pub type Strategies = MatrixImage<u8>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Game {
+    /// Payoffs of a player, indexed by its strategy, then by the strategy of the opponent.
+    payoffs: Vec<Vec<f64>>,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    self_interaction: bool,
+    noise: f64,
+    seed: u64,
+    step: u64,
+    series: Vec<Vec<f64>>,
+    palette: Palette,
+}
+
+impl Game {
+    /// Game of the given square payoff matrix, of at most 256 strategies, played over the Moore
+    /// neighborhood of size 1 with self-interaction and imitated without noise.
+    pub fn new(payoffs: Vec<Vec<f64>>) -> Result<Self, MatrixError> {
+        let strategies = payoffs.len();
+        if strategies == 0 || strategies > 256 || payoffs.iter().any(|row| row.len() != strategies) {
+            return Err(MatrixError::InvalidRule(format!("a game needs a square payoff matrix of 1 to 256 strategies, not {strategies} rows")));
+        }
+        let palette = match strategies {
+            1..=3 => Palette::new(
+                [Rgba([30, 60, 220, 255]), Rgba([220, 30, 30, 255]), Rgba([250, 200, 40, 255])][..strategies].to_vec(),
+            ),
+            _ => Palette::gradient(Rgba([30, 60, 220, 255]), Rgba([250, 200, 40, 255]), strategies),
+        };
+        Ok(Game {
+            payoffs,
+            hood_type: Neighborhood::Moore,
+            hood_size: 1,
+            self_interaction: true,
+            noise: 0.0,
+            seed: 0,
+            step: 0,
+            series: Vec::new(),
+            palette,
+        })
+    }
+    /// Weak prisoner's dilemma of Nowak and May, mutual cooperation paying 1, defection against a
+    /// cooperator paying the temptation b > 1 and any other outcome 0.
+    pub fn prisoners_dilemma(temptation: f64) -> Self {
+        Self::new(vec![vec![1.0, 0.0], vec![temptation, 0.0]]).expect("The matrix is square.")
+    }
+    /// Snowdrift game of Hauert and Doebeli, where cooperating costs c and brings a benefit of 1 to
+    /// both players, given the cost-to-benefit ratio r = c / (2 - c) in (0, 1).
+    pub fn snowdrift(ratio: f64) -> Self {
+        let cost = 2.0 * ratio / (1.0 + ratio);
+        Self::new(vec![vec![1.0 - cost / 2.0, 1.0 - cost], vec![1.0, 0.0]]).expect("The matrix is square.")
+    }
+    /// Rock-paper-scissors, a win paying 1, a loss -1 and a draw 0.
+    pub fn rock_paper_scissors() -> Self {
+        Self::new(vec![vec![0.0, -1.0, 1.0], vec![1.0, 0.0, -1.0], vec![-1.0, 1.0, 0.0]]).expect("The matrix is square.")
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    /// Whether every site also plays against itself, on by default.
+    pub fn with_self_interaction(mut self, self_interaction: bool) -> Self {
+        self.self_interaction = self_interaction;
+        self
+    }
+    /// Fermi noise K of the imitation, 0 for the deterministic imitation of better peers.
+    pub fn with_noise(mut self, noise: f64) -> Self {
+        self.noise = noise;
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_strategies(&self) -> usize {
+        self.payoffs.len()
+    }
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+    /// Fractions of the sites playing every strategy in the initial state and after every step
+    /// taken as a `Rule`.
+    pub fn get_series_ref(&self) -> &Vec<Vec<f64>> {
+        &self.series
+    }
+    /// Fraction of cooperators, strategy 0, over the series.
+    pub fn cooperation(&self) -> Vec<f64> {
+        self.series.iter().map(|fractions| fractions[COOPERATE as usize]).collect()
+    }
+    /// Lattice of strategies drawn from the seed with the given weights, one per strategy.
+    pub fn initial_state(&self, width: usize, height: usize, weights: &[f64]) -> Result<Strategies, MatrixError> {
+        if weights.len() != self.get_strategies() || weights.iter().any(|weight| *weight < 0.0) {
+            return Err(MatrixError::InvalidRule(format!("{weights:?} should have a non-negative weight for each of the {} strategies", self.get_strategies())));
+        }
+        let total: f64 = weights.iter().sum();
+        let mut rng = Philox::new(self.seed);
+        let mut strategies: Strategies = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(height, width)
+            .build();
+        for strategy in strategies.get_data_mut_ref() {
+            let mut uniform = random::unit_f64(&mut rng) * total;
+            *strategy = weights
+                .iter()
+                .position(|weight| {
+                    uniform -= weight;
+                    uniform < 0.0
+                })
+                .unwrap_or(weights.len() - 1) as u8;
+        }
+        Ok(strategies)
+    }
+    /// Lattice of strategies with every site playing the given one except at the given points.
+    pub fn uniform_state(width: usize, height: usize, strategy: u8, others: &[((u32, u32), u8)]) -> Result<Strategies, MatrixError> {
+        let mut strategies = MatrixImageBuilder::init()
+            .with_initial_value(strategy)
+            .with_height_and_width(height, width)
+            .build();
+        for (point, other) in others {
+            strategies.edit_point(*point, *other)?;
+        }
+        Ok(strategies)
+    }
+    fn payoff(&self, strategy: u8, opponent: u8) -> Result<f64, MatrixError> {
+        self.payoffs
+            .get(strategy as usize)
+            .and_then(|row| row.get(opponent as usize))
+            .copied()
+            .ok_or_else(|| MatrixError::InvalidRule(format!("strategy {} is outside the {} strategies of the game", strategy.max(opponent), self.get_strategies())))
+    }
+    /// Payoffs accumulated by every site against its neighbors.
+    pub fn payoffs(&self, strategies: &Strategies) -> Result<MatrixImage<f64>, MatrixError> {
+        let neighbors = stencil_neighbors(strategies, self.hood_size, self.hood_type)?;
+        let sites = strategies.get_data_ref();
+        let mut payoffs: MatrixImage<f64> = MatrixImageBuilder::init()
+            .with_initial_value(0.0)
+            .with_height_and_width(strategies.get_height(), strategies.get_width())
+            .build();
+        for (index, payoff) in payoffs.get_data_mut_ref().iter_mut().enumerate() {
+            let strategy = sites[index];
+            for neighbor in &neighbors[index] {
+                *payoff += self.payoff(strategy, sites[*neighbor])?;
+            }
+            if self.self_interaction {
+                *payoff += self.payoff(strategy, strategy)?;
+            }
+        }
+        Ok(payoffs)
+    }
+    /// Strategies after every site imitates its best-scoring peer, drawing from the streams of the
+    /// sites at the given step when there is noise.
+    pub fn imitate(&self, strategies: &Strategies, payoffs: &MatrixImage<f64>, step: u64) -> Result<Strategies, MatrixError> {
+        let objective = |payoffs: &MatrixImage<f64>, _, peer| payoffs.get_point_value(peer).unwrap_or(f64::NEG_INFINITY);
+        let mut next = strategies.clone();
+        for (index, strategy) in next.get_data_mut_ref().iter_mut().enumerate() {
+            let point = strategies.into_2d_point(index)?;
+            let own = payoffs.get_data_ref()[index];
+            let Some((peer, best)) = payoffs.optimal_peer(point, self.hood_size, self.hood_type, objective)? else {
+                continue;
+            };
+            let adopted = if self.noise > 0.0 {
+                random::unit_f64(&mut Philox::for_cell(self.seed, step, index)) < 1.0 / (1.0 + ((own - best) / self.noise).exp())
+            } else {
+                best > own
+            };
+            if adopted {
+                *strategy = strategies.get_point_value(peer)?;
+            }
+        }
+        Ok(next)
+    }
+    /// Plays one round and imitates.
+    pub fn next_generation(&self, strategies: &Strategies, step: u64) -> Result<Strategies, MatrixError> {
+        self.imitate(strategies, &self.payoffs(strategies)?, step)
+    }
+    /// Fraction of the sites playing every strategy.
+    pub fn fractions(&self, strategies: &Strategies) -> Vec<f64> {
+        let mut counts = vec![0_usize; self.get_strategies()];
+        for strategy in strategies.get_data_ref() {
+            if let Some(count) = counts.get_mut(*strategy as usize) {
+                *count += 1;
+            }
+        }
+        let sites = strategies.get_data_ref().len().max(1) as f64;
+        counts.into_iter().map(|count| count as f64 / sites).collect()
+    }
+    /// Draws the strategy map through the palette.
+    pub fn draw(&self, strategies: &Strategies) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw(strategies)
+    }
+    /// Draws the strategy changes of a two-strategy game as Nowak and May did: cooperators staying
+    /// cooperators in blue, defectors staying defectors in red, new defectors in yellow and new
+    /// cooperators in green.
+    pub fn draw_changes(previous: &Strategies, next: &Strategies) -> Result<RgbaImage, MatrixError> {
+        if (previous.get_width(), previous.get_height()) != (next.get_width(), next.get_height()) {
+            return Err(MatrixError::DimensionMismatch { expected: (previous.get_width(), previous.get_height()), found: (next.get_width(), next.get_height()) });
+        }
+        let mut image = RgbaImage::new(next.get_width().try_into()?, next.get_height().try_into()?);
+        for ((pixel, before), after) in image.pixels_mut().zip(previous.get_data_ref()).zip(next.get_data_ref()) {
+            *pixel = match (*before, *after) {
+                (COOPERATE, COOPERATE) => Rgba([30, 60, 220, 255]),
+                (COOPERATE, _) => Rgba([250, 200, 40, 255]),
+                (_, COOPERATE) => Rgba([40, 180, 60, 255]),
+                _ => Rgba([220, 30, 30, 255]),
+            };
+        }
+        Ok(image)
+    }
+}
+
+impl Rule<Strategies> for Game {
+    fn step(&mut self, state: &Strategies) -> Result<Strategies, MatrixError> {
+        if self.series.is_empty() {
+            self.series.push(self.fractions(state));
+        }
+        let next = self.next_generation(state, self.step)?;
+        self.step += 1;
+        self.series.push(self.fractions(&next));
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        models::games::{
+            COOPERATE,
+            DEFECT,
+            Game,
+        },
+        simulation::Rule,
+        traits::{
+            Matrix,
+            Optimal,
+        },
+    };
+
+    #[test]
+    fn defectors_invade_cooperators_in_blocks() {
+        let mut game = Game::prisoners_dilemma(1.875);
+        let state = Game::uniform_state(9, 9, COOPERATE, &[((4, 4), DEFECT)]).unwrap();
+        let payoffs = game.payoffs(&state).unwrap();
+        assert_eq!(payoffs.get_point_value((4_u32, 4_u32)).unwrap(), 15.0);
+        assert_eq!(payoffs.get_point_value((3_u32, 4_u32)).unwrap(), 8.0);
+        assert_eq!(payoffs.get_point_value((0_u32, 0_u32)).unwrap(), 9.0);
+        let next = game.step(&state).unwrap();
+        let defectors: usize = next.get_data_ref().iter().map(|strategy| *strategy as usize).sum();
+        assert_eq!(defectors, 9);
+        assert_eq!(next.get_point_value((2_u32, 2_u32)).unwrap(), COOPERATE);
+        assert_eq!(game.cooperation(), vec![80.0 / 81.0, 72.0 / 81.0]);
+        assert_eq!(Game::prisoners_dilemma(0.9).next_generation(&state, 0).unwrap(), Game::uniform_state(9, 9, COOPERATE, &[]).unwrap());
+        assert!(Game::new(vec![vec![1.0, 0.0]]).is_err());
+        assert!(game.clone().with_neighborhood(Neighborhood::Margolus, 1).next_generation(&state, 0).is_err());
+        assert!(payoffs.optimal_peer((4, 4), 1, Neighborhood::Margolus, |payoffs, _, peer| payoffs.get_point_value(peer).unwrap()).is_err());
+    }
+
+    #[test]
+    fn noisy_games_keep_strategies_coexisting() {
+        let mut cyclic = Game::rock_paper_scissors().with_noise(0.1).with_seed(50);
+        let mut strategies = cyclic.initial_state(48, 48, &[1.0, 1.0, 1.0]).unwrap();
+        assert_eq!(strategies, cyclic.initial_state(48, 48, &[1.0, 1.0, 1.0]).unwrap());
+        for _ in 0..60 {
+            strategies = cyclic.step(&strategies).unwrap();
+        }
+        assert!(cyclic.get_series_ref().iter().all(|fractions| fractions.iter().all(|fraction| *fraction > 0.05)), "{:?}", cyclic.get_series_ref().last());
+        assert!((cyclic.get_series_ref()[60].iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        let mut snowdrift = Game::snowdrift(0.4).with_noise(0.1).with_self_interaction(false).with_seed(50);
+        let mut strategies = snowdrift.initial_state(48, 48, &[1.0, 1.0]).unwrap();
+        for _ in 0..60 {
+            strategies = snowdrift.step(&strategies).unwrap();
+        }
+        let cooperation = snowdrift.cooperation()[60];
+        assert!(cooperation > 0.1 && cooperation < 0.95, "{cooperation}");
+        assert!(snowdrift.initial_state(8, 8, &[1.0]).is_err());
+    }
+}
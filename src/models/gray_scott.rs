@@ -0,0 +1,233 @@
+//!   Gray-Scott reaction-diffusion model, where a substrate U is fed at rate F and turned into an
+//!   autocatalyst V by the reaction U + 2V -> 3V, V being removed at rate F + k:
+//!
+//!   dU/dt = Du ∇²U - UV² + F(1 - U)
+//!   dV/dt = Dv ∇²V + UV² - (F + k)V
+//!
+//!   Concentrations are held in a `FourChannelMatrix`, U in the red channel and V in the blue one,
+//!   green and alpha being kept at 1 so that the state can be drawn as is.
+
+use image::RgbaImage;
+use crate::{
+    MatrixImageBuilder,
+    Neighborhood,
+    four_channel::FourChannelMatrix,
+    integrators::Field,
+    simulation::Rule,
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+    error::MatrixError,
+};
+use super::laplace_scale;
+
+/// Concentrations of U in the red channel and of V in the blue channel.
+pub type Concentrations = FourChannelMatrix<LatticeElement<f32>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrayScott {
+    feed: f32,
+    kill: f32,
+    diffusion_u: f32,
+    diffusion_v: f32,
+    time_step: f32,
+    hood_type: Neighborhood,
+    hood_size: usize,
+}
+
+impl GrayScott {
+    /// Model with the given feed and kill rates, Pearson's diffusion coefficients on a unit grid,
+    /// a unit time step and the five-point stencil.
+    pub fn new(feed: f32, kill: f32) -> Self {
+        GrayScott {
+            feed,
+            kill,
+            diffusion_u: 0.2097,
+            diffusion_v: 0.105,
+            time_step: 1.0,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+        }
+    }
+    /// Spots that grow and divide.
+    pub fn mitosis() -> Self {
+        Self::new(0.0367, 0.0649)
+    }
+    /// Branching growth that fills the lattice.
+    pub fn coral() -> Self {
+        Self::new(0.0545, 0.062)
+    }
+    /// Stable spots.
+    pub fn spots() -> Self {
+        Self::new(0.035, 0.065)
+    }
+    /// Parallel stripes, as in fingerprints.
+    pub fn stripes() -> Self {
+        Self::new(0.022, 0.051)
+    }
+    /// Worm-like segments.
+    pub fn worms() -> Self {
+        Self::new(0.078, 0.061)
+    }
+    pub fn get_feed(&self) -> f32 {
+        self.feed
+    }
+    pub fn get_kill(&self) -> f32 {
+        self.kill
+    }
+    pub fn get_time_step(&self) -> f32 {
+        self.time_step
+    }
+    pub fn with_diffusion(mut self, diffusion_u: f32, diffusion_v: f32) -> Self {
+        self.diffusion_u = diffusion_u;
+        self.diffusion_v = diffusion_v;
+        self
+    }
+    pub fn with_time_step(mut self, time_step: f32) -> Self {
+        self.time_step = time_step;
+        self
+    }
+    /// Neighborhood over which the Laplacian is computed. The sum of differences returned by
+    /// `laplace_operator` is divided by `Neighborhood::laplace_scale`, so that the diffusion
+    /// coefficients keep their meaning across stencils.
+    pub fn with_stencil(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    /// Lattice at the homogeneous steady state, U = 1 and V = 0 everywhere.
+    pub fn initial_state(width: usize, height: usize) -> Result<Concentrations, MatrixError> {
+        let channel = |value: f32| MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(value))
+            .with_height_and_width(height, width)
+            .build();
+        FourChannelMatrix::try_from([channel(1.0), channel(1.0), channel(0.0), channel(1.0)])
+    }
+    /// Perturbs the square of the given half size around the center, wrapping around the edges,
+    /// to U = 0.5 and V = 0.25.
+    pub fn seed_square(state: &mut Concentrations, center: (u32, u32), size: usize) -> Result<(), MatrixError> {
+        let square = state.get_data_ref()[0].get_lattice_neighborhood(center, size, Neighborhood::Moore);
+        for point in square {
+            state.get_data_mut_ref()[0].edit_point(point, 0.5)?;
+            state.get_data_mut_ref()[2].edit_point(point, 0.25)?;
+        }
+        Ok(())
+    }
+    /// Seeds squares at random centers, with the generator returning numbers in [0, 1). An empty
+    /// lattice is left as it is.
+    pub fn seed_squares(state: &mut Concentrations, count: usize, size: usize, mut generator: impl FnMut() -> f32) -> Result<(), MatrixError> {
+        if *state.get_width() == 0 || *state.get_height() == 0 {
+            return Ok(());
+        }
+        let (width, height) = (*state.get_width() as f32, *state.get_height() as f32);
+        for _ in 0..count {
+            let center = ((generator() * width) as u32, (generator() * height) as u32);
+            let center = (center.0.min(width as u32 - 1), center.1.min(height as u32 - 1));
+            Self::seed_square(state, center, size)?;
+        }
+        Ok(())
+    }
+    /// Adds noise of the given amplitude to V, with the generator returning numbers in [0, 1), and
+    /// clamps V to [0, 1].
+    pub fn seed_noise(state: &mut Concentrations, amplitude: f32, mut generator: impl FnMut() -> f32) -> Result<(), MatrixError> {
+        let v = &mut state.get_data_mut_ref()[2];
+        for point in 0..(v.get_width()*v.get_height()) {
+            let value = v.get_absolute_point_data(point).0 + amplitude * generator();
+            v.edit_point(v.into_2d_point(point)?, value.clamp(0.0, 1.0))?;
+        }
+        Ok(())
+    }
+    /// Rates of change of U and V at every point, the green and alpha channels being held constant.
+    /// Usable as the right-hand side of an `Integrator`.
+    pub fn derivative(&self, state: &Concentrations) -> Result<Concentrations, MatrixError> {
+        let [u, _, v, _] = state.get_data_ref();
+        let scale = laplace_scale(self.hood_type, self.hood_size)?.max(1) as f32;
+        let (mut rate_u, mut rate_v) = (u.clone(), v.clone());
+        for point_y in 0..u.get_height() as u32 {
+            for point_x in 0..u.get_width() as u32 {
+                let center = (point_x, point_y);
+                let u_value: f32 = u.get_point_value(center)?.into();
+                let v_value: f32 = v.get_point_value(center)?.into();
+                let laplace_u = f32::from(u.laplace_operator(center, self.hood_size, self.hood_type)?) / scale;
+                let laplace_v = f32::from(v.laplace_operator(center, self.hood_size, self.hood_type)?) / scale;
+                let reaction = u_value * v_value * v_value;
+                rate_u.edit_point(center, self.diffusion_u * laplace_u - reaction + self.feed * (1.0 - u_value))?;
+                rate_v.edit_point(center, self.diffusion_v * laplace_v + reaction - (self.feed + self.kill) * v_value)?;
+            }
+        }
+        let constant = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(u.get_height(), u.get_width())
+            .build();
+        FourChannelMatrix::try_from([rate_u, constant.clone(), rate_v, constant])
+    }
+    /// Advances the concentrations by one explicit Euler step.
+    pub fn next_state(&self, state: &Concentrations) -> Result<Concentrations, MatrixError> {
+        let mut next = state.clone();
+        next.add_scaled(&self.derivative(state)?, self.time_step as f64)?;
+        Ok(next)
+    }
+    /// Runs the model for the given number of steps from a square seeded at the center of a
+    /// lattice of the given size.
+    pub fn run(&self, width: usize, height: usize, steps: usize) -> Result<Concentrations, MatrixError> {
+        let mut state = Self::initial_state(width, height)?;
+        Self::seed_square(&mut state, (width as u32 / 2, height as u32 / 2), width.min(height) / 10)?;
+        for _ in 0..steps {
+            state = self.next_state(&state)?;
+        }
+        Ok(state)
+    }
+    /// Draws U in red and V in blue, with full green and alpha.
+    pub fn draw(state: &Concentrations) -> Result<RgbaImage, MatrixError> {
+        state.as_normals().multi_channel_image(None)
+    }
+}
+
+impl Rule<Concentrations> for GrayScott {
+    fn step(&mut self, state: &Concentrations) -> Result<Concentrations, MatrixError> {
+        self.next_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        models::gray_scott::GrayScott,
+        traits::Matrix,
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn laplace_scale_of_stencils() {
+        assert_eq!(Neighborhood::VonNeumann.laplace_scale(1), Some(1));
+        assert_eq!(Neighborhood::Moore.laplace_scale(1), Some(3));
+        assert_eq!(Neighborhood::VonNeumann.laplace_scale(2), Some(7));
+        assert_eq!(Neighborhood::Margolus.laplace_scale(1), None);
+    }
+
+    #[test]
+    fn homogeneous_state_is_steady_and_seeds_react() {
+        let model = GrayScott::coral();
+        let state = GrayScott::initial_state(12, 10).unwrap();
+        assert_eq!(model.next_state(&state).unwrap(), state);
+        let mut seeded = state.clone();
+        GrayScott::seed_square(&mut seeded, (6, 5), 2).unwrap();
+        let moore = model.clone().with_stencil(Neighborhood::Moore, 1).with_time_step(0.5);
+        for model in [model, moore] {
+            let mut next = seeded.clone();
+            for _ in 0..20 {
+                next = model.next_state(&next).unwrap();
+            }
+            let v: Vec<f32> = next.get_data_ref()[2].get_data().into_iter().map(f32::from).collect();
+            assert!(v.iter().all(|value| value.is_finite() && (0.0..=1.0).contains(value)));
+            assert!(v[5 * 12 + 6] > 0.0 && v[5 * 12 + 9] > 0.0);
+        }
+        assert!(GrayScott::coral().with_stencil(Neighborhood::Margolus, 1).next_state(&state).is_err());
+        for (width, height) in [(0, 4), (4, 0)] {
+            let mut empty = GrayScott::initial_state(width, height).unwrap();
+            GrayScott::seed_squares(&mut empty, 3, 1, || 0.5).unwrap();
+        }
+    }
+}
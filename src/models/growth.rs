@@ -0,0 +1,393 @@
+//!   Cluster growth from a seed at the center of the lattice. In diffusion-limited aggregation
+//!   random walkers are released one at a time from a circle around the cluster and stick to it
+//!   with the sticking probability when they reach it, growing a fractal cluster. Requiring several
+//!   hits of a site before it is occupied, noise reduction, brings out the anisotropy of the lattice
+//!   as arms along its axes, while weighting the sticking probability by the axis of contact
+//!   stretches the cluster along the favored axis. In Eden growth a uniformly random site of the perimeter of the cluster
+//!   is occupied at every attachment, growing a compact cluster with a rough surface.
+//!
+//!   Clusters are lattices of attachment order, 0 for empty sites, 1 for the seed and k for the
+//!   k-th site occupied, whose box-counting dimension estimates their fractal dimension.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    random::{
+        self,
+        Philox,
+        RngCore,
+    },
+    simulation::Rule,
+    automata::{
+        MOORE_OFFSETS,
+        VON_NEUMANN_OFFSETS,
+    },
+    traits::Matrix,
+    error::MatrixError,
+};
+
+/// Lattice holding only the seed, at the center.
+pub fn seeded(width: usize, height: usize) -> Result<MatrixImage<u32>, MatrixError> {
+    let mut order = MatrixImageBuilder::init()
+        .with_initial_value(0)
+        .with_height_and_width(height, width)
+        .build();
+    order.edit_point(((width / 2) as u32, (height / 2) as u32), 1_u32)?;
+    Ok(order)
+}
+
+/// Number of occupied sites, the order of the last one.
+fn occupied(order: &MatrixImage<u32>) -> u32 {
+    order.get_data_ref().iter().copied().max().unwrap_or(0)
+}
+
+/// Neighbor of a point at an offset, when it lies within the lattice.
+fn neighbor(width: usize, height: usize, point: (i64, i64), offset: (i64, i64)) -> Option<usize> {
+    let (x, y) = (point.0 + offset.0, point.1 + offset.1);
+    ((0..width as i64).contains(&x) && (0..height as i64).contains(&y)).then(|| y as usize * width + x as usize)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dla {
+    sticking: f64,
+    /// Weights of the sticking probability for contacts along the horizontal and vertical axes.
+    anisotropy: (f64, f64),
+    hits: u32,
+    launch_margin: f64,
+    batch: usize,
+    rng: Philox,
+    /// Hits received by every site, under noise reduction.
+    site_hits: Vec<u32>,
+}
+
+impl Dla {
+    /// Aggregation where walkers stick at the first contact with the given probability, released 5
+    /// sites away from the cluster, 100 of them attaching at every step taken as a `Rule`.
+    pub fn new(sticking: f64) -> Self {
+        Dla {
+            sticking,
+            anisotropy: (1.0, 1.0),
+            hits: 1,
+            launch_margin: 5.0,
+            batch: 100,
+            rng: Philox::new(0),
+            site_hits: Vec::new(),
+        }
+    }
+    /// Weights in [0, 1] of the sticking probability when the walker touches the cluster along the
+    /// horizontal and the vertical axis, the larger weight applying when it touches along both.
+    /// Unequal weights stretch the cluster along the favored axis.
+    pub fn with_anisotropy(mut self, horizontal: f64, vertical: f64) -> Self {
+        self.anisotropy = (horizontal, vertical);
+        self
+    }
+    /// Number of hits a site needs before it is occupied, 1 without noise reduction. A few hits
+    /// bring out the axes of the lattice.
+    pub fn with_noise_reduction(mut self, hits: u32) -> Self {
+        self.hits = hits.max(1);
+        self
+    }
+    /// Distance between the farthest site of the cluster and the circle walkers are released from.
+    pub fn with_launch_margin(mut self, launch_margin: f64) -> Self {
+        self.launch_margin = launch_margin;
+        self
+    }
+    /// Number of sites attached at every step taken as a `Rule`.
+    pub fn with_batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Philox::new(seed);
+        self
+    }
+    pub fn get_sticking(&self) -> f64 {
+        self.sticking
+    }
+    pub fn get_anisotropy(&self) -> (f64, f64) {
+        self.anisotropy
+    }
+    /// Releases walkers until the given number of sites are attached, returning the number
+    /// attached, fewer when the release circle no longer fits within the lattice. The sticking
+    /// probability must lie in (0, 1] and the anisotropy weights in [0, 1], not both 0, as no walker
+    /// would ever attach otherwise.
+    pub fn grow(&mut self, order: &mut MatrixImage<u32>, sites: usize) -> Result<usize, MatrixError> {
+        if !(self.sticking > 0.0 && self.sticking <= 1.0) {
+            return Err(MatrixError::InvalidRule(format!("the sticking probability {} should lie in (0, 1]", self.sticking)));
+        }
+        let (horizontal, vertical) = self.anisotropy;
+        if !((0.0..=1.0).contains(&horizontal) && (0.0..=1.0).contains(&vertical) && horizontal.max(vertical) > 0.0) {
+            return Err(MatrixError::InvalidRule(format!("the anisotropy weights {:?} should lie in [0, 1], not both 0", self.anisotropy)));
+        }
+        let (width, height) = (order.get_width(), order.get_height());
+        if self.site_hits.len() != width * height {
+            self.site_hits = vec![0; width * height];
+        }
+        let center = ((width / 2) as f64, (height / 2) as f64);
+        let distance = |x: i64, y: i64| ((x as f64 - center.0).powi(2) + (y as f64 - center.1).powi(2)).sqrt();
+        let mut count = occupied(order);
+        let mut radius = order
+            .get_data_ref()
+            .iter()
+            .enumerate()
+            .filter(|(_, order)| **order > 0)
+            .map(|(index, _)| distance((index % width) as i64, (index / width) as i64))
+            .fold(0.0, f64::max);
+        let limit = (width.min(height) / 2) as f64 - 2.0;
+        let mut attached = 0;
+        while attached < sites {
+            let launch = radius + self.launch_margin;
+            if launch > limit {
+                break;
+            }
+            let angle = random::unit_f64(&mut self.rng) * core::f64::consts::TAU;
+            let (mut x, mut y) = ((center.0 + launch * angle.cos()).round() as i64, (center.1 + launch * angle.sin()).round() as i64);
+            loop {
+                let (step_x, step_y) = VON_NEUMANN_OFFSETS[(self.rng.next_u32() % 4) as usize];
+                let next = neighbor(width, height, (x, y), (step_x, step_y));
+                match next {
+                    Some(index) if order.get_data_ref()[index] == 0 => (x, y) = (x + step_x, y + step_y),
+                    Some(_) => {},
+                    None => break,
+                }
+                if distance(x, y) > (2.0 * launch).min(limit + 1.0) {
+                    break;
+                }
+                let weight = VON_NEUMANN_OFFSETS
+                    .iter()
+                    .filter(|offset| neighbor(width, height, (x, y), **offset).is_some_and(|index| order.get_data_ref()[index] > 0))
+                    .map(|offset| if offset.0 == 0 { vertical } else { horizontal })
+                    .fold(0.0, f64::max);
+                if weight == 0.0 || random::unit_f64(&mut self.rng) >= self.sticking * weight {
+                    continue;
+                }
+                let index = y as usize * width + x as usize;
+                self.site_hits[index] += 1;
+                if self.site_hits[index] >= self.hits {
+                    count += 1;
+                    order.get_data_mut_ref()[index] = count;
+                    radius = radius.max(distance(x, y));
+                    attached += 1;
+                }
+                break;
+            }
+        }
+        Ok(attached)
+    }
+}
+
+impl Rule<MatrixImage<u32>> for Dla {
+    fn step(&mut self, state: &MatrixImage<u32>) -> Result<MatrixImage<u32>, MatrixError> {
+        let mut next = state.clone();
+        self.grow(&mut next, self.batch)?;
+        Ok(next)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Eden {
+    hood_type: Neighborhood,
+    batch: usize,
+    rng: Philox,
+}
+
+impl Eden {
+    /// Growth over the von Neumann perimeter, 100 sites attaching at every step taken as a `Rule`.
+    pub fn new() -> Self {
+        Eden {
+            hood_type: Neighborhood::VonNeumann,
+            batch: 100,
+            rng: Philox::new(0),
+        }
+    }
+    /// Neighborhood defining the perimeter of the cluster, von Neumann or Moore.
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood) -> Self {
+        self.hood_type = hood_type;
+        self
+    }
+    pub fn with_batch(mut self, batch: usize) -> Self {
+        self.batch = batch.max(1);
+        self
+    }
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Philox::new(seed);
+        self
+    }
+    /// Occupies uniformly random perimeter sites until the given number are attached, returning the
+    /// number attached, fewer when the cluster fills the lattice.
+    pub fn grow(&mut self, order: &mut MatrixImage<u32>, sites: usize) -> Result<usize, MatrixError> {
+        let offsets: &[(i64, i64)] = match self.hood_type.per_cell()? {
+            Neighborhood::Moore => &MOORE_OFFSETS,
+            _ => &VON_NEUMANN_OFFSETS,
+        };
+        let (width, height) = (order.get_width(), order.get_height());
+        let neighbors = |index: usize| offsets.iter().filter_map(move |offset| neighbor(width, height, ((index % width) as i64, (index / width) as i64), *offset));
+        let mut in_perimeter = vec![false; width * height];
+        let mut perimeter = Vec::new();
+        for index in (0..width * height).filter(|index| order.get_data_ref()[*index] > 0) {
+            for candidate in neighbors(index) {
+                if order.get_data_ref()[candidate] == 0 && !in_perimeter[candidate] {
+                    in_perimeter[candidate] = true;
+                    perimeter.push(candidate);
+                }
+            }
+        }
+        perimeter.sort_unstable();
+        let mut count = occupied(order);
+        let mut attached = 0;
+        while attached < sites && !perimeter.is_empty() {
+            let position = ((random::unit_f64(&mut self.rng) * perimeter.len() as f64) as usize).min(perimeter.len() - 1);
+            let index = perimeter.swap_remove(position);
+            count += 1;
+            order.get_data_mut_ref()[index] = count;
+            attached += 1;
+            for candidate in neighbors(index) {
+                if order.get_data_ref()[candidate] == 0 && !in_perimeter[candidate] {
+                    in_perimeter[candidate] = true;
+                    perimeter.push(candidate);
+                }
+            }
+        }
+        Ok(attached)
+    }
+}
+
+impl Default for Eden {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rule<MatrixImage<u32>> for Eden {
+    fn step(&mut self, state: &MatrixImage<u32>) -> Result<MatrixImage<u32>, MatrixError> {
+        let mut next = state.clone();
+        self.grow(&mut next, self.batch)?;
+        Ok(next)
+    }
+}
+
+/// Draws the occupied sites by spreading their attachment order over the colors of the palette,
+/// empty sites being black.
+pub fn draw(order: &MatrixImage<u32>, palette: &Palette) -> Result<RgbaImage, MatrixError> {
+    let last = occupied(order).max(1) as f64;
+    let colors = palette.get_colors_ref().len().clamp(1, u8::MAX as usize + 1);
+    let mut image = RgbaImage::new(order.get_width().try_into()?, order.get_height().try_into()?);
+    for (pixel, order) in image.pixels_mut().zip(order.get_data_ref()) {
+        *pixel = match order {
+            0 => Rgba([0, 0, 0, 255]),
+            order => palette.get_color(((*order - 1) as f64 / last * (colors - 1) as f64).round() as u8),
+        };
+    }
+    Ok(image)
+}
+
+/// Number of boxes holding a site satisfying the predicate, for box sides doubling from 1 while
+/// they fit within the lattice, as (side, boxes) pairs.
+pub fn box_counts<T: Clone>(matrix: &MatrixImage<T>, predicate: impl Fn(&T) -> bool) -> Vec<(usize, usize)> {
+    let (width, height) = (matrix.get_width(), matrix.get_height());
+    let mut counts = Vec::new();
+    let mut side = 1;
+    while side <= width.min(height) {
+        let columns = width.div_ceil(side);
+        let mut boxes = vec![false; columns * height.div_ceil(side)];
+        for (index, value) in matrix.get_data_ref().iter().enumerate() {
+            if predicate(value) {
+                boxes[(index / width / side) * columns + index % width / side] = true;
+            }
+        }
+        counts.push((side, boxes.iter().filter(|occupied| **occupied).count()));
+        side *= 2;
+    }
+    counts
+}
+
+/// Box-counting dimension, the least-squares slope of the logarithm of the number of boxes against
+/// the logarithm of the inverse of their side, over the given (side, boxes) pairs. None unless
+/// boxes are occupied at two different sides at least, as no slope can be fitted otherwise.
+pub fn fractal_dimension(counts: &[(usize, usize)]) -> Option<f64> {
+    let points: Vec<(f64, f64)> = counts
+        .iter()
+        .filter(|(_, boxes)| *boxes > 0)
+        .map(|(side, boxes)| (-(*side as f64).ln(), (*boxes as f64).ln()))
+        .collect();
+    let length = points.len() as f64;
+    let (mean_x, mean_y) = points.iter().fold((0.0, 0.0), |(x, y), point| (x + point.0 / length, y + point.1 / length));
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x) * (x - mean_x)).sum();
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        Neighborhood,
+        models::growth::{
+            self,
+            Dla,
+            Eden,
+        },
+        simulation::Rule,
+    };
+
+    #[test]
+    fn box_counting_measures_the_dimension_of_simple_sets() {
+        let filled: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(1).with_height_and_width(64, 64).build();
+        let counts = growth::box_counts(&filled, |value| *value > 0);
+        assert_eq!(counts.first(), Some(&(1, 4096)));
+        assert_eq!(counts.last(), Some(&(64, 1)));
+        assert!((growth::fractal_dimension(&counts).unwrap() - 2.0).abs() < 1e-9);
+        let mut line: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(64, 64).build();
+        line.get_data_mut_ref()[..64].iter_mut().for_each(|value| *value = 1);
+        assert!((growth::fractal_dimension(&growth::box_counts(&line, |value| *value > 0)).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(growth::fractal_dimension(&counts[..1]), None);
+        assert_eq!(growth::fractal_dimension(&[(1, 0), (2, 0)]), None);
+        let mut eden = Eden::new().with_seed(48);
+        let mut order = growth::seeded(21, 21).unwrap();
+        assert_eq!(eden.grow(&mut order, 1000).unwrap(), 440);
+        let mut orders = order.get_data_ref().clone();
+        orders.sort_unstable();
+        assert!(orders.into_iter().eq(1..=441));
+        assert!(Eden::new().with_neighborhood(Neighborhood::Margolus).grow(&mut order, 1).is_err());
+    }
+
+    #[test]
+    fn aggregates_are_fractal_and_eden_clusters_compact() {
+        let mut dla = Dla::new(1.0).with_seed(48).with_batch(400);
+        let mut order = growth::seeded(161, 161).unwrap();
+        for _ in 0..3 {
+            order = dla.step(&order).unwrap();
+        }
+        assert_eq!(order.get_data_ref().iter().filter(|order| **order > 0).count(), 1201);
+        let counts = growth::box_counts(&order, |order| *order > 0);
+        let dimension = growth::fractal_dimension(&counts[1..6]).unwrap();
+        assert!((1.3..1.8).contains(&dimension), "{dimension}");
+        assert!(Dla::new(0.0).grow(&mut order, 1).is_err());
+        assert!(Dla::new(f64::NAN).grow(&mut order, 1).is_err());
+        assert!(Dla::new(1.0).with_anisotropy(0.0, 0.0).grow(&mut order, 1).is_err());
+        let mut stretched = Dla::new(1.0).with_anisotropy(1.0, 0.05).with_seed(48);
+        let mut order = growth::seeded(161, 161).unwrap();
+        assert_eq!(stretched.grow(&mut order, 400).unwrap(), 400);
+        let extent = |axis: fn(usize) -> usize| {
+            let sites = order.get_data_ref().iter().enumerate().filter(|(_, order)| **order > 0).map(|(index, _)| axis(index));
+            let (low, high) = sites.fold((usize::MAX, 0), |(low, high), site| (low.min(site), high.max(site)));
+            high - low
+        };
+        let (horizontal, vertical) = (extent(|index| index % 161), extent(|index| index / 161));
+        assert!(horizontal > 2 * vertical, "{horizontal} {vertical}");
+        let mut eden = Eden::new().with_seed(48).with_batch(2000);
+        let mut compact = growth::seeded(161, 161).unwrap();
+        compact = eden.step(&compact).unwrap();
+        let counts = growth::box_counts(&compact, |order| *order > 0);
+        let eden_dimension = growth::fractal_dimension(&counts[1..5]).unwrap();
+        assert!(eden_dimension > dimension + 0.15, "{eden_dimension} {dimension}");
+    }
+}
@@ -0,0 +1,255 @@
+//!   Heat equation, du/dt = D ∇²u, on a periodic lattice with grid spacing dx. The explicit scheme
+//!   is stable while the stability number r = D·dt/dx² stays below its limit, 1/4 for the
+//!   five-point stencil; the implicit schemes, backward Euler and Crank-Nicolson, are stable for
+//!   any time step and solve their linear system by Gauss-Seidel iterations.
+
+use core::{
+    fmt::Debug,
+    ops::{
+        Add,
+        Div,
+        Mul,
+        Sub,
+    },
+};
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    Neighborhood,
+    integrators::Scalar,
+    simulation::Rule,
+    traits::{
+        Matrix,
+        Max,
+    },
+    error::MatrixError,
+};
+use super::{
+    laplace_scale,
+    stencil_neighbors,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    /// Forward Euler, first order in time and conditionally stable.
+    Explicit,
+    /// Backward Euler, first order in time and unconditionally stable.
+    BackwardEuler,
+    /// Crank-Nicolson, second order in time and unconditionally stable.
+    CrankNicolson,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heat {
+    diffusion: f64,
+    time_step: f64,
+    grid_spacing: f64,
+    scheme: Scheme,
+    allow_unstable: bool,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    tolerance: f64,
+    max_iterations: usize,
+}
+
+impl Heat {
+    /// Explicit solver with the given diffusion coefficient, time step and grid spacing, over the
+    /// five-point stencil.
+    pub fn new(diffusion: f64, time_step: f64, grid_spacing: f64) -> Self {
+        Heat {
+            diffusion,
+            time_step,
+            grid_spacing,
+            scheme: Scheme::Explicit,
+            allow_unstable: false,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            tolerance: 1e-6,
+            max_iterations: 1000,
+        }
+    }
+    pub fn with_scheme(mut self, scheme: Scheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+    /// Neighborhood over which the Laplacian is computed, scaled by `Neighborhood::laplace_scale`.
+    pub fn with_stencil(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    /// Lets the explicit scheme step beyond its stability limit instead of failing with
+    /// `MatrixError::Unstable`; check `is_stable` to warn about it.
+    pub fn allow_unstable(mut self) -> Self {
+        self.allow_unstable = true;
+        self
+    }
+    /// Largest change of a point between two Gauss-Seidel sweeps at which implicit solves stop,
+    /// and the number of sweeps after which they fail with `MatrixError::NotConverged`.
+    pub fn with_tolerance(mut self, tolerance: f64, max_iterations: usize) -> Self {
+        self.tolerance = tolerance;
+        self.max_iterations = max_iterations;
+        self
+    }
+    pub fn get_scheme(&self) -> Scheme {
+        self.scheme
+    }
+    pub fn get_time_step(&self) -> f64 {
+        self.time_step
+    }
+    /// D·dt/dx².
+    pub fn stability_number(&self) -> f64 {
+        self.diffusion * self.time_step / (self.grid_spacing * self.grid_spacing)
+    }
+    /// Largest stability number of the explicit scheme, the Laplace scale of the stencil over its
+    /// number of neighbors.
+    pub fn stability_limit(&self) -> f64 {
+        let neighbors = self.hood_type.length(self.hood_size).max(2) - 1;
+        self.hood_type.laplace_scale(self.hood_size).unwrap_or(0) as f64 / neighbors as f64
+    }
+    /// Largest time step of the explicit scheme.
+    pub fn max_stable_time_step(&self) -> f64 {
+        self.stability_limit() * self.grid_spacing * self.grid_spacing / self.diffusion
+    }
+    /// Whether the scheme is stable with the current time step, always true for implicit schemes.
+    pub fn is_stable(&self) -> bool {
+        self.scheme != Scheme::Explicit || self.stability_number() <= self.stability_limit()
+    }
+    /// Advances the temperatures by one time step.
+    pub fn next_state<T>(&self, state: &MatrixImage<T>) -> Result<MatrixImage<T>, MatrixError>
+    where T: Scalar + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+    {
+        let scale = laplace_scale(self.hood_type, self.hood_size)?.max(1) as f64;
+        let number = self.stability_number() / scale;
+        match self.scheme {
+            Scheme::Explicit => {
+                if !self.is_stable() && !self.allow_unstable {
+                    return Err(MatrixError::Unstable { number: self.stability_number(), limit: self.stability_limit() });
+                }
+                let mut next = state.clone();
+                for point_y in 0..state.get_height() as u32 {
+                    for point_x in 0..state.get_width() as u32 {
+                        let center = (point_x, point_y);
+                        let laplace = state.laplace_operator(center, self.hood_size, self.hood_type)?.to_f64();
+                        next.edit_point(center, T::from_f64(state.get_point_value(center)?.to_f64() + number * laplace))?;
+                    }
+                }
+                Ok(next)
+            },
+            Scheme::BackwardEuler => self.implicit_step(state, number, 1.0),
+            Scheme::CrankNicolson => self.implicit_step(state, number, 0.5),
+        }
+    }
+    /// Solves (1 - θrL) u' = (1 + (1 - θ)rL) u, with L the sum of differences to the neighbors.
+    fn implicit_step<T>(&self, state: &MatrixImage<T>, number: f64, theta: f64) -> Result<MatrixImage<T>, MatrixError>
+    where T: Scalar + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+    {
+        let neighbors = stencil_neighbors(state, self.hood_size, self.hood_type)?;
+        let values: Vec<f64> = state.get_data_ref().iter().map(Scalar::to_f64).collect();
+        let right_hand_side: Vec<f64> = values
+            .iter()
+            .zip(&neighbors)
+            .map(|(value, hood)| {
+                let laplace: f64 = hood.iter().map(|neighbor| values[*neighbor] - value).sum();
+                value + (1.0 - theta) * number * laplace
+            })
+            .collect();
+        let mut solution = values.clone();
+        let mut iterations = 0;
+        loop {
+            let mut change: f64 = 0.0;
+            for (index, hood) in neighbors.iter().enumerate() {
+                let sum: f64 = hood.iter().map(|neighbor| solution[*neighbor]).sum();
+                let value = (right_hand_side[index] + theta * number * sum) / (1.0 + theta * number * hood.len() as f64);
+                change = change.max((value - solution[index]).abs());
+                solution[index] = value;
+            }
+            iterations += 1;
+            if change <= self.tolerance {
+                break;
+            }
+            if iterations >= self.max_iterations || change.is_nan() {
+                return Err(MatrixError::NotConverged { iterations, residual: change });
+            }
+        }
+        let mut next = state.clone();
+        for (value, solved) in next.get_data_mut_ref().iter_mut().zip(solution) {
+            *value = T::from_f64(solved);
+        }
+        Ok(next)
+    }
+}
+
+impl<T> Rule<MatrixImage<T>> for Heat
+where T: Scalar + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+{
+    fn step(&mut self, state: &MatrixImage<T>) -> Result<MatrixImage<T>, MatrixError> {
+        self.next_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        models::heat::{
+            Heat,
+            Scheme,
+        },
+        traits::{
+            Matrix,
+            LatticeElement,
+        },
+        error::MatrixError,
+    };
+
+    fn hot_spot() -> MatrixImage<LatticeElement<f64>> {
+        let mut matrix: MatrixImage<LatticeElement<f64>> = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(9, 11)
+            .build();
+        matrix.edit_point((5_u32, 4_u32), 100.0).unwrap();
+        matrix
+    }
+
+    fn total(matrix: &MatrixImage<LatticeElement<f64>>) -> f64 {
+        matrix.get_data_ref().iter().map(|value| value.0).sum()
+    }
+
+    #[test]
+    fn explicit_scheme_refuses_unstable_time_steps() {
+        let heat = Heat::new(1.0, 0.3, 1.0);
+        assert!(!heat.is_stable());
+        assert!((heat.max_stable_time_step() - 0.25).abs() < 1e-12);
+        assert!(matches!(heat.next_state(&hot_spot()), Err(MatrixError::Unstable { .. })));
+        assert!(heat.clone().allow_unstable().next_state(&hot_spot()).is_ok());
+        assert!(heat.with_scheme(Scheme::CrankNicolson).is_stable());
+    }
+
+    #[test]
+    fn schemes_conserve_heat_and_respect_the_maximum_principle() {
+        for (scheme, time_step) in [(Scheme::Explicit, 0.2), (Scheme::BackwardEuler, 5.0), (Scheme::CrankNicolson, 0.5)] {
+            let heat = Heat::new(1.0, time_step, 1.0).with_scheme(scheme).with_tolerance(1e-10, 10000);
+            let mut state = hot_spot();
+            for _ in 0..10 {
+                state = heat.next_state(&state).unwrap();
+            }
+            assert!((total(&state) - 100.0).abs() < 1e-6, "{scheme:?}");
+            assert!(state.get_data_ref().iter().all(|value| (-1e-9..=100.0).contains(&value.0)), "{scheme:?}");
+        }
+    }
+
+    #[test]
+    fn crank_nicolson_matches_explicit_scheme_for_small_time_steps() {
+        let explicit = Heat::new(1.0, 0.01, 1.0);
+        let crank_nicolson = explicit.clone().with_scheme(Scheme::CrankNicolson).with_tolerance(1e-12, 1000);
+        let (mut expected, mut actual) = (hot_spot(), hot_spot());
+        for _ in 0..50 {
+            expected = explicit.next_state(&expected).unwrap();
+            actual = crank_nicolson.next_state(&actual).unwrap();
+        }
+        let difference = expected.get_data_ref().iter().zip(actual.get_data_ref()).map(|(a, b)| (a.0 - b.0).abs()).fold(0.0, f64::max);
+        assert!(difference < 1.0, "{difference}");
+    }
+}
@@ -0,0 +1,320 @@
+//!   Lattice Boltzmann D2Q9 fluid, where the nine channels of an `NChannelMatrix` hold the densities
+//!   of particles moving with each of the velocities of `VELOCITIES` at every point. A step relaxes
+//!   the distributions towards their local equilibrium with the BGK collision, then streams them to
+//!   the neighbor they move to, wrapping around the edges. Distributions streamed into an obstacle
+//!   bounce back to their origin with the opposite velocity, the wall lying halfway between.
+//!
+//!   In lattice units the speed of sound is 1/√3 and the kinematic viscosity (τ - 1/2) / 3, with τ
+//!   the relaxation time; velocities should stay well below the speed of sound.
+
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    n_channel::NChannelMatrix,
+    palette::Palette,
+    simulation::Rule,
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+    error::MatrixError,
+};
+
+type Atom = LatticeElement<f32>;
+
+/// Particle distributions of the nine velocities, in the order of `VELOCITIES`.
+pub type Distributions = NChannelMatrix<Atom, 9>;
+
+/// Velocities of the D2Q9 lattice, rest first, then the four axes and the four diagonals.
+pub const VELOCITIES: [(i64, i64); 9] = [(0, 0), (1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, 1), (-1, -1), (1, -1)];
+/// Weights of the velocities in the equilibrium distribution.
+pub const WEIGHTS: [f32; 9] = [4.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0];
+/// Index of the opposite of every velocity.
+pub const OPPOSITE: [usize; 9] = [0, 3, 4, 1, 2, 7, 8, 5, 6];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatticeBoltzmann {
+    relaxation_time: f32,
+    force: (f32, f32),
+    inflow: Option<(f32, f32)>,
+    obstacles: Option<MatrixImage<u8>>,
+}
+
+impl LatticeBoltzmann {
+    /// Fluid of the given kinematic viscosity in lattice units, without obstacles or forcing.
+    pub fn new(viscosity: f32) -> Self {
+        LatticeBoltzmann {
+            relaxation_time: 3.0 * viscosity + 0.5,
+            force: (0.0, 0.0),
+            inflow: None,
+            obstacles: None,
+        }
+    }
+    /// Uniform body force per unit volume, e.g. a pressure gradient driving a periodic channel,
+    /// applied by shifting the equilibrium velocity by τF/ρ.
+    pub fn with_force(mut self, force: (f32, f32)) -> Self {
+        self.force = force;
+        self
+    }
+    /// Holds the first column at the equilibrium of unit density and the given velocity, and lets
+    /// the fluid leave through the last column, which copies its neighbor, instead of wrapping
+    /// around horizontally.
+    pub fn with_inflow(mut self, velocity: (f32, f32)) -> Self {
+        self.inflow = Some(velocity);
+        self
+    }
+    /// Points where the mask is not 0 are solid walls.
+    pub fn with_obstacles(mut self, obstacles: MatrixImage<u8>) -> Self {
+        self.obstacles = Some(obstacles);
+        self
+    }
+    pub fn get_relaxation_time(&self) -> f32 {
+        self.relaxation_time
+    }
+    pub fn get_viscosity(&self) -> f32 {
+        (self.relaxation_time - 0.5) / 3.0
+    }
+    pub fn get_obstacles_ref(&self) -> Option<&MatrixImage<u8>> {
+        self.obstacles.as_ref()
+    }
+    /// Equilibrium distributions of the given density and velocity, to second order in the velocity.
+    pub fn equilibrium(density: f32, (velocity_x, velocity_y): (f32, f32)) -> [f32; 9] {
+        let square = velocity_x * velocity_x + velocity_y * velocity_y;
+        core::array::from_fn(|index| {
+            let (x, y) = VELOCITIES[index];
+            let projection = x as f32 * velocity_x + y as f32 * velocity_y;
+            WEIGHTS[index] * density * (1.0 + 3.0 * projection + 4.5 * projection * projection - 1.5 * square)
+        })
+    }
+    /// Density and velocity of a point given its distributions.
+    pub fn moments(distributions: &[f32; 9]) -> (f32, (f32, f32)) {
+        let density: f32 = distributions.iter().sum();
+        if density == 0.0 {
+            return (0.0, (0.0, 0.0));
+        }
+        let (mut momentum_x, mut momentum_y) = (0.0, 0.0);
+        for (value, (x, y)) in distributions.iter().zip(VELOCITIES) {
+            momentum_x += value * x as f32;
+            momentum_y += value * y as f32;
+        }
+        (density, (momentum_x / density, momentum_y / density))
+    }
+    /// Lattice at the equilibrium of the density and velocity given for every point.
+    pub fn state_from(width: usize, height: usize, fields: impl Fn((u32, u32)) -> (f32, (f32, f32))) -> Result<Distributions, MatrixError> {
+        let empty = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(height, width)
+            .build();
+        let mut state = NChannelMatrix::try_from(core::array::from_fn(|_| empty.clone()))?;
+        for point in 0..(width*height) {
+            let (density, velocity) = fields(empty.into_2d_point(point)?);
+            for (channel, value) in state.get_data_mut_ref().iter_mut().zip(Self::equilibrium(density, velocity)) {
+                channel.get_data_mut_ref()[point] = LatticeElement(value);
+            }
+        }
+        Ok(state)
+    }
+    /// Lattice at the equilibrium of a uniform density and velocity.
+    pub fn initial_state(width: usize, height: usize, density: f32, velocity: (f32, f32)) -> Result<Distributions, MatrixError> {
+        Self::state_from(width, height, |_| (density, velocity))
+    }
+    fn is_obstacle(&self, point: usize) -> bool {
+        self.obstacles.as_ref().is_some_and(|obstacles| obstacles.get_absolute_point_data(point) != 0)
+    }
+    fn distributions(state: &Distributions, point: usize) -> [f32; 9] {
+        core::array::from_fn(|index| state.get_data_ref()[index].get_data_ref()[point].0)
+    }
+    /// Collides and streams the distributions once, failing with `MatrixError::Unstable` when the
+    /// relaxation time is not above 1/2.
+    pub fn next_state(&self, state: &Distributions) -> Result<Distributions, MatrixError> {
+        if self.relaxation_time <= 0.5 {
+            return Err(MatrixError::Unstable { number: self.relaxation_time as f64, limit: 0.5 });
+        }
+        let (width, height) = (*state.get_width(), *state.get_height());
+        if let Some(obstacles) = &self.obstacles {
+            let found = (obstacles.get_width(), obstacles.get_height());
+            if found != (width, height) {
+                return Err(MatrixError::DimensionMismatch { expected: (width, height), found });
+            }
+        }
+        let lattice = &state.get_data_ref()[0];
+        let mut next = state.clone();
+        for point in 0..(width*height) {
+            if self.is_obstacle(point) {
+                continue;
+            }
+            let mut distributions = Self::distributions(state, point);
+            let (density, (velocity_x, velocity_y)) = Self::moments(&distributions);
+            let shift = if density > 0.0 { self.relaxation_time / density } else { 0.0 };
+            let equilibrium = Self::equilibrium(density, (velocity_x + shift * self.force.0, velocity_y + shift * self.force.1));
+            for (value, equilibrium) in distributions.iter_mut().zip(equilibrium) {
+                *value += (equilibrium - *value) / self.relaxation_time;
+            }
+            let origin = lattice.into_2d_point(point)?;
+            for (index, value) in distributions.into_iter().enumerate() {
+                let target = lattice.into_absolute_point(lattice.offset_point(origin, VELOCITIES[index]))?;
+                let (channel, target) = if self.is_obstacle(target) { (OPPOSITE[index], point) } else { (index, target) };
+                next.get_data_mut_ref()[channel].get_data_mut_ref()[target] = LatticeElement(value);
+            }
+        }
+        if let Some(velocity) = self.inflow {
+            let inflow = Self::equilibrium(1.0, velocity);
+            for y in 0..height {
+                let (first, last) = (y * width, y * width + width - 1);
+                for (index, channel) in next.get_data_mut_ref().iter_mut().enumerate() {
+                    let data = channel.get_data_mut_ref();
+                    data[first] = LatticeElement(inflow[index]);
+                    if width > 1 {
+                        data[last] = data[last - 1].clone();
+                    }
+                }
+            }
+        }
+        Ok(next)
+    }
+    /// Density at every point, 0 within obstacles.
+    pub fn density(&self, state: &Distributions) -> Result<MatrixImage<Atom>, MatrixError> {
+        let mut density = state.get_data_ref()[0].clone();
+        for (point, value) in density.get_data_mut_ref().iter_mut().enumerate() {
+            *value = LatticeElement(if self.is_obstacle(point) { 0.0 } else { Self::moments(&Self::distributions(state, point)).0 });
+        }
+        Ok(density)
+    }
+    /// Horizontal and vertical components of the velocity at every point, averaged over the time
+    /// step when a force is applied, 0 within obstacles.
+    pub fn velocity(&self, state: &Distributions) -> Result<[MatrixImage<Atom>; 2], MatrixError> {
+        let (mut velocity_x, mut velocity_y) = (state.get_data_ref()[0].clone(), state.get_data_ref()[0].clone());
+        for point in 0..(*state.get_width() * *state.get_height()) {
+            let (x, y) = if self.is_obstacle(point) {
+                (0.0, 0.0)
+            } else {
+                let (density, (x, y)) = Self::moments(&Self::distributions(state, point));
+                let shift = if density > 0.0 { 0.5 / density } else { 0.0 };
+                (x + shift * self.force.0, y + shift * self.force.1)
+            };
+            velocity_x.get_data_mut_ref()[point] = LatticeElement(x);
+            velocity_y.get_data_mut_ref()[point] = LatticeElement(y);
+        }
+        Ok([velocity_x, velocity_y])
+    }
+    /// Magnitude of the velocity at every point.
+    pub fn speed(&self, state: &Distributions) -> Result<MatrixImage<Atom>, MatrixError> {
+        let [mut speed, velocity_y] = self.velocity(state)?;
+        for (value, y) in speed.get_data_mut_ref().iter_mut().zip(velocity_y.get_data_ref()) {
+            *value = LatticeElement((value.0 * value.0 + y.0 * y.0).sqrt());
+        }
+        Ok(speed)
+    }
+    /// Curl of the velocity, ∂v/∂x - ∂u/∂y, by central differences wrapping around the edges,
+    /// positive where the fluid turns from the x axis towards the y axis.
+    pub fn vorticity(&self, state: &Distributions) -> Result<MatrixImage<Atom>, MatrixError> {
+        let [velocity_x, velocity_y] = self.velocity(state)?;
+        let mut vorticity = velocity_x.clone();
+        for point in 0..(velocity_x.get_width()*velocity_x.get_height()) {
+            let center = velocity_x.into_2d_point(point)?;
+            let at = |matrix: &MatrixImage<Atom>, offset: (i64, i64)| matrix.get_point_value(matrix.offset_point(center, offset)).map(|value| value.0);
+            let dv_dx = (at(&velocity_y, (1, 0))? - at(&velocity_y, (-1, 0))?) / 2.0;
+            let du_dy = (at(&velocity_x, (0, 1))? - at(&velocity_x, (0, -1))?) / 2.0;
+            vorticity.edit_point(center, dv_dx - du_dy)?;
+        }
+        Ok(vorticity)
+    }
+    /// Draws the vorticity with the diverging palette from -amplitude to amplitude, obstacles in
+    /// black.
+    pub fn draw_vorticity(&self, state: &Distributions, palette: &Palette, amplitude: f32) -> Result<RgbaImage, MatrixError> {
+        let mut image = palette.draw_range(&self.vorticity(state)?, -amplitude, amplitude)?;
+        if let Some(obstacles) = &self.obstacles {
+            for (point, pixel) in image.pixels_mut().enumerate() {
+                if obstacles.get_absolute_point_data(point) != 0 {
+                    *pixel = Rgba([0, 0, 0, 255]);
+                }
+            }
+        }
+        Ok(image)
+    }
+}
+
+impl Rule<Distributions> for LatticeBoltzmann {
+    fn step(&mut self, state: &Distributions) -> Result<Distributions, MatrixError> {
+        self.next_state(state)
+    }
+}
+
+/// Mask of the points within the given radius of a center, e.g. the cylinder of a flow past a
+/// cylinder.
+pub fn disc(width: usize, height: usize, center: (f32, f32), radius: f32) -> Result<MatrixImage<u8>, MatrixError> {
+    let mut mask: MatrixImage<u8> = MatrixImageBuilder::init()
+        .with_initial_value(0)
+        .with_height_and_width(height, width)
+        .build();
+    let points: Vec<(u32, u32)> = (0..width * height)
+        .map(|point| mask.into_2d_point(point))
+        .collect::<Result<_, _>>()?;
+    for (x, y) in points {
+        let (dx, dy) = (x as f32 - center.0, y as f32 - center.1);
+        if dx * dx + dy * dy <= radius * radius {
+            mask.edit_point((x, y), 1)?;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        models::lattice_boltzmann::{
+            self,
+            LatticeBoltzmann,
+        },
+        traits::Matrix,
+    };
+
+    #[test]
+    fn equilibrium_has_the_requested_moments_and_mass_is_conserved() {
+        let (density, velocity) = LatticeBoltzmann::moments(&LatticeBoltzmann::equilibrium(1.2, (0.05, -0.02)));
+        assert!((density - 1.2).abs() < 1e-6 && (velocity.0 - 0.05).abs() < 1e-6 && (velocity.1 + 0.02).abs() < 1e-6);
+        let fluid = LatticeBoltzmann::new(0.1).with_obstacles(lattice_boltzmann::disc(20, 12, (8.0, 6.0), 2.5).unwrap());
+        let mut state = LatticeBoltzmann::initial_state(20, 12, 1.0, (0.05, 0.0)).unwrap();
+        let mass = |state: &_| fluid.density(state).unwrap().get_data_ref().iter().map(|value| value.0).sum::<f32>();
+        let initial = mass(&state);
+        for _ in 0..50 {
+            state = fluid.next_state(&state).unwrap();
+        }
+        assert!((mass(&state) - initial).abs() < 1e-2, "{} {}", mass(&state), initial);
+        let [velocity_x, _] = fluid.velocity(&state).unwrap();
+        assert_eq!(velocity_x.get_point_value((8_u32, 6_u32)).unwrap().0, 0.0);
+        assert!(velocity_x.get_point_value((8_u32, 0_u32)).unwrap().0 > 0.0);
+    }
+
+    #[test]
+    fn forced_channel_develops_poiseuille_flow() {
+        let (width, height, force, viscosity) = (3, 12, 1e-5, 1.0 / 6.0);
+        let mut walls: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(height, width).build();
+        for x in 0..width as u32 {
+            walls.edit_point((x, 0), 1).unwrap();
+            walls.edit_point((x, height as u32 - 1), 1).unwrap();
+        }
+        let fluid = LatticeBoltzmann::new(viscosity).with_force((force, 0.0)).with_obstacles(walls);
+        let mut state = LatticeBoltzmann::initial_state(width, height, 1.0, (0.0, 0.0)).unwrap();
+        for _ in 0..4000 {
+            state = fluid.next_state(&state).unwrap();
+        }
+        let [velocity_x, _] = fluid.velocity(&state).unwrap();
+        let fluid_rows = (height - 2) as f32;
+        for y in 1..height as u32 - 1 {
+            let distance = y as f32 - 0.5;
+            let expected = force / (2.0 * viscosity) * distance * (fluid_rows - distance);
+            let actual = velocity_x.get_point_value((1, y)).unwrap().0;
+            assert!((actual - expected).abs() < 0.05 * expected.max(1e-9) + 1e-6, "at {y}: {actual} {expected}");
+        }
+        let vorticity = fluid.vorticity(&state).unwrap();
+        assert!(vorticity.get_point_value((1_u32, 2_u32)).unwrap().0 < 0.0);
+    }
+}
@@ -0,0 +1,82 @@
+//!   Physical models over lattices, continuous fields of `LatticeElement` values, spin lattices,
+//!   growing clusters and spatial games, stepped in time as a `Rule` of `Simulation`.
+
+pub mod gray_scott;
+pub use gray_scott::GrayScott;
+
+pub mod reaction_diffusion;
+pub use reaction_diffusion::ReactionDiffusion;
+
+pub mod heat;
+pub use heat::Heat;
+
+pub mod poisson;
+pub use poisson::Poisson;
+
+pub mod wave;
+pub use wave::Wave;
+
+pub mod lattice_boltzmann;
+pub use lattice_boltzmann::LatticeBoltzmann;
+
+pub mod spin;
+pub use spin::SpinModel;
+
+pub mod growth;
+pub use growth::{
+    Dla,
+    Eden,
+};
+
+pub mod games;
+pub use games::Game;
+
+use core::{
+    fmt::Debug,
+    ops::{
+        Add,
+        Div,
+        Mul,
+        Sub,
+    },
+};
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    Neighborhood,
+    traits::{
+        Matrix,
+        Max,
+    },
+    error::MatrixError,
+};
+
+/// Laplace scale of a neighborhood whose sum of differences approximates the Laplacian, the
+/// Margolus block being rejected.
+pub(crate) fn laplace_scale(hood_type: Neighborhood, size: usize) -> Result<usize, MatrixError> {
+    hood_type
+        .laplace_scale(size)
+        .ok_or_else(|| MatrixError::InvalidRule(format!("the Laplacian is not defined over the {hood_type:?} neighborhood")))
+}
+
+/// Absolute indices of the neighbors of every point, center excluded, as the lists of points whose
+/// differences to the center `laplace_operator` sums.
+pub(crate) fn stencil_neighbors<T>(matrix: &MatrixImage<T>, size: usize, hood_type: Neighborhood) -> Result<Vec<Vec<usize>>, MatrixError>
+where T: Clone + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+{
+    let hood_type = hood_type.per_cell()?;
+    (0..matrix.get_width() * matrix.get_height())
+        .map(|index| {
+            let center = matrix.into_2d_point(index)?;
+            let mut hood = matrix
+                .get_lattice_neighborhood(center, size, hood_type)
+                .into_iter()
+                .map(|point| matrix.into_absolute_point(point))
+                .collect::<Result<Vec<usize>, MatrixError>>()?;
+            if let Some(position) = hood.iter().position(|neighbor| *neighbor == index) {
+                hood.swap_remove(position);
+            }
+            Ok(hood)
+        })
+        .collect()
+}
@@ -0,0 +1,392 @@
+//!   Iterative solvers of the Poisson equation, ∇²u = f, on a periodic lattice with grid spacing dx,
+//!   over the same stencils as `laplace_operator`. Points of an optional mask keep their initial
+//!   value, e.g. sources and sinks of a potential field; without any, the source is shifted to
+//!   zero mean, as a periodic problem requires, and the potential is returned with zero mean.
+//!
+//!   Jacobi and Gauss-Seidel smooth the error quickly but converge slowly, in O(n²) iterations on an
+//!   n×n lattice, SOR with a good factor in O(n), and multigrid V-cycles in a number of cycles
+//!   independent of the size of the lattice.
+
+use core::{
+    fmt::Debug,
+    ops::{
+        Add,
+        Div,
+        Mul,
+        Sub,
+    },
+};
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    integrators::Scalar,
+    traits::{
+        Matrix,
+        Max,
+    },
+    error::MatrixError,
+};
+use super::{
+    laplace_scale,
+    stencil_neighbors,
+};
+
+/// Relaxation sweeps before and after the coarse grid correction of a V-cycle.
+const SMOOTHING_SWEEPS: usize = 2;
+/// Relaxation sweeps solving the coarsest grid of a V-cycle.
+const COARSEST_SWEEPS: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    /// Every point relaxed from the values of the previous iteration.
+    Jacobi,
+    /// Points relaxed in place, those with an even x + y first and then the others.
+    RedBlackGaussSeidel,
+    /// Successive over-relaxation with the given factor ω in (0, 2), points being relaxed in place
+    /// row by row and moved ω times their Gauss-Seidel update. ω = 1 is plain Gauss-Seidel.
+    Sor(f64),
+    /// Geometric multigrid V-cycles, halving the lattice while both of its sides are even, with
+    /// red-black Gauss-Seidel smoothing and bilinear interpolation of the corrections.
+    Multigrid,
+}
+
+impl Method {
+    /// SOR with the optimal factor of the five-point stencil on a lattice of the given size,
+    /// 2 / (1 + sin(π / n)) with n the longest side.
+    pub fn optimal_sor(width: usize, height: usize) -> Self {
+        let sides = width.max(height).max(2) as f64;
+        Method::Sor(2.0 / (1.0 + (core::f64::consts::PI / sides).sin()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Poisson {
+    method: Method,
+    grid_spacing: f64,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    tolerance: f64,
+    max_iterations: usize,
+    fixed: Option<MatrixImage<u8>>,
+}
+
+/// Discretization of the problem on one grid of a V-cycle.
+struct Level {
+    width: usize,
+    height: usize,
+    neighbors: Vec<Vec<usize>>,
+    fixed: Vec<bool>,
+    /// 1 / (laplace_scale · dx²), the factor of the sum of differences to the neighbors.
+    coefficient: f64,
+}
+
+impl Poisson {
+    /// Solver with the given method on a unit grid over the five-point stencil, iterating until the
+    /// root mean square residual falls below 1e-6, for at most 1000 iterations.
+    pub fn new(method: Method) -> Self {
+        Poisson {
+            method,
+            grid_spacing: 1.0,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            tolerance: 1e-6,
+            max_iterations: 1000,
+            fixed: None,
+        }
+    }
+    pub fn with_grid_spacing(mut self, grid_spacing: f64) -> Self {
+        self.grid_spacing = grid_spacing;
+        self
+    }
+    /// Neighborhood over which the Laplacian is computed, scaled by `Neighborhood::laplace_scale`.
+    pub fn with_stencil(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    /// Root mean square residual at which iterations stop, and the number of iterations, or
+    /// V-cycles, after which they fail with `MatrixError::NotConverged`.
+    pub fn with_tolerance(mut self, tolerance: f64, max_iterations: usize) -> Self {
+        self.tolerance = tolerance;
+        self.max_iterations = max_iterations;
+        self
+    }
+    /// Points where the mask is not 0 keep the value of the initial potential.
+    pub fn with_fixed(mut self, mask: MatrixImage<u8>) -> Self {
+        self.fixed = Some(mask);
+        self
+    }
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+    /// Solves ∇²u = f from an initial potential, returning the potential and the root mean square
+    /// residual after every iteration.
+    pub fn solve<T>(&self, source: &MatrixImage<T>, initial: &MatrixImage<T>) -> Result<(MatrixImage<T>, Vec<f64>), MatrixError>
+    where T: Scalar + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+    {
+        let dimensions = (source.get_width(), source.get_height());
+        for found in [(initial.get_width(), initial.get_height())]
+            .into_iter()
+            .chain(self.fixed.iter().map(|mask| (mask.get_width(), mask.get_height())))
+        {
+            if found != dimensions {
+                return Err(MatrixError::DimensionMismatch { expected: dimensions, found });
+            }
+        }
+        let levels = self.levels(dimensions)?;
+        let mut source: Vec<f64> = source.get_data_ref().iter().map(Scalar::to_f64).collect();
+        let mut potential: Vec<f64> = initial.get_data_ref().iter().map(Scalar::to_f64).collect();
+        let periodic = !levels[0].fixed.contains(&true);
+        if periodic {
+            let mean = source.iter().sum::<f64>() / source.len() as f64;
+            source.iter_mut().for_each(|value| *value -= mean);
+        }
+        let mut residual = vec![0.0; source.len()];
+        let mut residuals = Vec::new();
+        loop {
+            match self.method {
+                Method::Jacobi => jacobi(&levels[0], &mut potential, &source),
+                Method::RedBlackGaussSeidel => red_black(&levels[0], &mut potential, &source),
+                Method::Sor(omega) => sor(&levels[0], &mut potential, &source, omega),
+                Method::Multigrid => v_cycle(&levels, &mut potential, &source),
+            }
+            levels[0].residual(&potential, &source, &mut residual);
+            let norm = root_mean_square(&residual);
+            residuals.push(norm);
+            if norm <= self.tolerance {
+                break;
+            }
+            if residuals.len() >= self.max_iterations || !norm.is_finite() {
+                return Err(MatrixError::NotConverged { iterations: residuals.len(), residual: norm });
+            }
+        }
+        if periodic {
+            let mean = potential.iter().sum::<f64>() / potential.len() as f64;
+            potential.iter_mut().for_each(|value| *value -= mean);
+        }
+        let mut solution = initial.clone();
+        for (value, solved) in solution.get_data_mut_ref().iter_mut().zip(potential) {
+            *value = T::from_f64(solved);
+        }
+        Ok((solution, residuals))
+    }
+    /// Residual f - ∇²u at every point, computed with `laplace_operator`.
+    pub fn residual<T>(&self, source: &MatrixImage<T>, potential: &MatrixImage<T>) -> Result<MatrixImage<T>, MatrixError>
+    where T: Scalar + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+    {
+        let coefficient = self.coefficient(0)?;
+        let mut residual = source.clone();
+        for point in 0..(source.get_width()*source.get_height()) {
+            let center = source.into_2d_point(point)?;
+            let laplace = potential.laplace_operator(center, self.hood_size, self.hood_type)?.to_f64();
+            residual.edit_point(center, T::from_f64(source.get_absolute_point_data(point).to_f64() - coefficient * laplace))?;
+        }
+        Ok(residual)
+    }
+    fn coefficient(&self, depth: usize) -> Result<f64, MatrixError> {
+        let spacing = self.grid_spacing * (1 << depth) as f64;
+        Ok(1.0 / (laplace_scale(self.hood_type, self.hood_size)?.max(1) as f64 * spacing * spacing))
+    }
+    /// Grids of the V-cycles, from the lattice itself to the coarsest one, or the lattice alone for
+    /// the other methods.
+    fn levels(&self, (width, height): (usize, usize)) -> Result<Vec<Level>, MatrixError> {
+        let fixed = match &self.fixed {
+            Some(mask) => mask.get_data_ref().iter().map(|value| *value != 0).collect(),
+            None => vec![false; width * height],
+        };
+        let mut levels = vec![self.level(width, height, fixed, 0)?];
+        let smallest = 2 * self.hood_size + 2;
+        let coarsens = |level: &Level| level.width.is_multiple_of(2) && level.height.is_multiple_of(2) && level.width.min(level.height) / 2 >= smallest;
+        while self.method == Method::Multigrid && coarsens(&levels[levels.len() - 1]) {
+            let fine = &levels[levels.len() - 1];
+            let (width, height) = (fine.width / 2, fine.height / 2);
+            let fixed = (0..width * height)
+                .map(|index| fine.block(index % width, index / width).iter().any(|point| fine.fixed[*point]))
+                .collect();
+            let depth = levels.len();
+            levels.push(self.level(width, height, fixed, depth)?);
+        }
+        Ok(levels)
+    }
+    fn level(&self, width: usize, height: usize, fixed: Vec<bool>, depth: usize) -> Result<Level, MatrixError> {
+        let grid: MatrixImage<u8> = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(height, width)
+            .build();
+        Ok(Level {
+            width,
+            height,
+            neighbors: stencil_neighbors(&grid, self.hood_size, self.hood_type)?,
+            fixed,
+            coefficient: self.coefficient(depth)?,
+        })
+    }
+}
+
+impl Level {
+    /// Value of a free point that zeroes its residual given the values of its neighbors.
+    fn relaxed(&self, potential: &[f64], source: &[f64], index: usize) -> f64 {
+        let hood = &self.neighbors[index];
+        let sum: f64 = hood.iter().map(|neighbor| potential[*neighbor]).sum();
+        (sum - source[index] / self.coefficient) / hood.len() as f64
+    }
+    fn residual(&self, potential: &[f64], source: &[f64], residual: &mut [f64]) {
+        for (index, hood) in self.neighbors.iter().enumerate() {
+            residual[index] = if self.fixed[index] {
+                0.0
+            } else {
+                let laplace: f64 = hood.iter().map(|neighbor| potential[*neighbor] - potential[index]).sum();
+                source[index] - self.coefficient * laplace
+            };
+        }
+    }
+    /// Absolute indices of the 2×2 block of this grid under a point of the next coarser one.
+    fn block(&self, coarse_x: usize, coarse_y: usize) -> [usize; 4] {
+        let (x, y) = (2 * coarse_x, 2 * coarse_y);
+        [y * self.width + x, y * self.width + x + 1, (y + 1) * self.width + x, (y + 1) * self.width + x + 1]
+    }
+}
+
+fn root_mean_square(values: &[f64]) -> f64 {
+    (values.iter().map(|value| value * value).sum::<f64>() / values.len().max(1) as f64).sqrt()
+}
+
+fn jacobi(level: &Level, potential: &mut [f64], source: &[f64]) {
+    let previous = potential.to_vec();
+    for (index, value) in potential.iter_mut().enumerate() {
+        if !level.fixed[index] {
+            *value = level.relaxed(&previous, source, index);
+        }
+    }
+}
+
+fn red_black(level: &Level, potential: &mut [f64], source: &[f64]) {
+    for parity in [0, 1] {
+        for index in 0..potential.len() {
+            if (index % level.width + index / level.width) % 2 == parity && !level.fixed[index] {
+                potential[index] = level.relaxed(potential, source, index);
+            }
+        }
+    }
+}
+
+fn sor(level: &Level, potential: &mut [f64], source: &[f64], omega: f64) {
+    for index in 0..potential.len() {
+        if !level.fixed[index] {
+            potential[index] += omega * (level.relaxed(potential, source, index) - potential[index]);
+        }
+    }
+}
+
+/// One V-cycle on the grid of `levels[0]`, recursing on the coarser grids for the correction.
+fn v_cycle(levels: &[Level], potential: &mut [f64], source: &[f64]) {
+    let level = &levels[0];
+    let Some(coarse) = levels.get(1) else {
+        for _ in 0..COARSEST_SWEEPS {
+            red_black(level, potential, source);
+        }
+        return;
+    };
+    for _ in 0..SMOOTHING_SWEEPS {
+        red_black(level, potential, source);
+    }
+    let mut residual = vec![0.0; potential.len()];
+    level.residual(potential, source, &mut residual);
+    let coarse_source: Vec<f64> = (0..coarse.width * coarse.height)
+        .map(|index| level.block(index % coarse.width, index / coarse.width).iter().map(|point| residual[*point]).sum::<f64>() / 4.0)
+        .collect();
+    let mut correction = vec![0.0; coarse_source.len()];
+    v_cycle(&levels[1..], &mut correction, &coarse_source);
+    for (index, value) in potential.iter_mut().enumerate() {
+        if level.fixed[index] {
+            continue;
+        }
+        let (x, y) = (index % level.width, index / level.width);
+        let (coarse_x, coarse_y) = (x / 2, y / 2);
+        let side_x = (coarse_x + if x % 2 == 0 { coarse.width - 1 } else { 1 }) % coarse.width;
+        let side_y = (coarse_y + if y % 2 == 0 { coarse.height - 1 } else { 1 }) % coarse.height;
+        let at = |x: usize, y: usize| correction[y * coarse.width + x];
+        *value += (9.0 * at(coarse_x, coarse_y) + 3.0 * at(side_x, coarse_y) + 3.0 * at(coarse_x, side_y) + at(side_x, side_y)) / 16.0;
+    }
+    for _ in 0..SMOOTHING_SWEEPS {
+        red_black(level, potential, source);
+    }
+}
+
+/// Gradient of a potential at a point by central differences, wrapping around the edges, with the
+/// given grid spacing. Agents climbing the potential move along it.
+pub fn gradient<T>(potential: &MatrixImage<T>, point: (u32, u32), grid_spacing: f64) -> Result<(f64, f64), MatrixError>
+where T: Scalar
+{
+    let value = |offset: (i64, i64)| potential.get_point_value(potential.offset_point(point, offset)).map(|value| value.to_f64());
+    Ok((
+        (value((1, 0))? - value((-1, 0))?) / (2.0 * grid_spacing),
+        (value((0, 1))? - value((0, -1))?) / (2.0 * grid_spacing),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        models::poisson::{
+            self,
+            Method,
+            Poisson,
+        },
+        traits::Matrix,
+        error::MatrixError,
+    };
+
+    fn lattice(width: usize, height: usize, value: impl Fn(usize, usize) -> f64) -> MatrixImage<f64> {
+        let mut matrix: MatrixImage<f64> = MatrixImageBuilder::init()
+            .with_initial_value(0.0)
+            .with_height_and_width(height, width)
+            .build();
+        for y in 0..height {
+            for x in 0..width {
+                matrix.edit_point((x as u32, y as u32), value(x, y)).unwrap();
+            }
+        }
+        matrix
+    }
+
+    #[test]
+    fn methods_agree_on_a_periodic_problem() {
+        let source = lattice(16, 16, |x, y| if (x, y) == (4, 4) { 1.0 } else if (x, y) == (11, 9) { -1.0 } else { 0.0 });
+        let zero = lattice(16, 16, |_, _| 0.0);
+        let (expected, _) = Poisson::new(Method::Multigrid).with_tolerance(1e-10, 100).solve(&source, &zero).unwrap();
+        for method in [Method::Jacobi, Method::RedBlackGaussSeidel, Method::optimal_sor(16, 16)] {
+            let (potential, residuals) = Poisson::new(method).with_tolerance(1e-10, 5000).solve(&source, &zero).unwrap();
+            assert!(residuals.last().unwrap() <= &1e-10);
+            let difference = potential.get_data_ref().iter().zip(expected.get_data_ref()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+            assert!(difference < 1e-6, "{method:?} {difference}");
+        }
+        let residual = Poisson::new(Method::Jacobi).residual(&source, &expected).unwrap();
+        assert!(residual.get_data_ref().iter().all(|value| value.abs() < 1e-9));
+        let (slope_x, _) = poisson::gradient(&expected, (5, 4), 1.0).unwrap();
+        assert!(slope_x > 0.0);
+    }
+
+    #[test]
+    fn multigrid_needs_few_cycles_and_keeps_fixed_points() {
+        let mut mask: MatrixImage<u8> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(32, 32).build();
+        mask.edit_point((8_u32, 8_u32), 1).unwrap();
+        mask.edit_point((24_u32, 20_u32), 1).unwrap();
+        let initial = lattice(32, 32, |x, y| if (x, y) == (8, 8) { 1.0 } else { 0.0 });
+        let zero = lattice(32, 32, |_, _| 0.0);
+        let multigrid = Poisson::new(Method::Multigrid).with_fixed(mask.clone()).with_tolerance(1e-8, 100);
+        let (potential, cycles) = multigrid.solve(&zero, &initial).unwrap();
+        let (_, sweeps) = Poisson::new(Method::RedBlackGaussSeidel).with_fixed(mask.clone()).with_tolerance(1e-8, 10000).solve(&zero, &initial).unwrap();
+        assert!(cycles.len() * 10 < sweeps.len(), "{} {}", cycles.len(), sweeps.len());
+        assert!(cycles.windows(2).all(|pair| pair[1] < pair[0]));
+        assert_eq!(potential.get_point_value((8_u32, 8_u32)).unwrap(), 1.0);
+        assert_eq!(potential.get_point_value((24_u32, 20_u32)).unwrap(), 0.0);
+        assert!(potential.get_data_ref().iter().all(|value| (0.0..=1.0).contains(value)));
+        let jacobi = Poisson::new(Method::Jacobi).with_fixed(mask.clone()).with_tolerance(1e-8, 10);
+        assert!(matches!(jacobi.solve(&zero, &initial), Err(MatrixError::NotConverged { iterations: 10, .. })));
+    }
+}
@@ -0,0 +1,246 @@
+//!   Reaction-diffusion systems of N species, where every species diffuses with its own coefficient
+//!   and reacts with the others through a pointwise reaction function:
+//!
+//!   du_i/dt = D_i ∇²u_i + f_i(u_1, ..., u_N)
+//!
+//!   The species are the channels of an `NChannelMatrix`. Brusselator, FitzHugh-Nagumo, Schnakenberg
+//!   and Gierer-Meinhardt are provided as two species reference models.
+
+use core::{
+    fmt::Debug,
+    ops::{
+        Div,
+        Mul,
+        Add,
+        Sub,
+    },
+};
+use crate::{
+    MatrixImageBuilder,
+    Neighborhood,
+    n_channel::NChannelMatrix,
+    simulation::Rule,
+    traits::{
+        Matrix,
+        Max,
+        LatticeElement,
+    },
+    error::MatrixError,
+};
+use super::laplace_scale;
+
+type Atom = LatticeElement<f32>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReactionDiffusion<T, F, const N: usize> {
+    diffusion: [T; N],
+    reaction: F,
+    time_step: T,
+    hood_type: Neighborhood,
+    hood_size: usize,
+}
+
+impl<T, F, const N: usize> ReactionDiffusion<T, F, N>
+where
+    T: Clone + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd + From<u8>,
+    F: Fn(&[T; N]) -> [T; N],
+{
+    /// System with a diffusion coefficient per species and the reaction rates of all species given
+    /// their concentrations at a point, with a unit time step and the five-point stencil.
+    pub fn new(diffusion: [T; N], reaction: F) -> Self {
+        ReactionDiffusion {
+            diffusion,
+            reaction,
+            time_step: T::from(1),
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+        }
+    }
+    pub fn get_diffusion_ref(&self) -> &[T; N] {
+        &self.diffusion
+    }
+    pub fn get_time_step(&self) -> T {
+        self.time_step.clone()
+    }
+    pub fn with_time_step(mut self, time_step: T) -> Self {
+        self.time_step = time_step;
+        self
+    }
+    /// Neighborhood over which the Laplacian is computed, scaled by `Neighborhood::laplace_scale`.
+    pub fn with_stencil(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    /// Rates of change of all species, diffusion and reaction, at every point.
+    pub fn derivative(&self, state: &NChannelMatrix<T, N>) -> Result<NChannelMatrix<T, N>, MatrixError> {
+        let scale = from_count::<T>(laplace_scale(self.hood_type, self.hood_size)?.max(1));
+        let species = state.get_data_ref();
+        let mut derivative = state.clone();
+        for point in 0..(*state.get_width() * *state.get_height()) {
+            let center = species[0].into_2d_point(point)?;
+            let concentrations: [T; N] = core::array::from_fn(|index| species[index].get_absolute_point_data(point));
+            let reaction = (self.reaction)(&concentrations);
+            for (index, rate) in reaction.into_iter().enumerate() {
+                let laplace = species[index].laplace_operator(center, self.hood_size, self.hood_type)? / scale.clone();
+                derivative.get_data_mut_ref()[index].edit_point(center, self.diffusion[index].clone() * laplace + rate)?;
+            }
+        }
+        Ok(derivative)
+    }
+    /// Advances the concentrations by one explicit Euler step.
+    pub fn next_state(&self, state: &NChannelMatrix<T, N>) -> Result<NChannelMatrix<T, N>, MatrixError> {
+        let derivative = self.derivative(state)?;
+        let mut next = state.clone();
+        for (species, rates) in next.get_data_mut_ref().iter_mut().zip(derivative.get_data_ref()) {
+            for point in 0..(species.get_width()*species.get_height()) {
+                let value = species.get_absolute_point_data(point) + self.time_step.clone() * rates.get_absolute_point_data(point);
+                species.edit_point(species.into_2d_point(point)?, value)?;
+            }
+        }
+        Ok(next)
+    }
+}
+
+impl<T, F, const N: usize> Rule<NChannelMatrix<T, N>> for ReactionDiffusion<T, F, N>
+where
+    T: Clone + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd + From<u8>,
+    F: Fn(&[T; N]) -> [T; N],
+{
+    fn step(&mut self, state: &NChannelMatrix<T, N>) -> Result<NChannelMatrix<T, N>, MatrixError> {
+        self.next_state(state)
+    }
+}
+
+/// Count as a concentration, built by doubling and adding ones so that no integer narrower than
+/// `usize` is involved.
+fn from_count<T: Clone + Add<Output=T> + From<u8>>(count: usize) -> T {
+    (0..usize::BITS).rev().fold(T::from(0), |value, bit| {
+        let doubled = value.clone() + value;
+        if count >> bit & 1 == 1 { doubled + T::from(1) } else { doubled }
+    })
+}
+
+/// Lattice with every species at the given concentration everywhere.
+pub fn uniform_state<T, const N: usize>(width: usize, height: usize, concentrations: [T; N]) -> Result<NChannelMatrix<T, N>, MatrixError>
+where T: Clone + Debug + Default + Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd
+{
+    NChannelMatrix::try_from(concentrations.map(|concentration| MatrixImageBuilder::init()
+        .with_initial_value(concentration)
+        .with_height_and_width(height, width)
+        .build()))
+}
+
+/// Adds noise of the given amplitude, centered on zero, to every species, with the generator
+/// returning numbers in [0, 1). Turing patterns grow from such perturbations of a steady state.
+pub fn perturb<const N: usize>(state: &mut NChannelMatrix<Atom, N>, amplitude: f32, mut generator: impl FnMut() -> f32) -> Result<(), MatrixError> {
+    for species in state.get_data_mut_ref() {
+        for point in 0..(species.get_width()*species.get_height()) {
+            let value = species.get_absolute_point_data(point).0 + amplitude * (generator() - 0.5);
+            species.edit_point(species.into_2d_point(point)?, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Brusselator, du/dt = a - (b + 1)u + u²v and dv/dt = bu - u²v, with steady state u = a and
+/// v = b / a. Diffusion coefficients 1 and 8 and time step 0.01, forming Turing patterns when
+/// (1 + a / √8)² < b < 1 + a², e.g. a = 3 and b = 9.
+pub fn brusselator(a: f32, b: f32) -> ReactionDiffusion<Atom, impl Fn(&[Atom; 2]) -> [Atom; 2], 2> {
+    ReactionDiffusion::new([LatticeElement(1.0), LatticeElement(8.0)], move |[u, v]: &[Atom; 2]| {
+        let (u, v) = (u.0, v.0);
+        [LatticeElement(a - (b + 1.0) * u + u * u * v), LatticeElement(b * u - u * u * v)]
+    })
+    .with_time_step(LatticeElement(0.01))
+}
+
+/// FitzHugh-Nagumo, du/dt = u - u³ - v and dv/dt = ε(u - a₁v - a₀), an excitable activator u with
+/// a linear inhibitor v. Diffusion coefficients 1 and 20 and time step 0.01; with ε = 1, a₁ = 2
+/// and a₀ = 0 the steady state u = v = 0 breaks into labyrinths.
+pub fn fitzhugh_nagumo(epsilon: f32, a0: f32, a1: f32) -> ReactionDiffusion<Atom, impl Fn(&[Atom; 2]) -> [Atom; 2], 2> {
+    ReactionDiffusion::new([LatticeElement(1.0), LatticeElement(20.0)], move |[u, v]: &[Atom; 2]| {
+        let (u, v) = (u.0, v.0);
+        [LatticeElement(u - u * u * u - v), LatticeElement(epsilon * (u - a1 * v - a0))]
+    })
+    .with_time_step(LatticeElement(0.01))
+}
+
+/// Schnakenberg, du/dt = a - u + u²v and dv/dt = b - u²v, with steady state u = a + b and
+/// v = b / (a + b)². Diffusion coefficients 1 and 40 and time step 0.005, forming spots with
+/// a = 0.1 and b = 0.9.
+pub fn schnakenberg(a: f32, b: f32) -> ReactionDiffusion<Atom, impl Fn(&[Atom; 2]) -> [Atom; 2], 2> {
+    ReactionDiffusion::new([LatticeElement(1.0), LatticeElement(40.0)], move |[u, v]: &[Atom; 2]| {
+        let (u, v) = (u.0, v.0);
+        [LatticeElement(a - u + u * u * v), LatticeElement(b - u * u * v)]
+    })
+    .with_time_step(LatticeElement(0.005))
+}
+
+/// Gierer-Meinhardt, du/dt = a + u²/v - bu and dv/dt = u² - v, an activator u with its inhibitor
+/// v, with steady state u = (a + 1) / b and v = u². Diffusion coefficients 1 and 20 and time step
+/// 0.01, forming spots with a = 0.1 and b = 1.
+pub fn gierer_meinhardt(a: f32, b: f32) -> ReactionDiffusion<Atom, impl Fn(&[Atom; 2]) -> [Atom; 2], 2> {
+    ReactionDiffusion::new([LatticeElement(1.0), LatticeElement(20.0)], move |[u, v]: &[Atom; 2]| {
+        let (u, v) = (u.0, v.0);
+        [LatticeElement(a + u * u / v - b * u), LatticeElement(u * u - v)]
+    })
+    .with_time_step(LatticeElement(0.01))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        models::reaction_diffusion::{
+            self,
+            ReactionDiffusion,
+        },
+        traits::{
+            Matrix,
+            LatticeElement,
+        },
+    };
+
+    #[test]
+    fn diffusion_conserves_mass() {
+        let model = ReactionDiffusion::new([LatticeElement(0.2_f32), LatticeElement(0.0)], |_: &[LatticeElement<f32>; 2]| [LatticeElement(0.0), LatticeElement(0.0)]);
+        let mut state = reaction_diffusion::uniform_state(9, 7, [LatticeElement(0.0), LatticeElement(1.0)]).unwrap();
+        state.get_data_mut_ref()[0].edit_point((4_u32, 3_u32), 10.0).unwrap();
+        for _ in 0..10 {
+            state = model.next_state(&state).unwrap();
+        }
+        let total: f32 = state.get_data_ref()[0].get_data().iter().map(|value| value.0).sum();
+        assert!((total - 10.0).abs() < 1e-4);
+        assert!(state.get_data_ref()[0].get_point_value((4_u32, 3_u32)).unwrap().0 < 10.0);
+        assert!(state.get_data_ref()[1].get_data().iter().all(|value| value.0 == 1.0));
+    }
+
+    #[test]
+    fn wide_stencils_are_scaled_without_overflow() {
+        for (hood_type, hood_size) in [(Neighborhood::Moore, 4), (Neighborhood::VonNeumann, 6)] {
+            let model = ReactionDiffusion::new([LatticeElement(0.2_f32)], |_: &[LatticeElement<f32>; 1]| [LatticeElement(0.0)])
+                .with_stencil(hood_type, hood_size);
+            let mut state = reaction_diffusion::uniform_state(15, 15, [LatticeElement(0.0)]).unwrap();
+            state.get_data_mut_ref()[0].edit_point((7_u32, 7_u32), 10.0).unwrap();
+            let next = model.next_state(&state).unwrap();
+            let total: f32 = next.get_data_ref()[0].get_data().iter().map(|value| value.0).sum();
+            assert!((total - 10.0).abs() < 1e-4);
+            assert!(next.get_data_ref()[0].get_point_value((7_u32, 7_u32)).unwrap().0 < 10.0);
+        }
+    }
+
+    #[test]
+    fn reference_models_keep_their_steady_states() {
+        let (a, b) = (3.0, 9.0);
+        let brusselator = reaction_diffusion::brusselator(a, b);
+        let state = reaction_diffusion::uniform_state(5, 5, [LatticeElement(a), LatticeElement(b / a)]).unwrap();
+        assert_eq!(brusselator.next_state(&state).unwrap(), state);
+        let (a, b) = (0.1, 0.9);
+        let schnakenberg = reaction_diffusion::schnakenberg(a, b);
+        let state = reaction_diffusion::uniform_state(5, 5, [LatticeElement(a + b), LatticeElement(b / ((a + b) * (a + b)))]).unwrap();
+        let next = schnakenberg.next_state(&state).unwrap();
+        for (species, next_species) in state.get_data_ref().iter().zip(next.get_data_ref()) {
+            assert!(species.get_data().iter().zip(next_species.get_data()).all(|(value, next)| (value.0 - next.0).abs() < 1e-6));
+        }
+    }
+}
@@ -0,0 +1,471 @@
+//!   Ising and Potts spin lattices in contact with a heat bath, with the energy
+//!
+//!   E = -J Σ⟨ij⟩ σ(sᵢ, sⱼ) - h Σᵢ φ(sᵢ)
+//!
+//!   summed over the pairs of neighbors of the neighborhood, where σ(a, b) = ab and φ(a) = a for
+//!   Ising spins of ±1, and σ(a, b) = δ(a, b) and φ(a) = δ(a, 0) for Potts spins from 0 to q - 1.
+//!   Temperatures are in units of the coupling over Boltzmann's constant.
+//!
+//!   Single-flip sweeps, Metropolis or heat-bath, visit as many random sites as the lattice holds.
+//!   Wolff updates grow and flip one cluster of aligned spins, which decorrelates the lattice far
+//!   faster near the critical temperature; the field is then accounted for by accepting the flip of
+//!   the cluster with the Metropolis probability of its field energy.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    palette::Palette,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+use super::stencil_neighbors;
+
+/// Spins of ±1 for the Ising model and from 0 to q - 1 for the q-state Potts model.
+pub type Spins = MatrixImage<i8>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interaction {
+    Ising,
+    /// Potts model with the given number of states, at most 127 so that every state fits a spin.
+    Potts(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Update {
+    /// Sweep of single spin changes accepted with probability min(1, exp(-ΔE/T)).
+    Metropolis,
+    /// Sweep of single spins drawn from their Boltzmann distribution given their neighbors, also
+    /// known as Glauber dynamics.
+    HeatBath,
+    /// Flip of a single Wolff cluster.
+    Wolff,
+}
+
+/// Order parameter and energy per site of a lattice.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Observables {
+    /// Mean spin for the Ising model, and (q·n₀ - 1) / (q - 1) for the Potts model, where n₀ is the
+    /// fraction of sites in the most common state.
+    pub magnetization: f64,
+    pub energy: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpinModel {
+    interaction: Interaction,
+    coupling: f64,
+    temperature: f64,
+    field: f64,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    palette: Palette,
+}
+
+impl SpinModel {
+    /// Ferromagnetic Ising model with unit coupling over the von Neumann neighborhood, without field.
+    pub fn ising(temperature: f64) -> Self {
+        Self::new(Interaction::Ising, temperature, Palette::new(vec![Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255])]))
+    }
+    /// Ferromagnetic Potts model of the given number of states, clamped between 2 and 127, with unit
+    /// coupling over the von Neumann neighborhood, without field.
+    pub fn potts(states: u8, temperature: f64) -> Self {
+        let states = states.clamp(2, i8::MAX as u8);
+        Self::new(Interaction::Potts(states), temperature, Palette::gradient(Rgba([32, 0, 128, 255]), Rgba([255, 224, 0, 255]), states as usize))
+    }
+    fn new(interaction: Interaction, temperature: f64, palette: Palette) -> Self {
+        SpinModel {
+            interaction,
+            coupling: 1.0,
+            temperature,
+            field: 0.0,
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            palette,
+        }
+    }
+    /// Coupling J between neighbors, ferromagnetic when positive. Wolff updates need a positive one.
+    pub fn with_coupling(mut self, coupling: f64) -> Self {
+        self.coupling = coupling;
+        self
+    }
+    pub fn with_temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+    /// External field h, favoring spins of +1 for the Ising model and of state 0 for the Potts model.
+    pub fn with_field(mut self, field: f64) -> Self {
+        self.field = field;
+        self
+    }
+    pub fn with_neighborhood(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_interaction(&self) -> Interaction {
+        self.interaction
+    }
+    pub fn get_coupling(&self) -> f64 {
+        self.coupling
+    }
+    pub fn get_temperature(&self) -> f64 {
+        self.temperature
+    }
+    pub fn get_field(&self) -> f64 {
+        self.field
+    }
+    /// Exact critical temperature of the model on the square lattice, with the von Neumann
+    /// neighborhood of size 1 and no field: 2J / ln(1 + √2) for the Ising model and J / ln(1 + √q)
+    /// for the Potts model.
+    pub fn critical_temperature(&self) -> f64 {
+        match self.interaction {
+            Interaction::Ising => 2.0 * self.coupling / (1.0 + core::f64::consts::SQRT_2).ln(),
+            Interaction::Potts(states) => self.coupling / (1.0 + (states as f64).sqrt()).ln(),
+        }
+    }
+    /// Values a spin can take.
+    pub fn states(&self) -> Vec<i8> {
+        match self.interaction {
+            Interaction::Ising => vec![-1, 1],
+            Interaction::Potts(states) => (0..states as i8).collect(),
+        }
+    }
+    /// Lattice with every spin in the state favored by the field.
+    pub fn ordered_state(&self, width: usize, height: usize) -> Spins {
+        let aligned = match self.interaction {
+            Interaction::Ising => 1,
+            Interaction::Potts(_) => 0,
+        };
+        MatrixImageBuilder::init()
+            .with_initial_value(aligned)
+            .with_height_and_width(height, width)
+            .build()
+    }
+    /// Lattice of independent random spins, the infinite temperature state, with the generator
+    /// returning numbers in [0, 1).
+    pub fn random_state(&self, width: usize, height: usize, mut generator: impl FnMut() -> f64) -> Spins {
+        let states = self.states();
+        let mut spins = self.ordered_state(width, height);
+        for spin in spins.get_data_mut_ref() {
+            *spin = states[pick(states.len(), generator())];
+        }
+        spins
+    }
+    fn pair(&self, a: i8, b: i8) -> f64 {
+        match self.interaction {
+            Interaction::Ising => (a * b) as f64,
+            Interaction::Potts(_) => (a == b) as u8 as f64,
+        }
+    }
+    fn single(&self, a: i8) -> f64 {
+        match self.interaction {
+            Interaction::Ising => a as f64,
+            Interaction::Potts(_) => (a == 0) as u8 as f64,
+        }
+    }
+    /// Energy of the bonds and field term of a site holding the given value.
+    fn site_energy(&self, spins: &[i8], hood: &[usize], value: i8) -> f64 {
+        let bonds: f64 = hood.iter().map(|neighbor| self.pair(value, spins[*neighbor])).sum();
+        -self.coupling * bonds - self.field * self.single(value)
+    }
+    /// Change in energy of the lattice when the spin at a point takes the given value.
+    pub fn flip_energy(&self, spins: &Spins, point: (u32, u32), value: i8) -> Result<f64, MatrixError> {
+        let current = spins.get_point_value(point)?;
+        let hood = spins
+            .get_lattice_neighborhood(point, self.hood_size, self.hood_type.per_cell()?)
+            .into_iter()
+            .filter(|neighbor| *neighbor != point)
+            .map(|neighbor| spins.into_absolute_point(neighbor))
+            .collect::<Result<Vec<usize>, MatrixError>>()?;
+        Ok(self.site_energy(spins.get_data_ref(), &hood, value) - self.site_energy(spins.get_data_ref(), &hood, current))
+    }
+    /// Magnetization and energy per site.
+    pub fn observables(&self, spins: &Spins) -> Result<Observables, MatrixError> {
+        let neighbors = stencil_neighbors(spins, self.hood_size, self.hood_type)?;
+        self.check(spins.get_data_ref())?;
+        Ok(self.observe(spins.get_data_ref(), &neighbors))
+    }
+    /// Rejects the lattices holding a spin outside of the states of the model.
+    fn check(&self, spins: &[i8]) -> Result<(), MatrixError> {
+        let states = self.states();
+        match spins.iter().find(|spin| !states.contains(spin)) {
+            Some(spin) => Err(MatrixError::InvalidRule(format!("spin {spin} is not a state of the {:?} model", self.interaction))),
+            None => Ok(()),
+        }
+    }
+    fn observe(&self, spins: &[i8], neighbors: &[Vec<usize>]) -> Observables {
+        let sites = spins.len().max(1) as f64;
+        let energy: f64 = spins
+            .iter()
+            .zip(neighbors)
+            .map(|(spin, hood)| {
+                let bonds: f64 = hood.iter().map(|neighbor| self.pair(*spin, spins[*neighbor])).sum();
+                -self.coupling * bonds / 2.0 - self.field * self.single(*spin)
+            })
+            .sum();
+        let magnetization = match self.interaction {
+            Interaction::Ising => spins.iter().map(|spin| *spin as f64).sum::<f64>() / sites,
+            Interaction::Potts(states) => {
+                let mut counts = vec![0_usize; states as usize];
+                for spin in spins {
+                    counts[*spin as usize] += 1;
+                }
+                let largest = counts.into_iter().max().unwrap_or(0) as f64 / sites;
+                (states as f64 * largest - 1.0) / (states as f64 - 1.0)
+            },
+        };
+        Observables {
+            magnetization,
+            energy: energy / sites,
+        }
+    }
+    /// Applies one update to the spins in place, with the generator returning numbers in [0, 1),
+    /// and returns the observables afterwards.
+    pub fn sweep(&self, spins: &mut Spins, update: Update, mut generator: impl FnMut() -> f64) -> Result<Observables, MatrixError> {
+        let neighbors = stencil_neighbors(spins, self.hood_size, self.hood_type)?;
+        self.check(spins.get_data_ref())?;
+        let states = self.states();
+        let data = spins.get_data_mut_ref();
+        let sites = data.len();
+        if sites == 0 {
+            return Ok(Observables::default());
+        }
+        match update {
+            Update::Metropolis => {
+                for _ in 0..sites {
+                    let index = pick(sites, generator());
+                    let current = data[index];
+                    let proposal = match self.interaction {
+                        Interaction::Ising => -current,
+                        Interaction::Potts(_) => {
+                            let other = states[pick(states.len() - 1, generator())];
+                            if other >= current { other + 1 } else { other }
+                        },
+                    };
+                    let change = self.site_energy(data, &neighbors[index], proposal) - self.site_energy(data, &neighbors[index], current);
+                    if change <= 0.0 || generator() < (-change / self.temperature).exp() {
+                        data[index] = proposal;
+                    }
+                }
+            },
+            Update::HeatBath => {
+                let mut weights = vec![0.0; states.len()];
+                for _ in 0..sites {
+                    let index = pick(sites, generator());
+                    for (weight, state) in weights.iter_mut().zip(&states) {
+                        *weight = self.site_energy(data, &neighbors[index], *state);
+                    }
+                    let lowest = weights.iter().copied().fold(f64::INFINITY, f64::min);
+                    weights.iter_mut().for_each(|weight| *weight = (-(*weight - lowest) / self.temperature).exp());
+                    let mut threshold = generator() * weights.iter().sum::<f64>();
+                    data[index] = states[weights.len() - 1];
+                    for (weight, state) in weights.iter().zip(&states) {
+                        if threshold < *weight {
+                            data[index] = *state;
+                            break;
+                        }
+                        threshold -= weight;
+                    }
+                }
+            },
+            Update::Wolff => {
+                let seed = pick(sites, generator());
+                let current = data[seed];
+                let target = match self.interaction {
+                    Interaction::Ising => -current,
+                    Interaction::Potts(_) => {
+                        let other = states[pick(states.len() - 1, generator())];
+                        if other >= current { other + 1 } else { other }
+                    },
+                };
+                let bond = match self.interaction {
+                    Interaction::Ising => 1.0 - (-2.0 * self.coupling / self.temperature).exp(),
+                    Interaction::Potts(_) => 1.0 - (-self.coupling / self.temperature).exp(),
+                };
+                let mut in_cluster = vec![false; sites];
+                let (mut cluster, mut frontier) = (vec![seed], vec![seed]);
+                in_cluster[seed] = true;
+                while let Some(index) = frontier.pop() {
+                    for neighbor in &neighbors[index] {
+                        if !in_cluster[*neighbor] && data[*neighbor] == current && generator() < bond {
+                            in_cluster[*neighbor] = true;
+                            cluster.push(*neighbor);
+                            frontier.push(*neighbor);
+                        }
+                    }
+                }
+                let change = -self.field * (self.single(target) - self.single(current)) * cluster.len() as f64;
+                if change <= 0.0 || generator() < (-change / self.temperature).exp() {
+                    for index in cluster {
+                        data[index] = target;
+                    }
+                }
+            },
+        }
+        Ok(self.observe(data, &neighbors))
+    }
+    /// Magnetic susceptibility N (⟨m²⟩ - ⟨|m|⟩²) / T of a series of observables over a lattice of N
+    /// sites.
+    pub fn susceptibility(&self, series: &[Observables], sites: usize) -> f64 {
+        let (square, absolute) = means(series.iter().map(|observables| observables.magnetization.abs()));
+        sites as f64 * (square - absolute * absolute) / self.temperature
+    }
+    /// Specific heat N (⟨e²⟩ - ⟨e⟩²) / T² of a series of observables over a lattice of N sites.
+    pub fn specific_heat(&self, series: &[Observables], sites: usize) -> f64 {
+        let (square, mean) = means(series.iter().map(|observables| observables.energy));
+        sites as f64 * (square - mean * mean) / (self.temperature * self.temperature)
+    }
+    pub fn draw(&self, spins: &Spins) -> Result<RgbaImage, MatrixError> {
+        let mut states: MatrixImage<u8> = MatrixImageBuilder::init()
+            .with_initial_value(0)
+            .with_height_and_width(spins.get_height(), spins.get_width())
+            .build();
+        for (state, spin) in states.get_data_mut_ref().iter_mut().zip(spins.get_data_ref()) {
+            *state = match self.interaction {
+                Interaction::Ising => (*spin > 0) as u8,
+                Interaction::Potts(_) => *spin as u8,
+            };
+        }
+        self.palette.draw(&states)
+    }
+}
+
+/// Index below `count` from a number in [0, 1).
+fn pick(count: usize, random: f64) -> usize {
+    ((random * count as f64) as usize).min(count.max(1) - 1)
+}
+
+/// Means of the squares and of the values.
+fn means(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (mut count, mut square, mut sum) = (0, 0.0, 0.0);
+    for value in values {
+        count += 1;
+        square += value * value;
+        sum += value;
+    }
+    let count = count.max(1) as f64;
+    (square / count, sum / count)
+}
+
+/// Spin model driven by an update and a generator of numbers in [0, 1), as a `Rule` recording the
+/// observables after every update.
+pub struct Dynamics<G: FnMut() -> f64> {
+    model: SpinModel,
+    update: Update,
+    generator: G,
+    observables: Vec<Observables>,
+}
+
+impl<G: FnMut() -> f64> Dynamics<G> {
+    pub fn new(model: SpinModel, update: Update, generator: G) -> Self {
+        Dynamics {
+            model,
+            update,
+            generator,
+            observables: Vec::new(),
+        }
+    }
+    pub fn get_model_ref(&self) -> &SpinModel {
+        &self.model
+    }
+    pub fn get_observables_ref(&self) -> &Vec<Observables> {
+        &self.observables
+    }
+}
+
+impl<G: FnMut() -> f64> Rule<Spins> for Dynamics<G> {
+    fn step(&mut self, state: &Spins) -> Result<Spins, MatrixError> {
+        let mut next = state.clone();
+        let observables = self.model.sweep(&mut next, self.update, &mut self.generator)?;
+        self.observables.push(observables);
+        Ok(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Neighborhood,
+        models::spin::{
+            Interaction,
+            Observables,
+            SpinModel,
+            Update,
+        },
+        random::{
+            self,
+            Philox,
+        },
+    };
+    use alloc::vec::Vec;
+
+    #[test]
+    fn ordered_lattice_energy_and_flip_costs() {
+        let ising = SpinModel::ising(1.0).with_field(0.5);
+        let spins = ising.ordered_state(6, 5);
+        let observables = ising.observables(&spins).unwrap();
+        assert_eq!(observables.magnetization, 1.0);
+        assert_eq!(observables.energy, -2.5);
+        assert_eq!(ising.flip_energy(&spins, (2, 3), -1).unwrap(), 9.0);
+        let potts = SpinModel::potts(3, 1.0);
+        assert_eq!(potts.observables(&potts.ordered_state(6, 5)).unwrap().energy, -2.0);
+        assert_eq!(potts.flip_energy(&potts.ordered_state(6, 5), (0, 0), 2).unwrap(), 4.0);
+    }
+
+    #[test]
+    fn updates_order_below_and_disorder_above_the_critical_temperature() {
+        let mut rng = Philox::new(41);
+        for model in [SpinModel::ising(1.0), SpinModel::potts(3, 1.0)] {
+            let critical = model.critical_temperature();
+            for update in [Update::Metropolis, Update::HeatBath, Update::Wolff] {
+                let sweeps = if update == Update::Wolff { 400 } else { 100 };
+                let (cold, hot) = (model.clone().with_temperature(0.5 * critical), model.clone().with_temperature(3.0 * critical));
+                let mut ordered = cold.ordered_state(16, 16);
+                let mut disordered = hot.ordered_state(16, 16);
+                let (mut cold_series, mut hot_series) = (Vec::new(), Vec::new());
+                for _ in 0..sweeps {
+                    cold_series.push(cold.sweep(&mut ordered, update, || random::unit_f64(&mut rng)).unwrap());
+                    hot_series.push(hot.sweep(&mut disordered, update, || random::unit_f64(&mut rng)).unwrap());
+                }
+                let mean = |series: &[Observables]| series[sweeps / 2..].iter().map(|observables| observables.magnetization.abs()).sum::<f64>() / (sweeps / 2) as f64;
+                assert!(mean(&cold_series) > 0.8, "{model:?} {update:?} {}", mean(&cold_series));
+                assert!(mean(&hot_series) < 0.3, "{model:?} {update:?} {}", mean(&hot_series));
+                assert!(cold_series.last().unwrap().energy < hot_series.last().unwrap().energy);
+                assert!(hot.susceptibility(&hot_series[sweeps / 2..], 256) > 0.0);
+            }
+        }
+        let mut spins = SpinModel::ising(1.0).random_state(8, 8, || random::unit_f64(&mut rng));
+        assert!(spins.get_data_ref().iter().all(|spin| *spin == 1 || *spin == -1));
+        let field = SpinModel::ising(1.0).with_field(10.0);
+        for update in [Update::HeatBath, Update::Metropolis, Update::HeatBath, Update::Metropolis] {
+            field.sweep(&mut spins, update, || random::unit_f64(&mut rng)).unwrap();
+        }
+        assert!(field.observables(&spins).unwrap().magnetization > 0.9);
+    }
+
+    #[test]
+    fn rejects_spins_outside_of_the_states() {
+        let potts = SpinModel::potts(3, 1.0);
+        let mut spins = SpinModel::ising(1.0).ordered_state(4, 4);
+        spins.get_data_mut_ref()[0] = -1;
+        assert!(potts.observables(&spins).is_err());
+        assert!(potts.sweep(&mut spins, Update::Metropolis, || 0.5).is_err());
+        assert!(SpinModel::ising(1.0).with_neighborhood(Neighborhood::Margolus, 1).observables(&spins).is_err());
+        assert!(SpinModel::ising(1.0).with_neighborhood(Neighborhood::Margolus, 1).flip_energy(&spins, (1, 1), 1).is_err());
+        spins.get_data_mut_ref()[0] = 0;
+        assert!(SpinModel::ising(1.0).observables(&spins).is_err());
+        assert_eq!(SpinModel::potts(200, 1.0).get_interaction(), Interaction::Potts(127));
+        assert_eq!(SpinModel::potts(200, 1.0).states().len(), 127);
+    }
+}
@@ -0,0 +1,332 @@
+//!   Wave equation, d²u/dt² = c² ∇²u + s, stepped by the leapfrog scheme
+//!
+//!   u(t + dt) = 2u(t) - u(t - dt) + (c·dt/dx)² ∇²u(t) + dt² s(t)
+//!
+//!   which needs the current and the previous displacements, held in the two channels of an
+//!   `NChannelMatrix`. The scheme is stable while the Courant number c·dt/dx stays below its limit,
+//!   1/√2 for the five-point stencil. The lattice wraps around unless the waves are absorbed at its
+//!   edges, by a damping sponge layer or by Mur's first order condition.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    MatrixImageBuilder,
+    Neighborhood,
+    n_channel::NChannelMatrix,
+    palette::Palette,
+    simulation::Rule,
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+    error::MatrixError,
+};
+use super::laplace_scale;
+
+type Atom = LatticeElement<f32>;
+
+/// Current displacements in the first channel and those of the previous time step in the second.
+pub type WaveState = NChannelMatrix<Atom, 2>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// Waves leaving through an edge come back through the opposite one.
+    Periodic,
+    /// Waves are damped within the given number of points from the edges, the damping rate growing
+    /// quadratically from 0 to `strength` at the edges.
+    Sponge {
+        width: usize,
+        strength: f32,
+    },
+    /// Mur's first order absorbing condition, exact for waves hitting the edges head on.
+    Mur,
+}
+
+/// Oscillating forcing term, amplitude · sin(2π · frequency · t + phase), applied at its points.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Source {
+    points: Vec<(u32, u32)>,
+    amplitude: f32,
+    frequency: f32,
+    phase: f32,
+}
+
+impl Source {
+    pub fn point(point: (u32, u32), amplitude: f32, frequency: f32) -> Self {
+        Source {
+            points: vec![point],
+            amplitude,
+            frequency,
+            phase: 0.0,
+        }
+    }
+    /// Source along the segment between two points, both included, emitting plane-like wavefronts.
+    pub fn line(from: (u32, u32), to: (u32, u32), amplitude: f32, frequency: f32) -> Self {
+        let (dx, dy) = (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64);
+        let steps = dx.abs().max(dy.abs()).max(1);
+        let points = (0..=steps)
+            .map(|step| {
+                let rounded = |difference: i64| (2 * difference * step + steps).div_euclid(2 * steps);
+                ((from.0 as i64 + rounded(dx)) as u32, (from.1 as i64 + rounded(dy)) as u32)
+            })
+            .collect();
+        Source {
+            points,
+            amplitude,
+            frequency,
+            phase: 0.0,
+        }
+    }
+    pub fn with_phase(mut self, phase: f32) -> Self {
+        self.phase = phase;
+        self
+    }
+    pub fn get_points_ref(&self) -> &Vec<(u32, u32)> {
+        &self.points
+    }
+    pub fn value(&self, time: f32) -> f32 {
+        self.amplitude * (core::f32::consts::TAU * self.frequency * time + self.phase).sin()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wave {
+    speed: f32,
+    speed_field: Option<MatrixImage<Atom>>,
+    time_step: f32,
+    grid_spacing: f32,
+    time: f32,
+    boundary: Boundary,
+    sources: Vec<Source>,
+    hood_type: Neighborhood,
+    hood_size: usize,
+    palette: Palette,
+}
+
+impl Wave {
+    /// Waves of uniform speed with the given time step and grid spacing, over a periodic lattice
+    /// and the five-point stencil, drawn from blue through white to red.
+    pub fn new(speed: f32, time_step: f32, grid_spacing: f32) -> Self {
+        Wave {
+            speed,
+            speed_field: None,
+            time_step,
+            grid_spacing,
+            time: 0.0,
+            boundary: Boundary::Periodic,
+            sources: Vec::new(),
+            hood_type: Neighborhood::VonNeumann,
+            hood_size: 1,
+            palette: Palette::diverging(Rgba([0, 64, 255, 255]), Rgba([255, 255, 255, 255]), Rgba([255, 32, 0, 255]), 255),
+        }
+    }
+    /// Speed at every point of the lattice, e.g. slower in a lens or a denser medium, in place of
+    /// the uniform speed.
+    pub fn with_speed_field(mut self, speed_field: MatrixImage<Atom>) -> Self {
+        self.speed_field = Some(speed_field);
+        self
+    }
+    pub fn with_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+    pub fn with_source(mut self, source: Source) -> Self {
+        self.sources.push(source);
+        self
+    }
+    /// Neighborhood over which the Laplacian is computed, scaled by `Neighborhood::laplace_scale`.
+    pub fn with_stencil(mut self, hood_type: Neighborhood, hood_size: usize) -> Self {
+        self.hood_type = hood_type;
+        self.hood_size = hood_size;
+        self
+    }
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+    pub fn get_time(&self) -> f32 {
+        self.time
+    }
+    pub fn get_time_step(&self) -> f32 {
+        self.time_step
+    }
+    pub fn get_boundary(&self) -> Boundary {
+        self.boundary
+    }
+    pub fn get_sources_ref(&self) -> &Vec<Source> {
+        &self.sources
+    }
+    /// c·dt/dx with the largest speed of the lattice.
+    pub fn courant_number(&self) -> f32 {
+        let speed = match &self.speed_field {
+            Some(field) => field.get_data_ref().iter().map(|speed| speed.0).fold(0.0, f32::max),
+            None => self.speed,
+        };
+        speed * self.time_step / self.grid_spacing
+    }
+    /// Largest Courant number of the leapfrog scheme, √(2 · laplace_scale / neighbors).
+    pub fn courant_limit(&self) -> f32 {
+        let neighbors = self.hood_type.length(self.hood_size).max(2) - 1;
+        (2.0 * self.hood_type.laplace_scale(self.hood_size).unwrap_or(0) as f32 / neighbors as f32).sqrt()
+    }
+    pub fn is_stable(&self) -> bool {
+        self.courant_number() <= self.courant_limit()
+    }
+    /// Lattice at rest.
+    pub fn initial_state(width: usize, height: usize) -> Result<WaveState, MatrixError> {
+        let rest = MatrixImageBuilder::init()
+            .with_initial_value(LatticeElement(0.0))
+            .with_height_and_width(height, width)
+            .build();
+        NChannelMatrix::try_from([rest.clone(), rest])
+    }
+    /// Adds a Gaussian bump of the given amplitude and width to both the current and the previous
+    /// displacements, releasing it at rest, wrapping around the edges.
+    pub fn add_pulse(state: &mut WaveState, center: (u32, u32), width: f32, amplitude: f32) -> Result<(), MatrixError> {
+        let (lattice_width, lattice_height) = (*state.get_width() as i64, *state.get_height() as i64);
+        for channel in state.get_data_mut_ref() {
+            for point in 0..(channel.get_width()*channel.get_height()) {
+                let (x, y) = channel.into_2d_point(point)?;
+                let distance = |position: u32, center: u32, side: i64| {
+                    let offset = (position as i64 - center as i64).rem_euclid(side);
+                    offset.min(side - offset) as f32
+                };
+                let (dx, dy) = (distance(x, center.0, lattice_width), distance(y, center.1, lattice_height));
+                let value = channel.get_absolute_point_data(point).0 + amplitude * (-(dx * dx + dy * dy) / (2.0 * width * width)).exp();
+                channel.edit_point((x, y), value)?;
+            }
+        }
+        Ok(())
+    }
+    fn speed_at(&self, point: usize) -> f32 {
+        self.speed_field.as_ref().map_or(self.speed, |field| field.get_absolute_point_data(point).0)
+    }
+    /// Damping rate of the sponge layer at a point.
+    fn damping(&self, (x, y): (u32, u32), width: usize, height: usize) -> f32 {
+        let Boundary::Sponge { width: layer, strength } = self.boundary else {
+            return 0.0;
+        };
+        let edge = (x as usize).min(y as usize).min(width - 1 - x as usize).min(height - 1 - y as usize);
+        if edge >= layer {
+            return 0.0;
+        }
+        let depth = (layer - edge) as f32 / layer as f32;
+        strength * depth * depth
+    }
+    /// Advances the displacements by one time step, failing with `MatrixError::Unstable` beyond the
+    /// Courant limit.
+    pub fn next_state(&mut self, state: &WaveState) -> Result<WaveState, MatrixError> {
+        if !self.is_stable() {
+            return Err(MatrixError::Unstable { number: self.courant_number() as f64, limit: self.courant_limit() as f64 });
+        }
+        let [current, previous] = state.get_data_ref();
+        let (width, height) = (current.get_width(), current.get_height());
+        if let Some(field) = &self.speed_field {
+            let found = (field.get_width(), field.get_height());
+            if found != (width, height) {
+                return Err(MatrixError::DimensionMismatch { expected: (width, height), found });
+            }
+        }
+        let scale = laplace_scale(self.hood_type, self.hood_size)?.max(1) as f32;
+        let mut forcing = vec![0.0; width * height];
+        for source in &self.sources {
+            let value = source.value(self.time);
+            for point in &source.points {
+                forcing[current.into_absolute_point(*point)?] += value;
+            }
+        }
+        let mut next = current.clone();
+        for (point, forcing) in forcing.iter().enumerate() {
+            let center = current.into_2d_point(point)?;
+            let courant = self.speed_at(point) * self.time_step / self.grid_spacing;
+            let laplace = current.laplace_operator(center, self.hood_size, self.hood_type)?.0 / scale;
+            let damping = self.damping(center, width, height) * self.time_step / 2.0;
+            let (value, last) = (current.get_absolute_point_data(point).0, previous.get_absolute_point_data(point).0);
+            let value = (2.0 * value - (1.0 - damping) * last + courant * courant * laplace + self.time_step * self.time_step * forcing) / (1.0 + damping);
+            next.edit_point(center, value)?;
+        }
+        if self.boundary == Boundary::Mur && width > 1 && height > 1 {
+            self.absorb(current, &mut next)?;
+        }
+        self.time += self.time_step;
+        NChannelMatrix::try_from([next, current.clone()])
+    }
+    /// Overwrites the edges with Mur's condition, u'(edge) = u(inner) + k (u'(inner) - u(edge)),
+    /// k = (c·dt - dx) / (c·dt + dx), the inner point being the next one into the lattice.
+    fn absorb(&self, current: &MatrixImage<Atom>, next: &mut MatrixImage<Atom>) -> Result<(), MatrixError> {
+        let (width, height) = (current.get_width() as u32, current.get_height() as u32);
+        let edges = (0..height)
+            .flat_map(|y| [((0, y), (1, y)), ((width - 1, y), (width - 2, y))])
+            .chain((0..width).flat_map(|x| [((x, 0), (x, 1)), ((x, height - 1), (x, height - 2))]));
+        for (edge, inner) in edges {
+            let speed = self.speed_at(current.into_absolute_point(edge)?) * self.time_step;
+            let factor = (speed - self.grid_spacing) / (speed + self.grid_spacing);
+            let value = current.get_point_value(inner)?.0 + factor * (next.get_point_value(inner)?.0 - current.get_point_value(edge)?.0);
+            next.edit_point(edge, value)?;
+        }
+        Ok(())
+    }
+    /// Draws the current displacements with the diverging palette, from -amplitude to amplitude.
+    pub fn draw(&self, state: &WaveState, amplitude: f32) -> Result<RgbaImage, MatrixError> {
+        self.palette.draw_range(&state.get_data_ref()[0], -amplitude, amplitude)
+    }
+}
+
+impl Rule<WaveState> for Wave {
+    fn step(&mut self, state: &WaveState) -> Result<WaveState, MatrixError> {
+        self.next_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::wave::{
+            Boundary,
+            Source,
+            Wave,
+            WaveState,
+        },
+        error::MatrixError,
+    };
+
+    fn energy(state: &WaveState) -> f32 {
+        state.get_data_ref()[0].get_data_ref().iter().map(|value| value.0 * value.0).sum()
+    }
+
+    #[test]
+    fn leapfrog_refuses_courant_numbers_beyond_the_limit() {
+        let mut wave = Wave::new(1.0, 0.8, 1.0);
+        assert!((wave.courant_limit() - core::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        let state = Wave::initial_state(8, 8).unwrap();
+        assert!(matches!(wave.next_state(&state), Err(MatrixError::Unstable { .. })));
+        let line = Source::line((1, 6), (7, 2), 1.0, 0.1);
+        assert_eq!(line.get_points_ref().first(), Some(&(1, 6)));
+        assert_eq!(line.get_points_ref().last(), Some(&(7, 2)));
+        assert_eq!(line.get_points_ref().len(), 7);
+    }
+
+    #[test]
+    fn absorbing_boundaries_drain_a_pulse() {
+        let mut state = Wave::initial_state(48, 48).unwrap();
+        Wave::add_pulse(&mut state, (24, 24), 2.0, 1.0).unwrap();
+        let initial = energy(&state);
+        let mut remaining = [0.0; 3];
+        for (index, boundary) in [Boundary::Periodic, Boundary::Sponge { width: 12, strength: 1.0 }, Boundary::Mur].into_iter().enumerate() {
+            let mut wave = Wave::new(1.0, 0.5, 1.0).with_boundary(boundary);
+            let mut next = state.clone();
+            for _ in 0..200 {
+                next = wave.next_state(&next).unwrap();
+            }
+            assert!(next.get_data_ref()[0].get_data_ref().iter().all(|value| value.0.is_finite()));
+            remaining[index] = energy(&next) / initial;
+        }
+        assert!(remaining[0] > 0.1, "{remaining:?}");
+        assert!(remaining[1] < remaining[0] / 10.0, "{remaining:?}");
+        assert!(remaining[2] < remaining[0] / 10.0, "{remaining:?}");
+    }
+}
@@ -1,5 +1,20 @@
-use core::ops::Mul;
-use crate::MatrixImage;
+use core::{
+    fmt::Debug,
+    ops::{
+        Div,
+        Mul,
+        Add,
+        Sub,
+    },
+};
+use crate::{
+    error::MatrixError,
+    MatrixImage,
+    traits::{
+        self,
+        Matrix,
+    },
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct NChannelMatrix<T, const N: usize>
@@ -8,4 +23,41 @@ pub struct NChannelMatrix<T, const N: usize>
     height: usize,
     width: usize,
     data: [MatrixImage<T>; N],
-}
\ No newline at end of file
+}
+
+impl<T: Clone + Mul<Output=T>, const N: usize> NChannelMatrix<T, N> {
+    pub fn get_height(&self) -> &usize {
+        &self.height
+    }
+    pub fn get_width(&self) -> &usize {
+        &self.width
+    }
+    pub fn get_data(self) -> [MatrixImage<T>; N] {
+        self.data
+    }
+    pub fn get_data_ref(&self) -> &[MatrixImage<T>; N] {
+        &self.data
+    }
+    pub fn get_data_mut_ref(&mut self) -> &mut [MatrixImage<T>; N] {
+        &mut self.data
+    }
+}
+
+impl<T: Clone + Debug + Default + traits::Max + Add<Output=T> + Div<Output=T> + Sub<Output=T> + Mul<Output=T> + PartialOrd, const N: usize> TryFrom<[MatrixImage<T>; N]> for NChannelMatrix<T, N> {
+    type Error = MatrixError;
+    /// Fails with `MatrixError::DimensionMismatch` when the channels don't share their width and height.
+    fn try_from(value: [MatrixImage<T>; N]) -> Result<Self, Self::Error> {
+        let (width, height) = value.first().map_or((0, 0), |matrix| (matrix.get_width(), matrix.get_height()));
+        for matrix in &value {
+            let found = (matrix.get_width(), matrix.get_height());
+            if found != (width, height) {
+                return Err(MatrixError::DimensionMismatch { expected: (width, height), found });
+            }
+        }
+        Ok(NChannelMatrix {
+            height,
+            width,
+            data: value
+        })
+    }
+}
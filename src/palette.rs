@@ -0,0 +1,89 @@
+//!   Rendering of discrete cell states, where each state of a `MatrixImage<u8>` is an index into
+//!   a list of colors.
+
+use alloc::vec::Vec;
+use image::{
+    Rgba,
+    RgbaImage,
+};
+use crate::{
+    MatrixImage,
+    error,
+    traits::{
+        Matrix,
+        LatticeElement,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    colors: Vec<Rgba<u8>>,
+}
+
+impl Palette {
+    pub fn new(colors: Vec<Rgba<u8>>) -> Self {
+        Palette {
+            colors,
+        }
+    }
+    /// Linear interpolation from the first to the last color, over the given number of states.
+    pub fn gradient(from: Rgba<u8>, to: Rgba<u8>, states: usize) -> Self {
+        let steps = states.max(2) - 1;
+        let colors = (0..states)
+            .map(|state| {
+                let mut color = [0_u8; 4];
+                for (channel, value) in color.iter_mut().enumerate() {
+                    let (from, to) = (from.0[channel] as usize, to.0[channel] as usize);
+                    *value = ((from * (steps - state.min(steps)) + to * state.min(steps)) / steps) as u8;
+                }
+                Rgba(color)
+            })
+            .collect();
+        Palette {
+            colors,
+        }
+    }
+    /// Two gradients meeting at the middle color, to draw signed fields with `draw_range` over a
+    /// range centered on zero.
+    pub fn diverging(negative: Rgba<u8>, zero: Rgba<u8>, positive: Rgba<u8>, states: usize) -> Self {
+        let half = states / 2 + 1;
+        let mut colors = Self::gradient(negative, zero, half).colors;
+        colors.extend(Self::gradient(zero, positive, states + 1 - half).colors.into_iter().skip(1));
+        Palette {
+            colors,
+        }
+    }
+    pub fn get_colors_ref(&self) -> &Vec<Rgba<u8>> {
+        &self.colors
+    }
+    /// States without a color of their own are drawn with the last color of the palette,
+    /// and with transparent black when the palette is empty.
+    pub fn get_color(&self, state: u8) -> Rgba<u8> {
+        self.colors
+            .get(state as usize)
+            .or(self.colors.last())
+            .copied()
+            .unwrap_or(Rgba([0, 0, 0, 0]))
+    }
+    pub fn draw(&self, matrix: &MatrixImage<u8>) -> Result<RgbaImage, error::MatrixError> {
+        let mut image = RgbaImage::new(matrix.get_width().try_into()?, matrix.get_height().try_into()?);
+        for point in 0..(matrix.get_width()*matrix.get_height()) {
+            let (x,y) = matrix.into_2d_point(point)?;
+            image.put_pixel(x, y, self.get_color(matrix.get_absolute_point_data(point)));
+        }
+        Ok(image)
+    }
+    /// Draws a continuous field by spreading the values from `min` to `max` linearly over the
+    /// colors of the palette, values outside of the range taking the first or last color.
+    pub fn draw_range(&self, matrix: &MatrixImage<LatticeElement<f32>>, min: f32, max: f32) -> Result<RgbaImage, error::MatrixError> {
+        let last = (self.colors.len().max(1) - 1).min(u8::MAX as usize);
+        let mut image = RgbaImage::new(matrix.get_width().try_into()?, matrix.get_height().try_into()?);
+        for point in 0..(matrix.get_width()*matrix.get_height()) {
+            let (x,y) = matrix.into_2d_point(point)?;
+            let position = (matrix.get_absolute_point_data(point).0 - min) / (max - min);
+            let state = (position * last as f32).round().clamp(0.0, last as f32);
+            image.put_pixel(x, y, self.get_color(state as u8));
+        }
+        Ok(image)
+    }
+}
@@ -0,0 +1,294 @@
+//!   Seeded randomness for stochastic rules, built on `rand_core` so that any of its generators can
+//!   be used, and `no_std` like the rest of the crate.
+//!
+//!   `Philox` is the counter-based Philox4x32-10 generator of Salmon et al., whose output is a
+//!   keyed function of a 128 bit counter. The key holds the seed and the counter a stream id along
+//!   with the position within the stream, so that every cell of a lattice at every step draws from
+//!   its own stream, `Philox::for_cell`, independently of the order in which the cells are visited.
+//!   Serial and parallel runs of a rule drawing from these streams give the same lattice for the
+//!   same seed.
+
+use alloc::vec::Vec;
+#[cfg(feature = "rayon")]
+use rayon::iter::{
+    IndexedParallelIterator,
+    IntoParallelRefMutIterator,
+    ParallelIterator,
+};
+pub use rand_core::{
+    RngCore,
+    SeedableRng,
+};
+use rand_core::impls;
+use crate::{
+    MatrixImage,
+    schedule::Schedule,
+    simulation::Rule,
+    traits::Matrix,
+    error::MatrixError,
+};
+
+const MULTIPLIERS: [u32; 2] = [0xD251_1F53, 0xCD9E_8D57];
+const WEYL: [u32; 2] = [0x9E37_79B9, 0xBB67_AE85];
+const ROUNDS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Philox {
+    key: [u32; 2],
+    counter: [u32; 4],
+    block: [u32; 4],
+    position: usize,
+}
+
+impl Philox {
+    /// Sequential generator of the given seed, on a stream no lattice cell draws from.
+    pub fn new(seed: u64) -> Self {
+        Self::with_counter(seed, [0, u32::MAX, u32::MAX, u32::MAX])
+    }
+    /// Stream of the cell at the given absolute index at the given step, steps wrapping around
+    /// after 2³² - 1.
+    pub fn for_cell(seed: u64, step: u64, index: usize) -> Self {
+        let index = index as u64;
+        Self::with_counter(seed, [0, index as u32, (index >> 32) as u32, (step % u32::MAX as u64) as u32])
+    }
+    fn with_counter(seed: u64, counter: [u32; 4]) -> Self {
+        Philox {
+            key: [seed as u32, (seed >> 32) as u32],
+            counter,
+            block: [0; 4],
+            position: 4,
+        }
+    }
+    /// Philox4x32-10 bijection of a counter under a key.
+    pub fn block(key: [u32; 2], counter: [u32; 4]) -> [u32; 4] {
+        let (mut key, mut counter) = (key, counter);
+        for round in 0..ROUNDS {
+            if round > 0 {
+                key = [key[0].wrapping_add(WEYL[0]), key[1].wrapping_add(WEYL[1])];
+            }
+            let first = MULTIPLIERS[0] as u64 * counter[0] as u64;
+            let second = MULTIPLIERS[1] as u64 * counter[2] as u64;
+            counter = [
+                (second >> 32) as u32 ^ counter[1] ^ key[0],
+                second as u32,
+                (first >> 32) as u32 ^ counter[3] ^ key[1],
+                first as u32,
+            ];
+        }
+        counter
+    }
+}
+
+impl RngCore for Philox {
+    fn next_u32(&mut self) -> u32 {
+        if self.position == 4 {
+            self.block = Self::block(self.key, self.counter);
+            self.counter[0] = self.counter[0].wrapping_add(1);
+            self.position = 0;
+        }
+        self.position += 1;
+        self.block[self.position - 1]
+    }
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Philox {
+    type Seed = [u8; 8];
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::new(u64::from_le_bytes(seed))
+    }
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+/// Uniform number in [0, 1) with 53 random bits.
+pub fn unit_f64(rng: &mut impl RngCore) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// Uniform number in [0, 1) with 24 random bits.
+pub fn unit_f32(rng: &mut impl RngCore) -> f32 {
+    (rng.next_u32() >> 8) as f32 / (1_u32 << 24) as f32
+}
+
+/// Closure returning uniform numbers in [0, 1) from a generator, for the functions of the crate
+/// taking one, e.g. `GrayScott::seed_squares` or `SpinModel::sweep`.
+pub fn generator<R: RngCore>(mut rng: R) -> impl FnMut() -> f64 {
+    move || unit_f64(&mut rng)
+}
+
+/// Applies a stochastic update to every cell of a lattice, the update drawing from the stream of
+/// its cell at the given step. With the `rayon` feature the cells are updated in parallel, with the
+/// same result.
+#[cfg(not(feature = "rayon"))]
+pub fn map_cells<T, F>(matrix: &MatrixImage<T>, seed: u64, step: u64, update: F) -> MatrixImage<T>
+where
+    T: Clone,
+    F: Fn(&MatrixImage<T>, (u32, u32), &mut Philox) -> T,
+{
+    let width = matrix.get_width().max(1);
+    let mut next = matrix.clone();
+    for (index, value) in next.get_data_mut_ref().iter_mut().enumerate() {
+        let point = ((index % width) as u32, (index / width) as u32);
+        *value = update(matrix, point, &mut Philox::for_cell(seed, step, index));
+    }
+    next
+}
+
+/// Applies a stochastic update to every cell of a lattice, the update drawing from the stream of
+/// its cell at the given step. With the `rayon` feature the cells are updated in parallel, with the
+/// same result.
+#[cfg(feature = "rayon")]
+pub fn map_cells<T, F>(matrix: &MatrixImage<T>, seed: u64, step: u64, update: F) -> MatrixImage<T>
+where
+    T: Clone + Send + Sync,
+    F: Fn(&MatrixImage<T>, (u32, u32), &mut Philox) -> T + Sync,
+{
+    let width = matrix.get_width().max(1);
+    let mut next = matrix.clone();
+    next.get_data_mut_ref().par_iter_mut().enumerate().for_each(|(index, value)| {
+        let point = ((index % width) as u32, (index / width) as u32);
+        *value = update(matrix, point, &mut Philox::for_cell(seed, step, index));
+    });
+    next
+}
+
+/// Stochastic rule, updating every cell from the lattice and the stream of the cell at the current
+/// step, so that a run is reproduced by its seed. Cells are updated synchronously from the previous
+/// lattice unless another `Schedule` is given.
+#[derive(Clone, Debug)]
+pub struct Stochastic<F> {
+    seed: u64,
+    step: u64,
+    schedule: Schedule,
+    update: F,
+}
+
+impl<F> Stochastic<F> {
+    pub fn new(seed: u64, update: F) -> Self {
+        Stochastic {
+            seed,
+            step: 0,
+            schedule: Schedule::Synchronous,
+            update,
+        }
+    }
+    pub fn with_schedule(mut self, schedule: Schedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+    pub fn get_schedule(&self) -> Schedule {
+        self.schedule
+    }
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+    /// Number of steps taken, the step whose streams the next one draws from.
+    pub fn get_step(&self) -> u64 {
+        self.step
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+impl<T, F> Rule<MatrixImage<T>> for Stochastic<F>
+where
+    T: Clone,
+    F: Fn(&MatrixImage<T>, (u32, u32), &mut Philox) -> T,
+{
+    fn step(&mut self, state: &MatrixImage<T>) -> Result<MatrixImage<T>, MatrixError> {
+        let next = if self.schedule.is_synchronous() {
+            map_cells(state, self.seed, self.step, &self.update)
+        } else {
+            self.schedule.apply(state, self.seed, self.step, &self.update)
+        };
+        self.step += 1;
+        Ok(next)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, F> Rule<MatrixImage<T>> for Stochastic<F>
+where
+    T: Clone + Send + Sync,
+    F: Fn(&MatrixImage<T>, (u32, u32), &mut Philox) -> T + Sync,
+{
+    fn step(&mut self, state: &MatrixImage<T>) -> Result<MatrixImage<T>, MatrixError> {
+        let next = if self.schedule.is_synchronous() {
+            map_cells(state, self.seed, self.step, &self.update)
+        } else {
+            self.schedule.apply(state, self.seed, self.step, &self.update)
+        };
+        self.step += 1;
+        Ok(next)
+    }
+}
+
+/// Seeded permutation of `0..count` by Fisher-Yates shuffling.
+pub fn permutation(count: usize, rng: &mut impl RngCore) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..count).collect();
+    for last in (1..count).rev() {
+        let other = (unit_f64(rng) * (last + 1) as f64) as usize;
+        order.swap(last, other.min(last));
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        random::{
+            self,
+            Philox,
+            RngCore,
+            Stochastic,
+        },
+        simulation::Rule,
+        traits::Matrix,
+    };
+
+    #[test]
+    fn philox_matches_the_known_answers() {
+        assert_eq!(Philox::block([0, 0], [0, 0, 0, 0]), [0x6627_e8d5, 0xe169_c58d, 0xbc57_ac4c, 0x9b00_dbd8]);
+        assert_eq!(Philox::block([u32::MAX; 2], [u32::MAX; 4]), [0x408f_276d, 0x41c8_3b0e, 0xa20b_c7c6, 0x6d54_51fd]);
+        assert_eq!(
+            Philox::block([0xa409_3822, 0x299f_31d0], [0x243f_6a88, 0x85a3_08d3, 0x1319_8a2e, 0x0370_7344]),
+            [0xd16c_fe09, 0x94fd_cceb, 0x5001_e420, 0x2412_6ea1],
+        );
+        let mut rng = Philox::for_cell(0, 0, 0);
+        assert_eq!([rng.next_u32(), rng.next_u32(), rng.next_u32(), rng.next_u32()], [0x6627_e8d5, 0xe169_c58d, 0xbc57_ac4c, 0x9b00_dbd8]);
+        assert_ne!(rng.next_u32(), 0x6627_e8d5);
+    }
+
+    #[test]
+    fn cell_streams_do_not_depend_on_the_visiting_order() {
+        let matrix: MatrixImage<u32> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(7, 9).build();
+        let mut rule = Stochastic::new(42, |matrix: &MatrixImage<u32>, point, rng: &mut Philox| matrix.get_point_value(point).unwrap() ^ rng.next_u32());
+        let first = rule.step(&matrix).unwrap();
+        let second = rule.step(&first).unwrap();
+        let mut expected = first.clone();
+        for index in random::permutation(63, &mut Philox::new(1)) {
+            let point = ((index % 9) as u32, (index / 9) as u32);
+            let value = first.get_point_value(point).unwrap() ^ Philox::for_cell(42, 1, index).next_u32();
+            expected.edit_point(point, value).unwrap();
+        }
+        assert_eq!(second, expected);
+        assert_ne!(first, second);
+        let seeded: MatrixImage<u32> = MatrixImageBuilder::init().with_height_and_width(7, 9).with_seeded_generator(42, Philox::next_u32).build();
+        assert_eq!(seeded, first);
+        let mut order = random::permutation(100, &mut Philox::new(5));
+        order.sort();
+        assert!(order.into_iter().eq(0..100));
+    }
+}
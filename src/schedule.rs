@@ -0,0 +1,166 @@
+//!   Update schedules of stochastic rules. A schedule decides in which order, and how many times,
+//!   the cells of a lattice are updated during one step, and which lattice the updates read from:
+//!   synchronous updates read the previous lattice, asynchronous ones the lattice as updated so far.
+//!   The update of a cell is the closure of `random::Stochastic`, given the lattice to read, the
+//!   cell and its random stream, so that the same rule runs under every schedule.
+//!
+//!   Cells draw from the stream of their absolute index at the step, except under
+//!   `RandomIndependent` where a cell may be picked more than once, and the k-th update of the step
+//!   draws from stream k. The order of the cells is drawn from a stream no cell uses.
+
+use alloc::vec::Vec;
+use crate::{
+    MatrixImage,
+    random::{
+        self,
+        Philox,
+    },
+    traits::Matrix,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Schedule {
+    /// Every cell is updated from the previous lattice.
+    #[default]
+    Synchronous,
+    /// Every cell is updated once, one after the other in a random permutation drawn at every step.
+    RandomSequential,
+    /// As many updates as cells, each of a cell picked uniformly at random, so that some cells are
+    /// updated several times and others not at all.
+    RandomIndependent,
+    /// Cells whose coordinates have an even sum are updated synchronously, then the odd ones from
+    /// the lattice updated by the even ones.
+    Checkerboard,
+    /// Rows are updated one after the other from the top, every row synchronously from the lattice
+    /// updated by the rows above it.
+    Rows,
+    /// Columns are updated one after the other from the left, as `Rows` does with rows.
+    Columns,
+}
+
+impl Schedule {
+    pub fn is_synchronous(self) -> bool {
+        self == Schedule::Synchronous
+    }
+    /// Applies one step of the update to the lattice under the schedule, the cells drawing from
+    /// their streams of the given seed at the given step.
+    pub fn apply<T, F>(self, matrix: &MatrixImage<T>, seed: u64, step: u64, update: &F) -> MatrixImage<T>
+    where
+        T: Clone,
+        F: Fn(&MatrixImage<T>, (u32, u32), &mut Philox) -> T,
+    {
+        let (width, height) = (matrix.get_width(), matrix.get_height());
+        let cells = width * height;
+        let mut next = matrix.clone();
+        let mut order = Philox::for_cell(seed, step, usize::MAX);
+        match self {
+            Schedule::Synchronous => update_together(&mut next, 0..cells, seed, step, update),
+            Schedule::RandomSequential => {
+                for index in random::permutation(cells, &mut order) {
+                    let value = update(&next, point(index, width), &mut Philox::for_cell(seed, step, index));
+                    next.get_data_mut_ref()[index] = value;
+                }
+            },
+            Schedule::RandomIndependent => {
+                for stream in 0..cells {
+                    let index = ((random::unit_f64(&mut order) * cells as f64) as usize).min(cells - 1);
+                    let value = update(&next, point(index, width), &mut Philox::for_cell(seed, step, stream));
+                    next.get_data_mut_ref()[index] = value;
+                }
+            },
+            Schedule::Checkerboard => {
+                for parity in [0, 1] {
+                    let cells = (0..cells).filter(|index| (index % width + index / width) % 2 == parity);
+                    update_together(&mut next, cells, seed, step, update);
+                }
+            },
+            Schedule::Rows => {
+                for y in 0..height {
+                    update_together(&mut next, y * width..(y + 1) * width, seed, step, update);
+                }
+            },
+            Schedule::Columns => {
+                for x in 0..width {
+                    update_together(&mut next, (0..height).map(|y| y * width + x), seed, step, update);
+                }
+            },
+        }
+        next
+    }
+}
+
+fn point(index: usize, width: usize) -> (u32, u32) {
+    ((index % width) as u32, (index / width) as u32)
+}
+
+/// Updates the given cells synchronously, all of them reading the lattice as it was before.
+fn update_together<T, F>(matrix: &mut MatrixImage<T>, cells: impl Iterator<Item = usize>, seed: u64, step: u64, update: &F)
+where
+    T: Clone,
+    F: Fn(&MatrixImage<T>, (u32, u32), &mut Philox) -> T,
+{
+    let width = matrix.get_width();
+    let values: Vec<(usize, T)> = cells
+        .map(|index| (index, update(matrix, point(index, width), &mut Philox::for_cell(seed, step, index))))
+        .collect();
+    for (index, value) in values {
+        matrix.get_data_mut_ref()[index] = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        MatrixImage,
+        MatrixImageBuilder,
+        random::{
+            self,
+            Philox,
+            RngCore,
+            Stochastic,
+        },
+        schedule::Schedule,
+        simulation::Rule,
+        traits::Matrix,
+    };
+
+    /// A cell takes the value of its left neighbor when it is larger.
+    fn spread(matrix: &MatrixImage<u32>, point: (u32, u32), _: &mut Philox) -> u32 {
+        let left = matrix.get_point_value(matrix.offset_point(point, (-1, 0))).unwrap();
+        left.max(matrix.get_point_value(point).unwrap())
+    }
+
+    #[test]
+    fn schedules_read_the_lattice_as_updated_so_far() {
+        let mut matrix: MatrixImage<u32> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(2, 8).build();
+        matrix.edit_point((0_u32, 0), 1_u32).unwrap();
+        matrix.edit_point((0_u32, 1), 1_u32).unwrap();
+        let spread_over = |schedule: Schedule| schedule.apply(&matrix, 45, 0, &spread).get_data_ref().iter().sum::<u32>();
+        assert_eq!(spread_over(Schedule::Synchronous), 4);
+        assert_eq!(spread_over(Schedule::Rows), 4);
+        assert_eq!(spread_over(Schedule::Checkerboard), 5);
+        assert_eq!(spread_over(Schedule::Columns), 16);
+        let noise = |matrix: &MatrixImage<u32>, point, rng: &mut Philox| matrix.get_point_value(point).unwrap() ^ rng.next_u32();
+        let mut synchronous = Stochastic::new(45, noise);
+        assert_eq!(Schedule::Synchronous.apply(&matrix, 45, 0, &noise), synchronous.step(&matrix).unwrap());
+    }
+
+    #[test]
+    fn random_schedules_visit_cells_as_drawn() {
+        let matrix: MatrixImage<u32> = MatrixImageBuilder::init().with_initial_value(0).with_height_and_width(10, 10).build();
+        let visit = |matrix: &MatrixImage<u32>, point, _: &mut Philox| matrix.get_point_value(point).unwrap() + 1;
+        let mut sequential = Stochastic::new(45, visit).with_schedule(Schedule::RandomSequential);
+        let once = sequential.step(&matrix).unwrap();
+        assert!(once.get_data_ref().iter().all(|visits| *visits == 1));
+        let mut independent = Stochastic::new(45, visit).with_schedule(Schedule::RandomIndependent);
+        let visits = independent.step(&matrix).unwrap();
+        assert_eq!(visits.get_data_ref().iter().sum::<u32>(), 100);
+        assert!(visits.get_data_ref().contains(&0));
+        assert_eq!(visits, Schedule::RandomIndependent.apply(&matrix, 45, 0, &visit));
+        assert_ne!(visits, Schedule::RandomIndependent.apply(&matrix, 45, 1, &visit));
+        let order = |matrix: &MatrixImage<u32>, _, _: &mut Philox| matrix.get_data_ref().iter().max().unwrap() + 1;
+        let ranks = Schedule::RandomSequential.apply(&matrix, 45, 0, &order);
+        let expected = random::permutation(100, &mut Philox::for_cell(45, 0, usize::MAX));
+        assert!(expected.iter().enumerate().all(|(rank, index)| ranks.get_data_ref()[*index] == rank as u32 + 1));
+    }
+}
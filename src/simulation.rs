@@ -0,0 +1,287 @@
+//!   Generic runner for lattice rules. A `Simulation` repeatedly applies a `Rule` to its state,
+//!   records frames into `FrameSink`s every `frame_interval` steps, notifies observers after
+//!   every step and stops on the first satisfied `StopCondition`.
+
+use alloc::{
+    boxed::Box,
+    string::String,
+    vec::Vec,
+};
+use image::RgbaImage;
+use crate::error::MatrixError;
+
+pub type Render<'a, M> = Box<dyn Fn(&M) -> Result<RgbaImage, MatrixError> + 'a>;
+pub type Equivalence<'a, M> = Box<dyn Fn(&M, &M) -> bool + 'a>;
+pub type StepPredicate<'a, M> = Box<dyn Fn(usize, &M) -> bool + 'a>;
+pub type Observer<'a, M> = Box<dyn FnMut(usize, &M) + 'a>;
+
+/// Transition from one state of the lattice to the next one.
+pub trait Rule<M> {
+    fn step(&mut self, state: &M) -> Result<M, MatrixError>;
+}
+
+impl<M, F> Rule<M> for F
+where
+ F: FnMut(&M) -> Result<M, MatrixError>,
+{
+    fn step(&mut self, state: &M) -> Result<M, MatrixError> {
+        self(state)
+    }
+}
+
+/// Destination of the frames recorded by a `Simulation`.
+pub trait FrameSink<M> {
+    fn record(&mut self, step: usize, state: &M) -> Result<(), MatrixError>;
+}
+
+/// Renders every frame and saves it as `{directory}/{prefix}{step}.png`.
+pub struct PngDirectory<'a, M> {
+    directory: String,
+    prefix: String,
+    render: Render<'a, M>,
+}
+
+impl<'a, M> PngDirectory<'a, M> {
+    pub fn new(directory: impl Into<String>, render: impl Fn(&M) -> Result<RgbaImage, MatrixError> + 'a) -> Self {
+        PngDirectory {
+            directory: directory.into(),
+            prefix: String::from("matrix_"),
+            render: Box::new(render),
+        }
+    }
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+impl<M> FrameSink<M> for PngDirectory<'_, M> {
+    fn record(&mut self, step: usize, state: &M) -> Result<(), MatrixError> {
+        (self.render)(state)?.save(format!("{}/{}{}.png", self.directory, self.prefix, step))?;
+        Ok(())
+    }
+}
+
+/// Keeps a copy of every recorded frame together with its step.
+#[derive(Default)]
+pub struct MemorySink<M> {
+    frames: Vec<(usize, M)>,
+}
+
+impl<M> MemorySink<M> {
+    pub fn new() -> Self {
+        MemorySink {
+            frames: Vec::new(),
+        }
+    }
+    pub fn get_frames_ref(&self) -> &Vec<(usize, M)> {
+        &self.frames
+    }
+    pub fn into_frames(self) -> Vec<(usize, M)> {
+        self.frames
+    }
+}
+
+impl<M: Clone> FrameSink<M> for MemorySink<M> {
+    fn record(&mut self, step: usize, state: &M) -> Result<(), MatrixError> {
+        self.frames.push((step, state.clone()));
+        Ok(())
+    }
+}
+
+/// Hands every recorded frame to a closure.
+pub struct CallbackSink<F>(pub F);
+
+impl<M, F> FrameSink<M> for CallbackSink<F>
+where
+ F: FnMut(usize, &M) -> Result<(), MatrixError>,
+{
+    fn record(&mut self, step: usize, state: &M) -> Result<(), MatrixError> {
+        (self.0)(step, state)
+    }
+}
+
+pub enum StopCondition<'a, M> {
+    /// Stops once the given number of steps has been applied.
+    MaxSteps(usize),
+    /// Stops when the closure reports that the previous and the new state are equivalent.
+    SteadyState(Equivalence<'a, M>),
+    /// Stops when the closure returns true for the step count and the new state.
+    Predicate(StepPredicate<'a, M>),
+}
+
+impl<'a, M> StopCondition<'a, M> {
+    /// Steady state defined as equality between two consecutive states.
+    pub fn steady_state() -> Self
+    where M: PartialEq
+    {
+        Self::SteadyState(Box::new(|previous, next| previous == next))
+    }
+    pub fn predicate(predicate: impl Fn(usize, &M) -> bool + 'a) -> Self {
+        Self::Predicate(Box::new(predicate))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    MaxSteps,
+    SteadyState,
+    Predicate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimulationReport {
+    /// Number of steps applied to the initial state.
+    pub steps: usize,
+    pub reason: StopReason,
+}
+
+pub struct Simulation<'a, M, R: Rule<M>> {
+    state: M,
+    rule: R,
+    step: usize,
+    frame_interval: usize,
+    sinks: Vec<&'a mut dyn FrameSink<M>>,
+    observers: Vec<Observer<'a, M>>,
+    stop_conditions: Vec<StopCondition<'a, M>>,
+}
+
+impl<'a, M, R: Rule<M>> Simulation<'a, M, R> {
+    /// Without any stop condition the simulation runs until the rule returns an error.
+    pub fn new(initial_state: M, rule: R) -> Self {
+        Simulation {
+            state: initial_state,
+            rule,
+            step: 0,
+            frame_interval: 1,
+            sinks: Vec::new(),
+            observers: Vec::new(),
+            stop_conditions: Vec::new(),
+        }
+    }
+    pub fn with_steps(self, steps: usize) -> Self {
+        self.with_stop_condition(StopCondition::MaxSteps(steps))
+    }
+    /// Frames are recorded for the steps that are a multiple of the interval, starting with the initial state.
+    pub fn with_frame_interval(mut self, frame_interval: usize) -> Self {
+        self.frame_interval = frame_interval.max(1);
+        self
+    }
+    pub fn with_sink(mut self, sink: &'a mut dyn FrameSink<M>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+    /// The observer receives the step count and the state after every step.
+    pub fn with_observer(mut self, observer: impl FnMut(usize, &M) + 'a) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+    pub fn with_stop_condition(mut self, condition: StopCondition<'a, M>) -> Self {
+        self.stop_conditions.push(condition);
+        self
+    }
+    pub fn get_state(&self) -> &M {
+        &self.state
+    }
+    pub fn get_step(&self) -> usize {
+        self.step
+    }
+    pub fn into_state(self) -> M {
+        self.state
+    }
+    fn record(&mut self) -> Result<(), MatrixError> {
+        if self.step.is_multiple_of(self.frame_interval) {
+            for sink in self.sinks.iter_mut() {
+                sink.record(self.step, &self.state)?;
+            }
+        }
+        Ok(())
+    }
+    /// Applies the rule until a stop condition is met. Calling `run` again resumes from the current state,
+    /// with the step count carried over.
+    pub fn run(&mut self) -> Result<SimulationReport, MatrixError> {
+        if self.step == 0 {
+            self.record()?;
+        }
+        loop {
+            let max_steps_reached = self.stop_conditions.iter().any(|condition| {
+                matches!(condition, StopCondition::MaxSteps(steps) if self.step >= *steps)
+            });
+            if max_steps_reached {
+                return Ok(SimulationReport { steps: self.step, reason: StopReason::MaxSteps });
+            }
+            let next = self.rule.step(&self.state)?;
+            self.step += 1;
+            for observer in self.observers.iter_mut() {
+                observer(self.step, &next);
+            }
+            let mut reason = None;
+            for condition in &self.stop_conditions {
+                match condition {
+                    StopCondition::SteadyState(is_steady) if is_steady(&self.state, &next) => {
+                        reason = Some(StopReason::SteadyState);
+                    },
+                    StopCondition::Predicate(predicate) if predicate(self.step, &next) => {
+                        reason = Some(StopReason::Predicate);
+                    },
+                    _ => {},
+                }
+                if reason.is_some() {
+                    break;
+                }
+            }
+            self.state = next;
+            self.record()?;
+            if let Some(reason) = reason {
+                return Ok(SimulationReport { steps: self.step, reason });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::{
+        Simulation,
+        StopCondition,
+        StopReason,
+        MemorySink,
+        CallbackSink,
+    };
+    use crate::error::MatrixError;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn frames_and_max_steps() {
+        let mut frames = MemorySink::new();
+        let mut observed = Vec::new();
+        let report = Simulation::new(0_u32, |state: &u32| Ok::<u32, MatrixError>(state + 1))
+            .with_steps(10)
+            .with_frame_interval(4)
+            .with_sink(&mut frames)
+            .with_observer(|step, state| observed.push((step, *state)))
+            .run()
+            .unwrap();
+        assert_eq!(report.steps, 10);
+        assert_eq!(report.reason, StopReason::MaxSteps);
+        assert_eq!(frames.into_frames(), vec![(0, 0), (4, 4), (8, 8)]);
+        assert_eq!(observed.len(), 10);
+    }
+
+    #[test]
+    fn steady_state_and_predicate() {
+        let mut last_frame = None;
+        let mut sink = CallbackSink(|step: usize, state: &u32| { last_frame = Some((step, *state)); Ok(()) });
+        let report = Simulation::new(100_u32, |state: &u32| Ok::<u32, MatrixError>(state / 2))
+            .with_stop_condition(StopCondition::steady_state())
+            .with_sink(&mut sink)
+            .run()
+            .unwrap();
+        assert_eq!((report.steps, report.reason), (8, StopReason::SteadyState));
+        assert_eq!(last_frame, Some((8, 0)));
+        let mut simulation = Simulation::new(1_u32, |state: &u32| Ok::<u32, MatrixError>(state * 3))
+            .with_stop_condition(StopCondition::predicate(|_, state: &u32| *state > 100));
+        assert_eq!(simulation.run().unwrap().reason, StopReason::Predicate);
+        assert_eq!(*simulation.get_state(), 243);
+    }
+}
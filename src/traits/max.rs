@@ -20,4 +20,8 @@ impl Max for f64 {
 
 impl Max for i32 {
     const MAX: i32 = i32::MAX;
-}
\ No newline at end of file
+}
+
+impl Max for i8 {
+    const MAX: i8 = i8::MAX;
+}
@@ -1,4 +1,5 @@
 use core::{
+    cmp::Ordering,
     fmt::Debug,
     ops::{
         Div,
@@ -26,6 +27,9 @@ pub use lattice_element::*;
 pub mod max;
 pub use max::*;
 
+pub mod tone_map;
+pub use tone_map::FromChannel;
+
 pub mod model;
 pub use model::Model;
 pub use model::TryModel;
@@ -41,13 +45,18 @@ pub trait Matrix<T>
     /// Checks for bounds within the size of the matrix
     fn check_point_bounds(&self, point: (u32, u32)) -> Result<bool, error::MatrixError> {
         if point.0 >= self.get_width() as u32 || point.1 >= self.get_height() as u32 { 
-            Err(error::MatrixError::Overflow)
+            Err(error::MatrixError::OutOfBounds {
+                point,
+                width: self.get_width(),
+                height: self.get_height(),
+            })
         } else {
             Ok(true)
         }
     }
     fn get_point_value<U: Into<u32>>(&self, point: (U,U)) -> Result<T, error::MatrixError>;
     fn get_absolute_point_data(&self, absolute_point: usize) -> T;
+    #[allow(clippy::wrong_self_convention)]
     fn into_2d_point(&self, absolute_point: usize) -> Result<(u32, u32), error::MatrixError> {
         let x: u32 = (absolute_point % self.get_width()) as u32;
         let y: u32 = absolute_point as u32 / self.get_width() as u32; 
@@ -56,6 +65,7 @@ pub trait Matrix<T>
     }
     /// Transforms a 2D point reference point into a 1D point correlated with the 
     /// matrix raw data and its width/height.
+    #[allow(clippy::wrong_self_convention)]
     fn into_absolute_point(&self, point: (u32, u32)) -> Result<usize, error::MatrixError> {
         self.check_point_bounds(point)?;
         Ok( (point.0 + point.1 * (self.get_width() as u32)) as usize )
@@ -101,15 +111,14 @@ pub trait DrawMultiChannel<T>: Matrix<T>
  u8: From<T> 
 {
     fn draw_multi_channel(&self, channels: &[MatrixImage<T>; 4], channel_order:Option<&[Channel; 4]>) -> Result<RgbaImage, error::MatrixError> {
-        let mut length_holder = 0_usize;
-        let have_same_length = match channels.as_slice() {
-            [head, tail @ ..] => tail.iter().all(|matrix| {
-                length_holder = head.get_data().len();  // holds the last length value
-                head.get_data().len() == matrix.get_data().len()
-            }),
-            [] => false,
-        };
-        assert!(have_same_length, "Matrices should have the same length.");
+        let expected = (self.get_width(), self.get_height());
+        for matrix in channels {
+            let found = (matrix.get_width(), matrix.get_height());
+            if found != expected {
+                return Err(error::MatrixError::DimensionMismatch { expected, found });
+            }
+        }
+        let length_holder = expected.0 * expected.1;
         let matrix_order: Vec<MatrixImage<T>> = if let Some(channel_order) = channel_order {
             let mut order = channel_order
                 .iter()
@@ -145,6 +154,31 @@ pub trait DrawMultiChannel<T>: Matrix<T>
     }
 }
 
+/// Optimal peer point and its evaluation, if any.
+pub type OptimalPeer<T> = Result<Option<((u32, u32), T)>, error::MatrixError>;
+
+/// Iterator extension used by the `Optimal` implementations, returns the last element with the
+/// greatest key, as `Iterator::max_by` does, or `MatrixError::NonComparable` when two keys can not
+/// be ordered (e.g. NaN values).
+pub(crate) trait TryMaxBy: Iterator + Sized {
+    fn try_max_by<K: PartialOrd>(self, key: impl Fn(&Self::Item) -> &K) -> Result<Option<Self::Item>, error::MatrixError> {
+        let mut optimal: Option<Self::Item> = None;
+        for item in self {
+            optimal = match optimal {
+                None => Some(item),
+                Some(current) => match key(&item).partial_cmp(key(&current)) {
+                    Some(Ordering::Less) => Some(current),
+                    Some(_) => Some(item),
+                    None => return Err(error::MatrixError::NonComparable),
+                },
+            };
+        }
+        Ok(optimal)
+    }
+}
+
+impl<I: Iterator> TryMaxBy for I {}
+
 pub trait Optimal<T>
 where
  T: PartialOrd,
@@ -152,20 +186,21 @@ where
     /// Receives a point, neighborhood size and Neighborhood type, together with an objective function.
     /// Evaluates all pair of points from the reference to the neighborhood, and returns the point and evaluation T that maximizes
     /// The objective function.
+    /// Returns `MatrixError::NonComparable` when two evaluations can not be ordered.
     fn optimal_peer(
         &self, 
         self_point: (u32, u32), 
         hood_size: usize, 
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32)) -> T 
-    ) -> Option<((u32, u32), T)>;
+    ) -> OptimalPeer<T>;
     fn optimal_peer_internal_values<V>(
         &self, 
         self_point: (u32, u32), 
         hood_size: usize, 
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32)) -> (T,V) ,
-    ) -> Option<((u32, u32), (T,V))>;
+    ) -> OptimalPeer<(T,V)>;
     fn optimal_peer_with_coefficients<U: Copy>(
         &self, 
         self_point: (u32, u32), 
@@ -173,7 +208,7 @@ where
         hood_type: Neighborhood, 
         objective: impl Fn(&Self, (u32, u32), (u32, u32), U) -> T,
         c: U,
-    ) -> Option<((u32, u32), T)>;
+    ) -> OptimalPeer<T>;
     fn optimal_peer_internal_values_with_coefficients<U, V, F>(
         &self, 
         self_point: (u32, u32), 
@@ -181,7 +216,7 @@ where
         hood_type: Neighborhood, 
         objective: F,
         c: &mut U,
-    ) -> Option<((u32, u32), (T, V))>
+    ) -> OptimalPeer<(T, V)>
     where 
         F: for<'a> Fn(&'a Self, (u32, u32), (u32, u32), &'a mut U) -> (T, V),;
     fn optimal_peer_internal_values_with_coefficients_and_hood<U, V, F>(
@@ -190,7 +225,7 @@ where
         hood: Vec<(u32, u32)>, 
         objective: F,
         c: &mut U,
-    ) -> Option<((u32, u32), (T, V))>
+    ) -> OptimalPeer<(T, V)>
     where 
         F: for<'a> Fn(&'a Self, (u32, u32), (u32, u32), &'a mut U) -> (T, V),;
 }
@@ -0,0 +1,69 @@
+//!   Inverse of the tone mapping applied by the `u8::from(T)` conversions used for drawing,
+//!   so that a lattice drawn to an image and imported back keeps its values up to the
+//!   precision of a single channel.
+
+use super::LatticeElement;
+
+/// Fraction of the full range at the center of the bucket that `value` was truncated from,
+/// clamped to the full range for the last bucket.
+fn bucket_center(value: u8) -> f64 {
+    ((value as f64 + 0.5) / u8::MAX as f64).min(1.0)
+}
+
+pub trait FromChannel {
+    fn from_channel(value: u8) -> Self;
+}
+
+impl FromChannel for u8 {
+    fn from_channel(value: u8) -> Self {
+        value
+    }
+}
+
+impl FromChannel for LatticeElement<u8> {
+    fn from_channel(value: u8) -> Self {
+        LatticeElement(value)
+    }
+}
+
+impl FromChannel for LatticeElement<u32> {
+    fn from_channel(value: u8) -> Self {
+        LatticeElement((bucket_center(value) * u32::MAX as f64) as u32)
+    }
+}
+
+impl FromChannel for LatticeElement<i32> {
+    fn from_channel(value: u8) -> Self {
+        LatticeElement((bucket_center(value) * i32::MAX as f64) as i32)
+    }
+}
+
+impl FromChannel for LatticeElement<f32> {
+    fn from_channel(value: u8) -> Self {
+        LatticeElement((bucket_center(value) * f32::MAX as f64) as f32)
+    }
+}
+
+impl FromChannel for LatticeElement<f64> {
+    fn from_channel(value: u8) -> Self {
+        LatticeElement(bucket_center(value) * f64::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::traits::{
+        FromChannel,
+        LatticeElement,
+    };
+
+    #[test]
+    fn channel_round_trip() {
+        for value in 0..=u8::MAX {
+            assert_eq!(u8::from(LatticeElement::<u32>::from_channel(value)), value);
+            assert_eq!(u8::from(LatticeElement::<i32>::from_channel(value)), value);
+            assert_eq!(u8::from(LatticeElement::<f32>::from_channel(value)), value);
+            assert_eq!(u8::from(LatticeElement::<f64>::from_channel(value)), value);
+        }
+    }
+}